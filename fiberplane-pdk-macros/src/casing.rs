@@ -5,20 +5,28 @@ use std::convert::TryFrom;
 pub enum Casing {
     #[default]
     Original,
+    Lowercase,
+    Uppercase,
     CamelCase,
     PascalCase,
     SnakeCase,
     ScreamingSnakeCase,
+    KebabCase,
+    ScreamingKebabCase,
 }
 
 impl Casing {
     pub fn format_string(&self, string: &str) -> String {
         match self {
             Self::Original => string.to_owned(),
+            Self::Lowercase => string.to_lowercase(),
+            Self::Uppercase => string.to_uppercase(),
             Self::CamelCase => string.to_camel_case(),
             Self::PascalCase => string.to_pascal_case(),
             Self::SnakeCase => string.to_snake_case(),
             Self::ScreamingSnakeCase => string.to_screaming_snake_case(),
+            Self::KebabCase => string.to_kebab_case(),
+            Self::ScreamingKebabCase => string.to_kebab_case().to_uppercase(),
         }
     }
 }
@@ -28,10 +36,14 @@ impl TryFrom<&str> for Casing {
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
         match value {
+            "lowercase" => Ok(Self::Lowercase),
+            "UPPERCASE" => Ok(Self::Uppercase),
             "camelCase" => Ok(Self::CamelCase),
             "PascalCase" => Ok(Self::PascalCase),
             "snake_case" => Ok(Self::SnakeCase),
             "SCREAMING_SNAKE_CASE" => Ok(Self::ScreamingSnakeCase),
+            "kebab-case" => Ok(Self::KebabCase),
+            "SCREAMING-KEBAB-CASE" => Ok(Self::ScreamingKebabCase),
             other => Err(format!("Unrecognized case format: {}", other)),
         }
     }
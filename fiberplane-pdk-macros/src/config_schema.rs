@@ -1,29 +1,173 @@
-use crate::schema_generator::generate_schema;
+use crate::field_attrs::FieldAttrs;
+use crate::schema_enum::{generate_enum_schema, generate_enum_validation};
+use crate::schema_field::{serde_field_name, SerdeAttrs};
+use crate::schema_generator::{generate_schema, get_flattened_type, get_ident};
+use crate::validation::generate_validation;
 use proc_macro::TokenStream;
+use proc_macro_error::abort;
 use quote::quote;
-use syn::{parse_macro_input, ItemStruct};
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Field, Fields};
 
 pub fn derive_config_schema(input: TokenStream) -> TokenStream {
-    let schema: proc_macro2::TokenStream = generate_schema("ConfigField", input.clone()).into();
-    let schema_struct = parse_macro_input!(input as ItemStruct);
-    let ident = schema_struct.ident;
+    let input = parse_macro_input!(input as DeriveInput);
+    let ident = &input.ident;
+    let attrs = &input.attrs;
+
+    let (schema, optional_schema, validation, secret_fields, log_level) = match &input.data {
+        Data::Struct(data) => (
+            generate_schema("ConfigField", &data.fields, attrs, false),
+            generate_schema("ConfigField", &data.fields, attrs, true),
+            generate_validation(&data.fields, attrs, |ident| quote! { &self.#ident }),
+            generate_secret_fields(&data.fields, attrs),
+            generate_log_level(&data.fields),
+        ),
+        Data::Enum(data) => (
+            generate_enum_schema("ConfigField", ident, data, attrs, false),
+            generate_enum_schema("ConfigField", ident, data, attrs, true),
+            generate_enum_validation(data),
+            data.variants
+                .iter()
+                .flat_map(|variant| match &variant.fields {
+                    Fields::Named(fields) => generate_secret_fields(&fields.named, &variant.attrs),
+                    _ => Vec::new(),
+                })
+                .collect(),
+            quote! {},
+        ),
+        Data::Union(_) => abort!(ident, "unions are not supported"),
+    };
+
+    // Types that are flattened into another config don't export the schema
+    // themselves.
+    let export = if FieldAttrs::from_attrs(attrs).nested {
+        quote! {}
+    } else {
+        quote! {
+            #[pdk_export]
+            fn get_config_schema() -> fiberplane_pdk::providers::ConfigSchema {
+                #ident::schema()
+            }
+        }
+    };
 
     let output = quote! {
         #[automatically_derived]
         impl #ident {
             pub fn parse(config: fiberplane_pdk::providers::ProviderConfig)
                     -> fiberplane_pdk::prelude::Result<Self> {
-                fiberplane_pdk::serde_json::from_value(config)
+                for field_name in Self::secret_fields() {
+                    if let Some(fiberplane_pdk::serde_json::Value::String(value)) =
+                        config.get(field_name)
+                    {
+                        fiberplane_pdk::secret::register_secret(value);
+                    }
+                }
+
+                let config: Self = fiberplane_pdk::serde_json::from_value(config)
                     .map_err(|err| fiberplane_pdk::bindings::Error::Config {
                         message: format!("Error parsing config: {:?}", err),
-                    })
+                    })?;
+                config.validate()?;
+                #log_level
+                Ok(config)
+            }
+
+            /// Validates the fields according to their validation attributes.
+            pub fn validate(&self) -> fiberplane_pdk::prelude::Result<()> {
+                #validation
+            }
+
+            pub fn schema() -> fiberplane_pdk::providers::ConfigSchema {
+                use fiberplane_pdk::providers::*;
+
+                #schema
+            }
+
+            /// Returns the schema with none of the fields marked as required.
+            /// Used when the type is flattened into an `Option`.
+            #[doc(hidden)]
+            pub fn optional_schema() -> fiberplane_pdk::providers::ConfigSchema {
+                use fiberplane_pdk::providers::*;
+
+                #optional_schema
+            }
+
+            /// Returns the names of the fields that contain secrets, including
+            /// those of flattened types.
+            #[doc(hidden)]
+            pub fn secret_fields() -> Vec<&'static str> {
+                let secret_fields: Vec<Vec<&'static str>> = vec![#(#secret_fields),*];
+                secret_fields.into_iter().flatten().collect()
+            }
+        }
+
+        #[automatically_derived]
+        impl fiberplane_pdk::context::FromRequest for #ident {
+            fn from_request(request: &fiberplane_pdk::providers::ProviderRequest)
+                    -> fiberplane_pdk::prelude::Result<Self> {
+                Self::parse(request.config.clone())
             }
         }
 
-        #[pdk_export]
-        fn get_config_schema() -> fiberplane_pdk::providers::ConfigSchema {
-            #schema
+        #[automatically_derived]
+        impl fiberplane_pdk::context::FromConfig for #ident {
+            fn from_config(config: &fiberplane_pdk::providers::ProviderConfig)
+                    -> fiberplane_pdk::prelude::Result<Self> {
+                Self::parse(config.clone())
+            }
         }
+
+        #export
     };
     output.into()
 }
+
+/// Generates the statements that apply the log level from any field of the
+/// `LogLevel` type.
+fn generate_log_level<'a>(fields: impl IntoIterator<Item = &'a Field>) -> proc_macro2::TokenStream {
+    let statements = fields.into_iter().filter_map(|field| {
+        if SerdeAttrs::from_attrs(&field.attrs).flatten {
+            return None;
+        }
+
+        let ident = field.ident.as_ref()?;
+        match get_ident(field) {
+            (type_ident, true, false) if type_ident == "LogLevel" => Some(quote! {
+                fiberplane_pdk::logging::set_log_level(config.#ident);
+            }),
+            (type_ident, false, false) if type_ident == "LogLevel" => Some(quote! {
+                if let Some(level) = config.#ident {
+                    fiberplane_pdk::logging::set_log_level(level);
+                }
+            }),
+            _ => None,
+        }
+    });
+
+    quote! { #(#statements)* }
+}
+
+/// Generates an expression for every field that contains secrets, each of
+/// which evaluates to a list of field names.
+fn generate_secret_fields<'a>(
+    fields: impl IntoIterator<Item = &'a Field>,
+    struct_attrs: &[Attribute],
+) -> Vec<proc_macro2::TokenStream> {
+    fields
+        .into_iter()
+        .filter_map(|field| {
+            if SerdeAttrs::from_attrs(&field.attrs).flatten {
+                let (flattened_type, _) = get_flattened_type(field);
+                return Some(quote! { <#flattened_type>::secret_fields() });
+            }
+
+            if !FieldAttrs::from_attrs(&field.attrs).secret {
+                return None;
+            }
+
+            let name = field.ident.as_ref()?.to_string();
+            let name = serde_field_name(&name, &field.attrs, struct_attrs);
+            Some(quote! { vec![#name] })
+        })
+        .collect()
+}
@@ -2,7 +2,8 @@ use proc_macro_error::abort;
 use quote::ToTokens;
 use syn::ext::IdentExt;
 use syn::parse::{Parse, ParseStream};
-use syn::{parenthesized, Attribute, Error, Ident, LitInt, LitStr, Result, Token};
+use syn::punctuated::Punctuated;
+use syn::{bracketed, parenthesized, Attribute, Error, Ident, Lit, LitInt, LitStr, Result, Token};
 
 #[derive(Clone, Debug, Default)]
 pub struct FieldAttrs {
@@ -11,6 +12,16 @@ pub struct FieldAttrs {
     /// Only supported on checkbox fields.
     pub checked: bool,
 
+    /// Default value of the field, which is used as its initial value in the
+    /// schema. Fields with a default value are not required.
+    ///
+    /// Booleans are supported on checkbox fields, integers on integer fields
+    /// and strings on text and select fields. Numbers may also be used on
+    /// text fields, such as those of floating-point types. Note that Serde
+    /// needs to know about the default too, so fields that are not optional
+    /// also need a `#[serde(default)]` annotation.
+    pub default: Option<DefaultValue>,
+
     /// Suggested label to display along the field.
     ///
     /// Supported on any field type for which a `label` can be specified in the
@@ -19,19 +30,40 @@ pub struct FieldAttrs {
 
     /// Optional maximum value to be entered.
     ///
-    /// Only supported on integer fields.
-    pub max: Option<i32>,
+    /// Supported on integer, floating-point and duration fields. Values are
+    /// validated when parsing, with durations being compared in seconds.
+    /// Bounds on integer fields must be whole numbers.
+    pub max: Option<f64>,
 
     /// Optional minimum value to be entered.
     ///
-    /// Only supported on integer fields.
-    pub min: Option<i32>,
+    /// Supported on integer, floating-point and duration fields. Values are
+    /// validated when parsing, with durations being compared in seconds.
+    /// Bounds on integer fields must be whole numbers.
+    pub min: Option<f64>,
 
     /// Whether multi-line input is useful for this field.
     ///
     /// Only supported on text fields.
     pub multiline: bool,
 
+    /// Indicates that a config struct or enum is only used as a flattened part
+    /// of another config, so it shouldn't implement `get_config_schema()`.
+    ///
+    /// Only supported on types deriving `ConfigSchema`.
+    pub nested: bool,
+
+    /// Whether the value may not be empty or consist of whitespace only.
+    ///
+    /// Validated when parsing.
+    pub non_empty: bool,
+
+    /// List of values the field is restricted to, specified as
+    /// `one_of = ["...", "..."]`.
+    ///
+    /// Validated when parsing.
+    pub one_of: Vec<String>,
+
     /// List of options from which to choose.
     ///
     /// Only supported on select fields. Multiple options are specified using
@@ -52,12 +84,34 @@ pub struct FieldAttrs {
     /// `prerequisite = "..."` annotations.
     pub prerequisites: Vec<String>,
 
+    /// Regular expression that the entire value must match.
+    ///
+    /// Validated when parsing.
+    pub regex: Option<String>,
+
+    /// Indicates that the field contains a secret, such as a password or a
+    /// token.
+    ///
+    /// Values of secret fields are redacted from log messages. Fields of type
+    /// `Secret` are always treated as secret, and also hide their value from
+    /// their `Debug` output. Only supported on text fields.
+    ///
+    /// Secret fields are not marked as password inputs in the schema yet,
+    /// since `TextField` has no flag for that. This is left for a follow-up
+    /// that adds one to `fiberplane-models`.
+    pub secret: bool,
+
     /// Indicates that this is a select field. Both text fields and select
     /// fields accept values of type `String`, so the `select` attribute is
     /// distinguish between them.
     ///
     /// Note that select fields that accept multiple values use `Vec<String>`
     /// as a type. These still require the use of the `select` attribute.
+    ///
+    /// Fields with an enum type implementing `SelectOptions` also require the
+    /// `select` attribute, in which case the options are derived from the
+    /// enum's variants. Without it, fields of types the macros don't know are
+    /// rejected, and lists of them are array fields.
     pub select: bool,
 
     /// Specifies the granularity that any specified numbers must adhere to.
@@ -70,6 +124,11 @@ pub struct FieldAttrs {
     /// Only supported on select fields and text fields.
     pub supports_suggestions: bool,
 
+    /// Whether the value must be a valid URL.
+    ///
+    /// Validated when parsing.
+    pub url: bool,
+
     /// Value of the field as it will be included in the encoded query.
     ///
     /// Only supported on checkbox fields. If omitted, a default value of "true"
@@ -77,6 +136,14 @@ pub struct FieldAttrs {
     pub value: Option<String>,
 }
 
+/// A literal value given using the `default` attribute.
+#[derive(Clone, Debug)]
+pub enum DefaultValue {
+    Bool(bool),
+    Integer(i32),
+    Text(String),
+}
+
 impl FieldAttrs {
     pub fn from_attrs(attrs: &[Attribute]) -> Self {
         attrs
@@ -107,14 +174,51 @@ impl Parse for FieldAttrs {
                 })
         };
 
+        let parse_f64 = || -> Result<f64> {
+            content.parse::<Token![=]>()?;
+            match content.parse::<Lit>()? {
+                Lit::Int(value) => value.base10_parse(),
+                Lit::Float(value) => value.base10_parse(),
+                other => Err(Error::new(other.span(), "Expected a number")),
+            }
+        };
+
         let parse_string = || -> Result<String> {
             content.parse::<Token![=]>()?;
-            Ok(content
-                .parse::<LitStr>()?
-                .to_token_stream()
-                .to_string()
-                .trim_matches('"')
-                .to_owned())
+            Ok(content.parse::<LitStr>()?.value())
+        };
+
+        let parse_default = || -> Result<DefaultValue> {
+            content.parse::<Token![=]>()?;
+            match content.parse::<Lit>()? {
+                Lit::Bool(value) => Ok(DefaultValue::Bool(value.value)),
+                Lit::Int(value) => Ok(DefaultValue::Integer(value.base10_parse()?)),
+                Lit::Float(value) => Ok(DefaultValue::Text(value.base10_digits().to_owned())),
+                Lit::Str(value) => Ok(DefaultValue::Text(value.value())),
+                other => Err(Error::new(
+                    other.span(),
+                    "Expected a boolean, number or string as default value",
+                )),
+            }
+        };
+
+        let parse_string_list = || -> Result<Vec<String>> {
+            content.parse::<Token![=]>()?;
+            let list;
+            bracketed!(list in content);
+            Ok(Punctuated::<LitStr, Token![,]>::parse_terminated(&list)?
+                .iter()
+                .map(LitStr::value)
+                .collect())
+        };
+
+        let parse_regex = || -> Result<String> {
+            content.parse::<Token![=]>()?;
+            let pattern = content.parse::<LitStr>()?;
+            regex::Regex::new(&pattern.value()).map_err(|err| {
+                Error::new(pattern.span(), format!("Invalid regular expression: {err}"))
+            })?;
+            Ok(pattern.value())
         };
 
         let mut result = Self::default();
@@ -122,16 +226,23 @@ impl Parse for FieldAttrs {
             let key: Ident = content.call(IdentExt::parse_any)?;
             match key.to_string().as_ref() {
                 "checked" | "checked_by_default" => result.checked = true,
+                "default" => result.default = Some(parse_default()?),
                 "label" => result.label = Some(parse_string()?),
-                "max" => result.max = Some(parse_i32()?),
-                "min" => result.min = Some(parse_i32()?),
+                "max" => result.max = Some(parse_f64()?),
+                "min" => result.min = Some(parse_f64()?),
                 "multiline" => result.multiline = true,
+                "nested" => result.nested = true,
+                "non_empty" => result.non_empty = true,
+                "one_of" => result.one_of = parse_string_list()?,
                 "option" => result.options.push(parse_string()?),
                 "prerequisite" => result.prerequisites.push(parse_string()?),
                 "placeholder" => result.placeholder = Some(parse_string()?),
+                "regex" => result.regex = Some(parse_regex()?),
+                "secret" => result.secret = true,
                 "select" => result.select = true,
                 "step" => result.step = Some(parse_i32()?),
                 "supports_suggestions" => result.supports_suggestions = true,
+                "url" => result.url = true,
                 "value" => result.value = Some(parse_string()?),
                 other => {
                     return Err(Error::new(
@@ -12,8 +12,11 @@ mod ident_or_string;
 mod provider_data;
 mod query_schema;
 mod query_types;
+mod schema_enum;
 mod schema_field;
 mod schema_generator;
+mod select_options;
+mod validation;
 
 use proc_macro::TokenStream;
 use proc_macro_error::proc_macro_error;
@@ -25,26 +28,62 @@ use quote::quote;
 /// defined:
 ///
 /// * **handler** - This is the function that will be called for handling
-///   requests of the given type. Handlers may have one or two arguments, the
-///   types of which are given between parentheses. The first argument is either
-///   `ProviderRequest`, in which case the entire request is passed through
-///   without processing, or the type of the query data. The second argument,
-///   which is optional, specifies a type for the provider's config. Add a
-///   `.await` call to the handler if the handler is asynchronous. Providing a
-///   handler is mandatory.
+///   requests of the given type. The types of its arguments are given between
+///   parentheses, and the arguments are resolved by their type, so they may be
+///   given in any order: `ProviderRequest` receives the entire request without
+///   processing, `String` receives the query type and any other type is
+///   extracted from the request through the `FromRequest` trait. This trait is
+///   implemented by types deriving `QuerySchema`, which are parsed from the
+///   query data, by types deriving `ConfigSchema`, which are parsed from the
+///   config, and by the PDK's `QueryContext`. The schema of the query type is
+///   taken from the argument that represents the query data. Add a `.await`
+///   call to the handler if the handler is asynchronous. Handlers return a
+///   `Blob`, unless a return type implementing `ProviderData` is given after
+///   an arrow (`-> Events`), in which case the macro encodes the returned data.
+///   Providing a handler is mandatory.
 /// * **label** - A label that is used when presenting the query type to the
 ///   user. Some query types are not intended to be user-selected (such as the
 ///   `status` query type) in which case they should not define a label.
 /// * **supported_mime_types** - A list of MIME types that is supported by the
 ///   provider for data produced when running queries of the given type. This
 ///   should at least include the MIME type of the `Blob`s returned by
-///   `handler()`. If the provider implements the `extract_data()` function this
-///   may include other MIME types as well, in which case `extract_data()` has
-///   the responsibility of converting from the data format returned by
-///   `handler()` to the requested MIME type.
+///   `handler()`. If the provider implements the `extract_data()` function
+///   this may include other MIME types as well, in which case `extract_data()`
+///   has the responsibility of converting from the data format returned by
+///   `handler()` to the requested MIME type. If the handler declares its
+///   return type, the list may be omitted to use the MIME type of that type.
+///   Otherwise, it is a compile error if the list doesn't include it.
+/// * **cells** - An optional function that creates the cells for the `Blob`s
+///   returned by `handler()`. Its arguments are determined by their types:
+///   `Blob` receives the response, `String` receives the query type and any
+///   other type is parsed from the response using its `ProviderData`
+///   implementation.
+/// * **extract** - An optional function that extracts data from the `Blob`s
+///   returned by `handler()`. It is called for responses of which the MIME
+///   type matches one of the `supported_mime_types`. Its arguments are
+///   determined by their types: `Blob` receives the response, `String`
+///   receives the requested MIME type, `Option<String>` receives the query
+///   and any other type is parsed from the response using its `ProviderData`
+///   implementation.
+/// * **suggestions** - An optional map from field names to the functions that
+///   provide auto-suggestions for those fields. These functions return a
+///   `Vec<Suggestion>`. Their arguments are resolved by their type as well:
+///   `ProviderRequest` receives the entire request, `AutoSuggestRequest`
+///   receives the request for suggestions, `String` receives the query type
+///   for which suggestions are requested and any other type is extracted from
+///   the config through the `FromConfig` trait, which is implemented by types
+///   deriving `ConfigSchema`. The fields are marked as supporting suggestions
+///   in the schema of the query data, so the handler of the query type needs
+///   to take the query data type, and it is a compile error if the schema
+///   doesn't contain a text or select field with the given name.
 ///
 /// This macro generates the `invoke2()` and `get_supported_query_types()`
-/// functions for you.
+/// functions for you. If any of the query types defines `cells` or `extract`
+/// handlers, the `create_cells()` and `extract_data()` functions are generated
+/// as well. These return `Error::UnsupportedRequest` for query types without
+/// such a handler. If any of the query types defines `suggestions`,
+/// `SUGGESTIONS_QUERY_TYPE` is added to the supported query types and its
+/// requests are dispatched to the handler for the requested field.
 ///
 /// # Example
 ///
@@ -54,11 +93,14 @@ use quote::quote;
 /// pdk_query_types! {
 ///     TIMESERIES_QUERY_TYPE => {
 ///         label: "Timeseries query",
-///         handler: query_timeseries(ExampleQueryData, ExampleConfig),
-///         supported_mime_types: [TIMESERIES_MIME_TYPE],
+///         handler: query_timeseries(ExampleQueryData, ExampleConfig) -> TimeseriesVector,
+///         cells: create_graph_cell(),
+///         suggestions: {
+///             "query" => query_suggestions(AutoSuggestRequest, ExampleConfig).await,
+///         },
 ///     },
 ///     STATUS_QUERY_TYPE => {
-///         handler: query_status(ProviderRequest).await,
+///         handler: query_status(ExampleConfig, QueryContext).await,
 ///         supported_mime_types: [STATUS_MIME_TYPE],
 ///     },
 ///     "x-custom-query-type" => {
@@ -68,11 +110,25 @@ use quote::quote;
 ///     }
 /// }
 ///
-/// fn query_timeseries(query_data: ExampleQueryData, config: ExampleConfig) -> Result<Blob> {
+/// fn query_timeseries(
+///     query_data: ExampleQueryData,
+///     config: ExampleConfig,
+/// ) -> Result<TimeseriesVector> {
 ///     todo!("Implement timeseries query handling")
 /// }
 ///
-/// async fn query_status(request: ProviderRequest) -> Result<Blob> {
+/// fn create_graph_cell() -> Result<Vec<Cell>> {
+///     todo!("Create a graph cell that displays the timeseries")
+/// }
+///
+/// async fn query_suggestions(
+///     request: AutoSuggestRequest,
+///     config: ExampleConfig,
+/// ) -> Result<Vec<Suggestion>> {
+///     todo!("Implement suggestions for the query field")
+/// }
+///
+/// async fn query_status(config: ExampleConfig, context: QueryContext) -> Result<Blob> {
 ///     todo!("Implement status checking")
 /// }
 ///
@@ -89,13 +145,27 @@ pub fn pdk_query_types(input: TokenStream) -> TokenStream {
 
 /// Used to automatically generate a config schema for a given struct.
 ///
-/// The macro extends the struct to which it is applied with a static `parse()`
-/// method and it will automatically implement the provider's
+/// The macro extends the struct to which it is applied with static `parse()`
+/// and `schema()` methods and it will automatically implement the provider's
 /// `get_config_schema()` function for you.
 ///
 /// `parse()` takes an untyped `ProviderConfig` object and parses it into an
 /// instance of the struct.
 ///
+/// Fields annotated with `#[serde(flatten)]` are expanded into the fields of
+/// their type, which should derive `ConfigSchema` too. Such nested types use
+/// the `#[pdk(nested)]` attribute, so they don't implement
+/// `get_config_schema()` themselves. If the flattened field is an `Option`,
+/// none of its fields are marked as required. The PDK's `Auth` type can be
+/// used this way to add the common authentication fields.
+///
+/// The macro may also be applied to enums that are either
+/// `#[serde(untagged)]` or internally tagged using `#[serde(tag = "...")]`.
+/// Their schema contains the fields of all variants, none of which are
+/// required. For internally tagged enums, it starts with a select field for
+/// the tag, which the fields of the variants list as their prerequisite. The
+/// label of the tag field may be specified on the enum.
+///
 /// # Example
 ///
 /// ```no_compile
@@ -105,6 +175,23 @@ pub fn pdk_query_types(input: TokenStream) -> TokenStream {
 /// struct MyConfig {
 ///     #[pdk(label = "Specify your endpoint")]
 ///     pub endpoint: String,
+///
+///     #[serde(flatten)]
+///     pub auth: Option<Auth>,
+///
+///     #[serde(flatten)]
+///     pub storage: Storage,
+/// }
+///
+/// #[derive(ConfigSchema, Deserialize)]
+/// #[pdk(nested, label = "Where to store results")]
+/// #[serde(tag = "storage", rename_all = "snake_case")]
+/// enum Storage {
+///     Memory,
+///     Disk {
+///         #[pdk(label = "Directory to store results in")]
+///         path: String,
+///     },
 /// }
 ///
 /// #[pdk_export]
@@ -123,8 +210,19 @@ pub fn derive_config_schema(input: TokenStream) -> TokenStream {
 /// Used to automatically generate conversion methods to convert your data to
 /// and from the `Blob` type.
 ///
-/// The macro extends the struct to which it is applied with `parse()` and
-/// `serialize()` methods.
+/// The macro implements the `ProviderData` trait for the struct or enum to
+/// which it is applied. The type is encoded using its `serde` implementation.
+/// Generic type parameters are required to implement `Serialize` and
+/// `DeserializeOwned` as well.
+///
+/// The following attributes are supported:
+///
+/// * **mime_type** - The MIME type of the data, without the suffix for its
+///   encoding. Required.
+/// * **encoding** - The encoding used by `to_blob()`: `json`, `msgpack` or
+///   `cbor` (a compile error unless the `cbor` feature of the PDK is enabled).
+///   Defaults to `msgpack`.
+///   Blobs in any of these encodings can be parsed regardless.
 ///
 /// # Example
 ///
@@ -132,7 +230,7 @@ pub fn derive_config_schema(input: TokenStream) -> TokenStream {
 /// use fiberplane_pdk::prelude::*;
 ///
 /// #[derive(ProviderData, Serialize, Deserialize)]
-/// #[pdk(mime_type = MY_MIME_TYPE]
+/// #[pdk(mime_type = MY_MIME_TYPE, encoding = json)]
 /// struct MyData {
 ///     // specify your fields here...
 /// }
@@ -194,6 +292,45 @@ pub fn derive_query_schema(input: TokenStream) -> TokenStream {
     query_schema::derive_query_schema(input)
 }
 
+/// Used to automatically implement the `SelectOptions` trait for a fieldless
+/// enum, so it can be used as the type of select fields in query and config
+/// schemas.
+///
+/// The values of the options are derived from the variants, honoring
+/// `#[serde(rename)]` and `#[serde(rename_all)]`. Labels can be specified per
+/// variant and default to the name of the variant in sentence case. Fields of
+/// the enum's type need the `select` attribute to be presented as select
+/// fields.
+///
+/// # Example
+///
+/// ```no_compile
+/// use fiberplane_pdk::prelude::*;
+///
+/// #[derive(Deserialize, SelectOptions)]
+/// #[serde(rename_all = "snake_case")]
+/// enum Status {
+///     #[pdk(label = "In progress")]
+///     Running,
+///     Complete,
+/// }
+///
+/// #[derive(Deserialize, QuerySchema)]
+/// struct MyQueryData {
+///     #[pdk(label = "Only show items with the given status", select)]
+///     pub status: Option<Status>,
+///
+///     #[pdk(label = "Statuses to highlight", select)]
+///     pub highlighted: Vec<Status>,
+/// }
+/// ```
+#[proc_macro_derive(SelectOptions, attributes(pdk, serde))]
+#[proc_macro_error]
+pub fn derive_select_options(input: TokenStream) -> TokenStream {
+    proc_macro_error::set_dummy(input.clone().into());
+    select_options::derive_select_options(input)
+}
+
 /// Exports a provider function to make it available to the provider runtime.
 ///
 /// # Example
@@ -1,30 +1,71 @@
 use proc_macro::TokenStream;
 use proc_macro_error::abort;
-use quote::quote;
+use quote::{quote, quote_spanned};
 use syn::ext::IdentExt;
 use syn::parse::{Parse, ParseStream};
-use syn::{parenthesized, parse_macro_input, Attribute, Error, Ident, ItemStruct, Result, Token};
+use syn::{
+    parenthesized, parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Error, Ident,
+    Result, Token,
+};
 
 use crate::ident_or_string::IdentOrString;
 
 pub fn derive_provider_data(input: TokenStream) -> TokenStream {
-    let schema_struct = parse_macro_input!(input as ItemStruct);
-    let attrs = ProviderDataAttrs::from_attrs(&schema_struct.attrs);
-    let ident = &schema_struct.ident;
+    let input = parse_macro_input!(input as DeriveInput);
+    if let Data::Union(_) = input.data {
+        abort!(input.ident, "unions are not supported");
+    }
+
+    let attrs = ProviderDataAttrs::from_attrs(&input.attrs);
+    let ident = &input.ident;
     let Some(mime_type) = attrs.mime_type else {
-        abort!(schema_struct, "Missing required PDK attribute: mime-type");
+        abort!(input, "Missing required PDK attribute: mime-type");
     };
 
+    // Type parameters need to be serializable for the type itself to be.
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param
+            .bounds
+            .push(parse_quote!(fiberplane_pdk::serde::Serialize));
+        param
+            .bounds
+            .push(parse_quote!(fiberplane_pdk::serde::de::DeserializeOwned));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let encoding = attrs.encoding.map(|encoding| {
+        let value = match encoding.to_string().as_ref() {
+            "json" => quote! { fiberplane_pdk::provider_data::Encoding::Json },
+            "msgpack" => quote! { fiberplane_pdk::provider_data::Encoding::MessagePack },
+            // Only the PDK knows whether its `cbor` feature is enabled.
+            "cbor" => quote_spanned! {encoding.span()=> fiberplane_pdk::__pdk_cbor_encoding!() },
+            other => abort!(
+                encoding,
+                "Unknown encoding: {}. Expected one of: json, msgpack, cbor",
+                other
+            ),
+        };
+        quote! {
+            const ENCODING: fiberplane_pdk::provider_data::Encoding = #value;
+        }
+    });
+
     let output = quote! {
         #[automatically_derived]
-        impl fiberplane_pdk::provider_data::ProviderData for #ident {
+        impl #impl_generics fiberplane_pdk::provider_data::ProviderData
+                for #ident #ty_generics #where_clause {
+            const MIME_TYPE: &'static str = #mime_type;
+            #encoding
+
             fn parse_blob(blob: fiberplane_pdk::bindings::Blob)
                     -> fiberplane_pdk::prelude::Result<Self> {
-                fiberplane_pdk::provider_data::parse_blob(#mime_type, blob)
+                fiberplane_pdk::provider_data::parse_blob(Self::MIME_TYPE, blob)
             }
 
-            fn to_blob(&self) -> fiberplane_pdk::prelude::Result<fiberplane_pdk::bindings::Blob> {
-                fiberplane_pdk::provider_data::to_blob(#mime_type, &self)
+            fn to_blob_with_encoding(&self, encoding: fiberplane_pdk::provider_data::Encoding)
+                    -> fiberplane_pdk::prelude::Result<fiberplane_pdk::bindings::Blob> {
+                fiberplane_pdk::provider_data::to_blob_with_encoding(Self::MIME_TYPE, &self, encoding)
             }
         }
     };
@@ -34,6 +75,7 @@ pub fn derive_provider_data(input: TokenStream) -> TokenStream {
 #[derive(Default)]
 struct ProviderDataAttrs {
     pub mime_type: Option<IdentOrString>,
+    pub encoding: Option<Ident>,
 }
 
 impl ProviderDataAttrs {
@@ -64,6 +106,10 @@ impl Parse for ProviderDataAttrs {
             let key: Ident = content.call(IdentExt::parse_any)?;
             match key.to_string().as_ref() {
                 "mime_type" => result.mime_type = Some(parse_ident_or_string()?),
+                "encoding" => {
+                    content.parse::<Token![=]>()?;
+                    result.encoding = Some(content.call(IdentExt::parse_any)?);
+                }
                 other => {
                     return Err(Error::new(
                         content.span(),
@@ -1,11 +1,18 @@
-use crate::schema_generator::generate_schema;
+use crate::schema_field::{serde_field_name, SerdeAttrs};
+use crate::schema_generator::{generate_schema, get_flattened_type};
+use crate::validation::generate_validation;
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, ItemStruct};
+use syn::{parse_macro_input, Attribute, Fields, ItemStruct};
 
 pub fn derive_query_schema(input: TokenStream) -> TokenStream {
-    let schema: proc_macro2::TokenStream = generate_schema("QueryField", input.clone()).into();
     let schema_struct = parse_macro_input!(input as ItemStruct);
+    let fields = &schema_struct.fields;
+    let attrs = &schema_struct.attrs;
+    let schema = generate_schema("QueryField", fields, attrs, false);
+    let optional_schema = generate_schema("QueryField", fields, attrs, true);
+    let validation = generate_validation(fields, attrs, |ident| quote! { &self.#ident });
+    let field_names = generate_field_names(fields, attrs);
     let ident = schema_struct.ident;
 
     let output = quote! {
@@ -13,7 +20,14 @@ pub fn derive_query_schema(input: TokenStream) -> TokenStream {
         impl #ident {
             pub fn parse(query_data: fiberplane_pdk::bindings::Blob)
                     -> fiberplane_pdk::prelude::Result<Self> {
-                fiberplane_pdk::parse_query(query_data)
+                let query: Self = fiberplane_pdk::parse_query(query_data)?;
+                query.validate()?;
+                Ok(query)
+            }
+
+            /// Validates the fields according to their validation attributes.
+            pub fn validate(&self) -> fiberplane_pdk::prelude::Result<()> {
+                #validation
             }
 
             pub fn schema() -> fiberplane_pdk::providers::QuerySchema {
@@ -21,7 +35,54 @@ pub fn derive_query_schema(input: TokenStream) -> TokenStream {
 
                 #schema
             }
+
+            /// Returns the schema with none of the fields marked as required.
+            /// Used when the struct is flattened into an `Option`.
+            #[doc(hidden)]
+            pub fn optional_schema() -> fiberplane_pdk::providers::QuerySchema {
+                use fiberplane_pdk::providers::*;
+
+                #optional_schema
+            }
+        }
+
+        #[automatically_derived]
+        impl fiberplane_pdk::context::FromRequest for #ident {
+            const QUERY_FIELDS: fiberplane_pdk::context::QueryFieldNames = #field_names;
+
+            fn from_request(request: &fiberplane_pdk::providers::ProviderRequest)
+                    -> fiberplane_pdk::prelude::Result<Self> {
+                Self::parse(request.query_data.clone())
+            }
+
+            fn query_schema() -> Option<fiberplane_pdk::providers::QuerySchema> {
+                Some(Self::schema())
+            }
         }
     };
     output.into()
 }
+
+/// Generates the `QueryFieldNames` for the given fields, referring to those of
+/// flattened types.
+fn generate_field_names(fields: &Fields, struct_attrs: &[Attribute]) -> proc_macro2::TokenStream {
+    let (flattened, fields): (Vec<_>, Vec<_>) = fields
+        .iter()
+        .partition(|field| SerdeAttrs::from_attrs(&field.attrs).flatten);
+
+    let names = fields.into_iter().filter_map(|field| {
+        let name = field.ident.as_ref()?.to_string();
+        Some(serde_field_name(&name, &field.attrs, struct_attrs))
+    });
+    let flattened = flattened.into_iter().map(|field| {
+        let (flattened_type, _) = get_flattened_type(field);
+        quote! { <#flattened_type as fiberplane_pdk::context::FromRequest>::QUERY_FIELDS }
+    });
+
+    quote! {
+        fiberplane_pdk::context::QueryFieldNames {
+            names: &[#(#names),*],
+            flattened: &[#(#flattened),*],
+        }
+    }
+}
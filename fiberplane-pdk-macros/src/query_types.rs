@@ -1,11 +1,13 @@
 use proc_macro::TokenStream;
-use proc_macro2::Ident;
-use proc_macro_error::abort_call_site;
-use quote::quote;
+use proc_macro2::{Ident, Spacing, TokenTree};
+use proc_macro_error::{abort, abort_call_site};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
 use syn::{
-    braced, bracketed, parenthesized, parse_macro_input, parse_quote, token, Expr, Result, Token,
+    braced, bracketed, parenthesized, parse_macro_input, parse_quote, token, Path, Result, Token,
+    Type,
 };
 
 use crate::ident_or_string::IdentOrString;
@@ -21,31 +23,77 @@ pub fn define_query_types(input: TokenStream) -> TokenStream {
         .map(|query_type| {
             let identifier = &query_type.identifier;
 
-            let schema = query_type
-                .handler()
-                .as_ref()
-                .and_then(|handler| handler.arg_types.first())
-                .filter(|&ty| ty != "ProviderRequest")
-                .map(|query_type| {
-                    quote! { .with_schema(#query_type::schema()) }
-                });
+            let suggestion_fields: Vec<_> = query_type
+                .suggestions()
+                .into_iter()
+                .flat_map(|suggestions| suggestions.handlers)
+                .map(|handler| handler.field)
+                .collect();
 
             let label = query_type.label().map(|label| {
                 quote! { .with_label(#label) }
             });
 
-            let mime_types = query_type
-                .mime_types()
-                .map(|supported_mime_types| supported_mime_types.mime_types)
-                .map(|mime_types| {
+            // Handlers that return typed data determine the supported MIME
+            // type, unless they are specified explicitly.
+            let return_type = query_type.handler().and_then(|handler| handler.return_type);
+            let mime_types = match (query_type.mime_types(), return_type) {
+                (Some(supported_mime_types), _) => {
+                    let mime_types = supported_mime_types.mime_types;
                     quote! { .supporting_mime_types(&[#mime_types]) }
-                });
+                }
+                (None, Some(return_type)) => quote! {
+                    .supporting_mime_types(&[
+                        <#return_type as fiberplane_pdk::provider_data::ProviderData>::MIME_TYPE
+                    ])
+                },
+                (None, None) => quote! {},
+            };
 
-            quote! {
+            let supported_query_type = quote! {
                 SupportedQueryType::new(#identifier)
                     #label
-                    #schema
                     #mime_types
+            };
+
+            let Some(query_schema) = query_type
+                .handler()
+                .and_then(|handler| handler.query_schema())
+            else {
+                return supported_query_type;
+            };
+
+            let schema = if suggestion_fields.is_empty() {
+                quote! { schema }
+            } else {
+                quote! {
+                    schema
+                        .into_iter()
+                        .map(|field| match field {
+                            fiberplane_pdk::providers::QueryField::Text(field)
+                                if [#(#suggestion_fields),*].contains(&field.name.as_str()) =>
+                            {
+                                fiberplane_pdk::providers::QueryField::Text(
+                                    field.with_suggestions()
+                                )
+                            }
+                            fiberplane_pdk::providers::QueryField::Select(field)
+                                if [#(#suggestion_fields),*].contains(&field.name.as_str()) =>
+                            {
+                                fiberplane_pdk::providers::QueryField::Select(
+                                    field.with_suggestions()
+                                )
+                            }
+                            field => field,
+                        })
+                        .collect()
+                }
+            };
+            quote! {
+                match #query_schema {
+                    Some(schema) => #supported_query_type.with_schema(#schema),
+                    None => #supported_query_type,
+                }
             }
         })
         .collect();
@@ -56,23 +104,15 @@ pub fn define_query_types(input: TokenStream) -> TokenStream {
             let identifier = &query_type.identifier;
 
             let handler = query_type.handler().map(|handler| {
-                let fn_name = handler.ident;
-                let args = handler.arg_types.iter().enumerate().map(|(pos, ty)| {
-                    if ty == "ProviderRequest" {
-                        quote! { request }
-                    } else {
-                        let parse_arg = match pos {
-                            0 => quote! { request.query_data },
-                            1 => quote! { request.config },
-                            _ => abort_call_site!("handlers may specify at most two arguments"),
-                        };
-                        quote! { #ty::parse(#parse_arg)? }
-                    }
-                });
-                if handler.is_async {
-                    quote! { #fn_name(#(#args),*).await }
-                } else {
-                    quote! { #fn_name(#(#args),*) }
+                let invoke = handler.invoke();
+                match &handler.return_type {
+                    Some(return_type) => quote! {
+                        {
+                            let data: #return_type = #invoke?;
+                            fiberplane_pdk::provider_data::ProviderData::to_blob(&data)
+                        }
+                    },
+                    None => invoke,
                 }
             });
 
@@ -80,13 +120,20 @@ pub fn define_query_types(input: TokenStream) -> TokenStream {
         })
         .collect();
 
+    let mime_type_checks = generate_mime_type_checks(&query_types);
+    let suggestion_field_checks = generate_suggestion_field_checks(&query_types);
+    let (suggestions_query_type, suggestions_handler) = generate_suggestions(&query_types);
+    let create_cells = generate_create_cells(&query_types);
+    let extract_data = generate_extract_data(&query_types);
+
     let output = quote! {
         #[pdk_export]
         async fn get_supported_query_types(_config: fiberplane_pdk::providers::ProviderConfig)
             -> Vec<fiberplane_pdk::providers::SupportedQueryType> {
             fiberplane_pdk::prelude::init_panic_hook();
             vec![
-                #(#supported_query_types),*
+                #(#supported_query_types,)*
+                #suggestions_query_type
             ]
         }
 
@@ -94,15 +141,289 @@ pub fn define_query_types(input: TokenStream) -> TokenStream {
         async fn invoke2(request: fiberplane_pdk::providers::ProviderRequest)
             -> fiberplane_pdk::prelude::Result<fiberplane_pdk::bindings::Blob> {
             fiberplane_pdk::prelude::init_panic_hook();
+            fiberplane_pdk::logging::init_logging();
             match request.query_type.as_str() {
                 #(#handlers),*,
+                #suggestions_handler
                 _ => Err(Error::UnsupportedRequest)
             }
         }
+
+        #(#mime_type_checks)*
+
+        #(#suggestion_field_checks)*
+
+        #create_cells
+
+        #extract_data
     };
     output.into()
 }
 
+/// Generates compile-time assertions that the MIME type of the data returned
+/// by handlers is one of the supported MIME types of their query type.
+fn generate_mime_type_checks(
+    query_types: &Punctuated<QueryType, Token![,]>,
+) -> Vec<proc_macro2::TokenStream> {
+    query_types
+        .iter()
+        .filter_map(|query_type| {
+            let return_type = query_type.handler()?.return_type?;
+            let mime_types = query_type.mime_types()?.mime_types;
+            Some(quote_spanned! {return_type.span()=>
+                const _: () = assert!(
+                    fiberplane_pdk::provider_data::contains_mime_type(
+                        &[#mime_types],
+                        <#return_type as fiberplane_pdk::provider_data::ProviderData>::MIME_TYPE,
+                    ),
+                    "the MIME type of the data returned by the handler is not one of the \
+                    supported MIME types of the query type",
+                );
+            })
+        })
+        .collect()
+}
+
+/// Generates compile-time assertions that the fields for which suggestions are
+/// provided are part of the schema of the query data.
+fn generate_suggestion_field_checks(
+    query_types: &Punctuated<QueryType, Token![,]>,
+) -> Vec<proc_macro2::TokenStream> {
+    query_types
+        .iter()
+        .flat_map(|query_type| {
+            let Some(suggestions) = query_type.suggestions() else {
+                return Vec::new();
+            };
+
+            let query_data_types: Vec<_> = query_type
+                .handler()
+                .map(|handler| handler.query_data_types().cloned().collect())
+                .unwrap_or_default();
+            if query_data_types.is_empty() {
+                abort!(
+                    query_type.identifier,
+                    "query types with `suggestions` must have a handler that takes the query data"
+                );
+            }
+
+            suggestions
+                .handlers
+                .into_iter()
+                .map(|suggestion| {
+                    let field = suggestion.field;
+                    quote_spanned! {field.span()=>
+                        const _: () = assert!(
+                            false #(|| <#query_data_types as fiberplane_pdk::context::FromRequest>
+                                ::QUERY_FIELDS.contains(#field))*,
+                            "suggestions are provided for a field that is not part of the schema \
+                            of the query data",
+                        );
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Generates the supported query type and the `invoke2()` match arm for
+/// `SUGGESTIONS_QUERY_TYPE` if any of the query types defines `suggestions`.
+///
+/// Suggestion handlers are selected based on the query type and field of the
+/// `AutoSuggestRequest`, and return the suggestions for that field.
+fn generate_suggestions(
+    query_types: &Punctuated<QueryType, Token![,]>,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+    let handlers: Vec<_> = query_types
+        .iter()
+        .flat_map(|query_type| {
+            let identifier = &query_type.identifier;
+            let suggestions = query_type.suggestions()?;
+            let handlers = suggestions.handlers.into_iter().map(move |suggestion| {
+                let field = suggestion.field;
+                suggestion.handler.disallow_return_type("suggestions");
+                let invoke = suggestion.handler.suggest();
+                quote! { (#identifier, #field) => #invoke? }
+            });
+            Some(handlers.collect::<Vec<_>>())
+        })
+        .flatten()
+        .collect();
+    if handlers.is_empty() {
+        return (quote! {}, quote! {});
+    }
+
+    if let Some(query_type) = query_types.iter().find(|query_type| {
+        matches!(&query_type.identifier, IdentOrString::Ident(ident) if ident == "SUGGESTIONS_QUERY_TYPE")
+    }) {
+        abort!(
+            query_type.identifier,
+            "`SUGGESTIONS_QUERY_TYPE` is handled automatically when query types declare \
+            `suggestions`"
+        );
+    }
+
+    let query_type = quote! {
+        SupportedQueryType::new(fiberplane_pdk::providers::SUGGESTIONS_QUERY_TYPE)
+            .supporting_mime_types(&[fiberplane_pdk::providers::SUGGESTIONS_MIME_TYPE])
+    };
+    let handler = quote! {
+        fiberplane_pdk::providers::SUGGESTIONS_QUERY_TYPE => {
+            let suggest_request =
+                fiberplane_pdk::providers::AutoSuggestRequest::parse(request.query_data.clone())?;
+            let suggestions = match (
+                suggest_request.query_type.as_str(),
+                suggest_request.field.as_str(),
+            ) {
+                #(#handlers,)*
+                _ => return Err(Error::UnsupportedRequest),
+            };
+            fiberplane_pdk::provider_data::ProviderData::to_blob(
+                &fiberplane_pdk::prelude::Suggestions(suggestions),
+            )
+        }
+    };
+    (query_type, handler)
+}
+
+/// Generates the `create_cells()` function if any of the query types defines
+/// a `cells` handler.
+fn generate_create_cells(
+    query_types: &Punctuated<QueryType, Token![,]>,
+) -> proc_macro2::TokenStream {
+    let mut uses_response = false;
+    let handlers: Vec<_> = query_types
+        .iter()
+        .filter_map(|query_type| {
+            let identifier = &query_type.identifier;
+            let handler = query_type.cells()?;
+            let fn_name = handler.sync_path("cells");
+            let args: Vec<_> = handler.arg_types.iter().map(|ty| match type_name(ty).as_str() {
+                "String" => quote! { query_type },
+                "Blob" => {
+                    uses_response = true;
+                    quote! { response }
+                }
+                _ => {
+                    uses_response = true;
+                    quote! {
+                        <#ty as fiberplane_pdk::provider_data::ProviderData>::parse_blob(response)?
+                    }
+                }
+            }).collect();
+
+            Some(quote! { #identifier => #fn_name(#(#args),*) })
+        })
+        .collect();
+    if handlers.is_empty() {
+        return quote! {};
+    }
+
+    let response = if uses_response {
+        quote! { response }
+    } else {
+        quote! { _response }
+    };
+
+    quote! {
+        #[pdk_export]
+        fn create_cells(query_type: String, #response: fiberplane_pdk::bindings::Blob)
+            -> fiberplane_pdk::prelude::Result<Vec<fiberplane_pdk::prelude::Cell>> {
+            match query_type.as_str() {
+                #(#handlers),*,
+                _ => Err(fiberplane_pdk::prelude::Error::UnsupportedRequest)
+            }
+        }
+    }
+}
+
+/// Generates the `extract_data()` function if any of the query types defines
+/// an `extract` handler.
+///
+/// Because `extract_data()` doesn't receive the query type, the handler is
+/// selected based on the MIME type of the response, which should match one of
+/// the query type's supported MIME types.
+fn generate_extract_data(
+    query_types: &Punctuated<QueryType, Token![,]>,
+) -> proc_macro2::TokenStream {
+    let (mut uses_mime_type, mut uses_query) = (false, false);
+    let handlers: Vec<_> = query_types
+        .iter()
+        .filter_map(|query_type| {
+            let handler = query_type.extract()?;
+            let Some(supported_mime_types) = query_type.mime_types() else {
+                abort!(
+                    handler.path,
+                    "query types with an `extract` handler must specify their supported MIME types"
+                );
+            };
+            let mime_types = supported_mime_types.mime_types;
+
+            let fn_name = handler.sync_path("extract");
+            let args: Vec<_> = handler
+                .arg_types
+                .iter()
+                .map(|ty| match type_name(ty).as_str() {
+                    "Blob" => quote! { response },
+                    "String" => {
+                        uses_mime_type = true;
+                        quote! { mime_type }
+                    }
+                    "Option<String>" => {
+                        uses_query = true;
+                        quote! { query }
+                    }
+                    _ => quote! {
+                        <#ty as fiberplane_pdk::provider_data::ProviderData>::parse_blob(response)?
+                    },
+                })
+                .collect();
+
+            Some(quote! {
+                if [#mime_types]
+                    .iter()
+                    .any(|supported_mime_type| response.mime_type.starts_with(supported_mime_type))
+                {
+                    return #fn_name(#(#args),*);
+                }
+            })
+        })
+        .collect();
+    if handlers.is_empty() {
+        return quote! {};
+    }
+
+    let mime_type = if uses_mime_type {
+        quote! { mime_type }
+    } else {
+        quote! { _mime_type }
+    };
+    let query = if uses_query {
+        quote! { query }
+    } else {
+        quote! { _query }
+    };
+
+    quote! {
+        #[pdk_export]
+        fn extract_data(
+            response: fiberplane_pdk::bindings::Blob,
+            #mime_type: String,
+            #query: Option<String>,
+        ) -> fiberplane_pdk::prelude::Result<fiberplane_pdk::bindings::Blob> {
+            #(#handlers)*
+
+            Err(fiberplane_pdk::prelude::Error::UnsupportedRequest)
+        }
+    }
+}
+
+/// Returns the name of a type without any whitespace, so that it can be
+/// compared against the types that have special meaning in handlers.
+fn type_name(ty: &Type) -> String {
+    ty.to_token_stream().to_string().replace(' ', "")
+}
+
 struct QueryTypes {
     query_types: Punctuated<QueryType, Token![,]>,
 }
@@ -133,6 +454,26 @@ impl QueryType {
             })
     }
 
+    fn cells(&self) -> Option<QueryHandler> {
+        self.fields
+            .iter()
+            .find(|field| field.name == "cells")
+            .map(|field| {
+                let value = &field.value;
+                parse_quote! { #value }
+            })
+    }
+
+    fn extract(&self) -> Option<QueryHandler> {
+        self.fields
+            .iter()
+            .find(|field| field.name == "extract")
+            .map(|field| {
+                let value = &field.value;
+                parse_quote! { #value }
+            })
+    }
+
     fn label(&self) -> Option<IdentOrString> {
         self.fields
             .iter()
@@ -143,6 +484,16 @@ impl QueryType {
             })
     }
 
+    fn suggestions(&self) -> Option<SuggestionHandlers> {
+        self.fields
+            .iter()
+            .find(|field| field.name == "suggestions")
+            .map(|field| {
+                let value = &field.value;
+                parse_quote! { #value }
+            })
+    }
+
     fn mime_types(&self) -> Option<SupportedMimeTypes> {
         self.fields
             .iter()
@@ -169,33 +520,181 @@ impl Parse for QueryType {
 struct QueryTypeField {
     name: Ident,
     _colon: token::Colon,
-    value: Expr,
+    value: proc_macro2::TokenStream,
 }
 
 impl Parse for QueryTypeField {
     fn parse(input: ParseStream) -> Result<Self> {
+        let name = input.parse()?;
+        let _colon = input.parse()?;
+
+        // The value is kept as raw tokens, because handler arguments may be
+        // types such as `Option<String>`, which cannot be parsed as an
+        // expression. Commas only end the value outside of generic arguments,
+        // such as in `Result<HashMap<String, Series>>`. The `>` of `->` and
+        // `=>` doesn't close generic arguments.
+        let mut value = proc_macro2::TokenStream::new();
+        let mut depth = 0usize;
+        let mut after_arrow_start = false;
+        while !input.is_empty() && (depth > 0 || !input.peek(Token![,])) {
+            let token = input.parse::<TokenTree>()?;
+            if let TokenTree::Punct(punct) = &token {
+                match punct.as_char() {
+                    '<' => depth += 1,
+                    '>' if !after_arrow_start => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+                after_arrow_start =
+                    matches!(punct.as_char(), '-' | '=') && punct.spacing() == Spacing::Joint;
+            } else {
+                after_arrow_start = false;
+            }
+            value.extend([token]);
+        }
+
         Ok(Self {
-            name: input.parse()?,
-            _colon: input.parse()?,
-            value: input.parse()?,
+            name,
+            _colon,
+            value,
         })
     }
 }
 
 struct QueryHandler {
     is_async: bool,
-    ident: Ident,
+    path: Path,
     _parens: token::Paren,
-    arg_types: Punctuated<Ident, Token![,]>,
+    arg_types: Punctuated<Type, Token![,]>,
+    return_type: Option<Type>,
+}
+
+impl QueryHandler {
+    /// Returns an expression that invokes the handler from `invoke2()`.
+    ///
+    /// Arguments are resolved by their type: `ProviderRequest` receives the
+    /// request itself, `String` receives the query type and any other type is
+    /// extracted from the request using its `FromRequest` implementation.
+    fn invoke(&self) -> proc_macro2::TokenStream {
+        self.call(|ty| match type_name(ty).as_str() {
+            "String" => quote! { request.query_type.clone() },
+            _ => quote! {
+                <#ty as fiberplane_pdk::context::FromRequest>::from_request(&request)?
+            },
+        })
+    }
+
+    /// Returns an expression that invokes the handler from the `invoke2()`
+    /// match arm for `SUGGESTIONS_QUERY_TYPE`.
+    ///
+    /// Because the query data of the request is the `AutoSuggestRequest`,
+    /// arguments are resolved differently: `ProviderRequest` receives the
+    /// request itself, `AutoSuggestRequest` receives the request for
+    /// suggestions, `String` receives the query type for which suggestions are
+    /// requested and any other type is extracted from the config using its
+    /// `FromConfig` implementation.
+    fn suggest(&self) -> proc_macro2::TokenStream {
+        self.call(|ty| match type_name(ty).as_str() {
+            "AutoSuggestRequest" => quote! { suggest_request.clone() },
+            "String" => quote! { suggest_request.query_type.clone() },
+            _ => quote_spanned! {ty.span()=>
+                <#ty as fiberplane_pdk::context::FromConfig>::from_config(&request.config)?
+            },
+        })
+    }
+
+    /// Returns an expression that calls the handler, passing the request
+    /// itself for `ProviderRequest` and resolving the other arguments using
+    /// `resolve_arg`.
+    fn call(
+        &self,
+        resolve_arg: impl Fn(&Type) -> proc_macro2::TokenStream,
+    ) -> proc_macro2::TokenStream {
+        let mut moves_request = false;
+        let (bindings, args): (Vec<_>, Vec<_>) = self
+            .arg_types
+            .iter()
+            .enumerate()
+            .map(|(pos, ty)| {
+                let arg = format_ident!("arg{}", pos);
+                if type_name(ty) == "ProviderRequest" {
+                    if moves_request {
+                        abort!(ty, "handlers may take the `ProviderRequest` only once");
+                    }
+                    moves_request = true;
+                    return (quote! {}, quote! { request });
+                }
+
+                let value = resolve_arg(ty);
+                (quote! { let #arg = #value; }, quote! { #arg })
+            })
+            .unzip();
+
+        let fn_name = &self.path;
+        let call = if self.is_async {
+            quote! { #fn_name(#(#args),*).await }
+        } else {
+            quote! { #fn_name(#(#args),*) }
+        };
+        quote! {
+            {
+                #(#bindings)*
+                #call
+            }
+        }
+    }
+
+    /// Returns the types of the arguments that may represent the query data.
+    fn query_data_types(&self) -> impl Iterator<Item = &Type> {
+        self.arg_types
+            .iter()
+            .filter(|ty| !matches!(type_name(ty).as_str(), "ProviderRequest" | "String"))
+    }
+
+    /// Returns an expression that evaluates to the schema of the query data,
+    /// if any of the arguments represents the query data.
+    fn query_schema(&self) -> Option<proc_macro2::TokenStream> {
+        let mut schemas = self
+            .query_data_types()
+            .map(|ty| quote! { <#ty as fiberplane_pdk::context::FromRequest>::query_schema });
+
+        let first = schemas.next()?;
+        Some(quote! { #first() #(.or_else(#schemas))* })
+    }
+
+    /// Returns the path of the handler function, making sure it is not
+    /// asynchronous. Used for handlers that are called from synchronous
+    /// exports.
+    fn sync_path(&self, field_name: &str) -> &Path {
+        self.disallow_return_type(field_name);
+        if self.is_async {
+            abort!(
+                self.path,
+                "`{}` handlers cannot be asynchronous",
+                field_name
+            );
+        }
+        &self.path
+    }
+
+    /// Aborts if a return type is given for handlers that don't support one.
+    fn disallow_return_type(&self, field_name: &str) {
+        if let Some(return_type) = &self.return_type {
+            abort!(
+                return_type,
+                "`{}` handlers cannot specify a return type",
+                field_name
+            );
+        }
+    }
 }
 
 impl Parse for QueryHandler {
     fn parse(input: ParseStream) -> Result<Self> {
         let content;
 
-        let ident = input.parse::<Ident>()?;
+        let path = input.call(Path::parse_mod_style)?;
         let _parens = parenthesized!(content in input);
-        let arg_types = content.parse_terminated(Ident::parse)?;
+        let arg_types = content.parse_terminated(Type::parse)?;
         let is_async = if input.peek(Token![.]) && input.peek2(Token![await]) {
             input.parse::<Token![.]>()?;
             input.parse::<Token![await]>()?;
@@ -203,12 +702,19 @@ impl Parse for QueryHandler {
         } else {
             false
         };
+        let return_type = if input.peek(Token![->]) {
+            input.parse::<Token![->]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
 
         Ok(Self {
             is_async,
-            ident,
+            path,
             _parens,
             arg_types,
+            return_type,
         })
     }
 }
@@ -227,3 +733,34 @@ impl Parse for SupportedMimeTypes {
         })
     }
 }
+
+struct SuggestionHandlers {
+    _braces: token::Brace,
+    handlers: Punctuated<SuggestionHandler, Token![,]>,
+}
+
+impl Parse for SuggestionHandlers {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let content;
+        Ok(Self {
+            _braces: braced!(content in input),
+            handlers: content.parse_terminated(SuggestionHandler::parse)?,
+        })
+    }
+}
+
+struct SuggestionHandler {
+    field: IdentOrString,
+    _arrow: token::FatArrow,
+    handler: QueryHandler,
+}
+
+impl Parse for SuggestionHandler {
+    fn parse(input: ParseStream) -> Result<Self> {
+        Ok(Self {
+            field: input.parse()?,
+            _arrow: input.parse()?,
+            handler: input.parse()?,
+        })
+    }
+}
@@ -0,0 +1,123 @@
+use crate::field_attrs::FieldAttrs;
+use crate::schema_field::{serde_field_name, SchemaField, SerdeAttrs};
+use crate::schema_generator::generate_schema_parts;
+use crate::validation::generate_validation;
+use fiberplane_models::providers::SelectField;
+use proc_macro2::TokenStream;
+use proc_macro_error::abort;
+use quote::quote;
+use std::collections::BTreeSet;
+use syn::{Attribute, DataEnum, Field, Fields, Ident};
+
+/// Generates a schema from the given enum.
+///
+/// Internally tagged enums result in a select field for the tag, followed by
+/// the fields of all the variants, which list the tag as their prerequisite.
+/// Untagged enums only result in the fields of all the variants. Since only
+/// the fields of a single variant need to be filled in, none of the fields of
+/// the variants are marked as required. Fields that occur in multiple variants
+/// are only included once.
+pub fn generate_enum_schema(
+    field_enum: &str,
+    ident: &Ident,
+    data: &DataEnum,
+    enum_attrs: &[Attribute],
+    optional: bool,
+) -> TokenStream {
+    let serde_attrs = SerdeAttrs::from_attrs(enum_attrs);
+    if serde_attrs.content.is_some() || (serde_attrs.tag.is_none() && !serde_attrs.untagged) {
+        abort!(
+            ident,
+            "enums in a schema must be either `#[serde(untagged)]` or internally tagged using \
+            `#[serde(tag = \"...\")]`"
+        );
+    }
+
+    let mut parts = Vec::new();
+    if let Some(tag) = &serde_attrs.tag {
+        let attrs = FieldAttrs::from_attrs(enum_attrs);
+        let options: Vec<_> = data
+            .variants
+            .iter()
+            .map(|variant| serde_field_name(&variant.ident.to_string(), &variant.attrs, enum_attrs))
+            .collect();
+
+        let mut tag_field = SchemaField::Select(SelectField::new().with_options(options));
+        if let Some(label) = attrs.label {
+            tag_field = tag_field.with_label(&label);
+        }
+        if let Some(placeholder) = attrs.placeholder {
+            tag_field = tag_field.with_placeholder(&placeholder);
+        }
+        if !optional {
+            tag_field = tag_field.required();
+        }
+
+        let tag_field = tag_field
+            .with_name(tag)
+            .to_token_stream(field_enum, &[], &[]);
+        parts.push(quote! { vec![#tag_field] });
+    }
+
+    let mut field_names = BTreeSet::new();
+    for variant in &data.variants {
+        let fields: Vec<&Field> = match &variant.fields {
+            Fields::Named(fields) => fields
+                .named
+                .iter()
+                .filter(|field| {
+                    let Some(ident) = &field.ident else {
+                        return false;
+                    };
+                    SerdeAttrs::from_attrs(&field.attrs).flatten
+                        || field_names.insert(serde_field_name(
+                            &ident.to_string(),
+                            &field.attrs,
+                            &variant.attrs,
+                        ))
+                })
+                .collect(),
+            Fields::Unit if serde_attrs.tag.is_some() => continue,
+            _ => abort!(
+                variant,
+                "only unit variants and variants with named fields are supported"
+            ),
+        };
+
+        parts.extend(generate_schema_parts(
+            field_enum,
+            fields,
+            &variant.attrs,
+            true,
+            serde_attrs.tag.as_deref(),
+        ));
+    }
+
+    quote! { vec![#(#parts),*].into_iter().flatten().collect() }
+}
+
+/// Generates an expression that validates the fields of whichever variant the
+/// enum holds.
+pub fn generate_enum_validation(data: &DataEnum) -> TokenStream {
+    let arms = data.variants.iter().map(|variant| {
+        let ident = &variant.ident;
+        match &variant.fields {
+            Fields::Named(fields) => {
+                let field_idents = fields.named.iter().map(|field| &field.ident);
+                let validation =
+                    generate_validation(&fields.named, &variant.attrs, |ident| quote! { #ident });
+                quote! {
+                    #[allow(unused_variables)]
+                    Self::#ident { #(#field_idents),* } => #validation
+                }
+            }
+            _ => quote! { Self::#ident { .. } => Ok(()) },
+        }
+    });
+
+    quote! {
+        match self {
+            #(#arms),*
+        }
+    }
+}
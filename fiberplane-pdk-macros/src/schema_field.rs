@@ -17,6 +17,9 @@ pub enum SchemaField {
     Select(SelectField),
     Text(TextField),
     Array(ArraySchema),
+    /// A select field of which the options are derived from the variants of
+    /// the enum with the given type name.
+    EnumSelect(SelectField, String),
 }
 
 /// A compile-time representation of an ArrayField schema,
@@ -50,6 +53,7 @@ impl SchemaField {
                 minimum_length: 1,
                 ..schema
             }),
+            EnumSelect(field, type_name) => EnumSelect(field.required(), type_name),
         }
     }
 
@@ -60,9 +64,6 @@ impl SchemaField {
         field_attrs: &[Attribute],
         struct_attrs: &[Attribute],
     ) -> proc_macro2::TokenStream {
-        let serde_field_attrs = SerdeAttrs::from_attrs(field_attrs);
-        let serde_struct_attrs = SerdeAttrs::from_attrs(struct_attrs);
-
         use SchemaField::*;
 
         let field_variant = match &self {
@@ -70,25 +71,23 @@ impl SchemaField {
             DateTimeRange(_) => quote! { DateTimeRange },
             Integer(_) => quote! { Integer },
             Label(_) => quote! { Label },
-            Select(_) => quote! { Select },
+            Select(_) | EnumSelect(..) => quote! { Select },
             Text(_) => quote! { Text },
             Array(_) => quote! { Array },
         };
         let enum_ident = Ident::new(field_enum, Span::call_site());
         let field_ident = Ident::new(&format!("{field_variant}Field"), Span::call_site());
 
-        let name = serde_field_attrs.rename.unwrap_or_else(|| {
-            let name = match &self {
-                Checkbox(field) => &field.name,
-                DateTimeRange(field) => &field.name,
-                Integer(field) => &field.name,
-                Label(field) => &field.name,
-                Select(field) => &field.name,
-                Text(field) => &field.name,
-                Array(field) => &field.name,
-            };
-            serde_struct_attrs.rename_all.format_string(name)
-        });
+        let name = match &self {
+            Checkbox(field) => &field.name,
+            DateTimeRange(field) => &field.name,
+            Integer(field) => &field.name,
+            Label(field) => &field.name,
+            Select(field) | EnumSelect(field, _) => &field.name,
+            Text(field) => &field.name,
+            Array(field) => &field.name,
+        };
+        let name = serde_field_name(name, field_attrs, struct_attrs);
         let name = quote! { .with_name(#name) };
 
         let checked = match &self {
@@ -96,12 +95,35 @@ impl SchemaField {
             _ => quote! {},
         };
 
+        let initial_value = match &self {
+            Integer(IntegerField {
+                initial_value: Some(value),
+                ..
+            }) => quote! { .with_initial_value(#value) },
+            Select(SelectField {
+                initial_value: Some(value),
+                ..
+            })
+            | EnumSelect(
+                SelectField {
+                    initial_value: Some(value),
+                    ..
+                },
+                _,
+            )
+            | Text(TextField {
+                initial_value: Some(value),
+                ..
+            }) => quote! { .with_initial_value(#value) },
+            _ => quote! {},
+        };
+
         let label = match &self {
             Checkbox(field) => &field.label,
             DateTimeRange(field) => &field.label,
             Integer(field) => &field.label,
             Label(field) => &field.label,
-            Select(field) => &field.label,
+            Select(field) | EnumSelect(field, _) => &field.label,
             Text(field) => &field.label,
             Array(field) => &field.label,
         };
@@ -130,7 +152,7 @@ impl SchemaField {
         let multiple = match &self {
             Text(field) if field.multiple => quote! { .multiple() },
             Label(field) if field.multiple => quote! { .multiple() },
-            Select(field) if field.multiple => quote! { .multiple() },
+            Select(field) | EnumSelect(field, _) if field.multiple => quote! { .multiple() },
             _ => quote! {},
         };
 
@@ -143,6 +165,14 @@ impl SchemaField {
             Select(SelectField { options, .. }) if !options.is_empty() => {
                 quote! { .with_options([#(#options.into()),*]) }
             }
+            EnumSelect(_, type_name) => {
+                let type_ident = format_ident!("{type_name}");
+                quote! {
+                    .with_options(
+                        <#type_ident as fiberplane_pdk::select_options::SelectOptions>::options()
+                    )
+                }
+            }
             _ => quote! {},
         };
 
@@ -151,7 +181,7 @@ impl SchemaField {
             DateTimeRange(field) => &field.placeholder,
             Integer(_) => "",
             Label(field) => &field.placeholder,
-            Select(field) => &field.placeholder,
+            Select(field) | EnumSelect(field, _) => &field.placeholder,
             Text(field) => &field.placeholder,
             _ => "",
         };
@@ -161,7 +191,9 @@ impl SchemaField {
         };
 
         let prerequisites = match &self {
-            Select(SelectField { prerequisites, .. }) | Text(TextField { prerequisites, .. })
+            Select(SelectField { prerequisites, .. })
+            | EnumSelect(SelectField { prerequisites, .. }, _)
+            | Text(TextField { prerequisites, .. })
                 if !prerequisites.is_empty() =>
             {
                 quote! { .with_prerequisites([#(#prerequisites.into()),*]) }
@@ -174,7 +206,7 @@ impl SchemaField {
             DateTimeRange(field) => field.required,
             Integer(field) => field.required,
             Label(field) => field.required,
-            Select(field) => field.required,
+            Select(field) | EnumSelect(field, _) => field.required,
             Text(field) => field.required,
             Array(_) => false,
         };
@@ -195,6 +227,13 @@ impl SchemaField {
                 supports_suggestions,
                 ..
             })
+            | EnumSelect(
+                SelectField {
+                    supports_suggestions,
+                    ..
+                },
+                _,
+            )
             | Text(TextField {
                 supports_suggestions,
                 ..
@@ -228,6 +267,7 @@ impl SchemaField {
             #enum_ident::#field_variant(#field_ident::new()
                 #name
                 #checked
+                #initial_value
                 #label
                 #max
                 #min
@@ -252,6 +292,7 @@ impl SchemaField {
             Integer(field) => Integer(field.with_label(label)),
             Label(field) => Label(field.with_label(label)),
             Select(field) => Select(field.with_label(label)),
+            EnumSelect(field, type_name) => EnumSelect(field.with_label(label), type_name),
             Text(field) => Text(field.with_label(label)),
             Array(field) => Array(ArraySchema {
                 label: label.to_string(),
@@ -268,6 +309,7 @@ impl SchemaField {
             Integer(field) => Integer(field.with_name(name)),
             Label(field) => Label(field.with_name(name)),
             Select(field) => Select(field.with_name(name)),
+            EnumSelect(field, type_name) => EnumSelect(field.with_name(name), type_name),
             Text(field) => Text(field.with_name(name)),
             Array(field) => Array(ArraySchema {
                 name: name.to_string(),
@@ -276,6 +318,32 @@ impl SchemaField {
         }
     }
 
+    /// Adds a prerequisite to fields that support them. Other fields are
+    /// returned unchanged.
+    pub fn with_prerequisite(self, name: &str) -> Self {
+        use SchemaField::*;
+        let with_prerequisite = |prerequisites: &[String]| {
+            let mut prerequisites = prerequisites.to_vec();
+            prerequisites.push(name.to_owned());
+            prerequisites
+        };
+        match self {
+            Select(field) => {
+                let prerequisites = with_prerequisite(&field.prerequisites);
+                Select(field.with_prerequisites(prerequisites))
+            }
+            EnumSelect(field, type_name) => {
+                let prerequisites = with_prerequisite(&field.prerequisites);
+                EnumSelect(field.with_prerequisites(prerequisites), type_name)
+            }
+            Text(field) => {
+                let prerequisites = with_prerequisite(&field.prerequisites);
+                Text(field.with_prerequisites(prerequisites))
+            }
+            _ => self,
+        }
+    }
+
     pub fn with_placeholder(self, name: &str) -> Self {
         use SchemaField::*;
         match self {
@@ -284,28 +352,63 @@ impl SchemaField {
             Integer(_) => self,
             Label(field) => Label(field.with_placeholder(name)),
             Select(field) => Select(field.with_placeholder(name)),
+            EnumSelect(field, type_name) => EnumSelect(field.with_placeholder(name), type_name),
             Text(field) => Text(field.with_placeholder(name)),
             Array(_) => self,
         }
     }
 }
 
+/// Returns the name of a field as it is (de)serialized by Serde.
+pub fn serde_field_name(
+    name: &str,
+    field_attrs: &[Attribute],
+    struct_attrs: &[Attribute],
+) -> String {
+    SerdeAttrs::from_attrs(field_attrs)
+        .rename
+        .unwrap_or_else(|| {
+            SerdeAttrs::from_attrs(struct_attrs)
+                .rename_all
+                .format_string(name)
+        })
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct SerdeAttrs {
+    pub content: Option<String>,
+    pub default: bool,
+    pub flatten: bool,
     pub rename: Option<String>,
     pub rename_all: Casing,
+    pub tag: Option<String>,
+    pub untagged: bool,
 }
 
 impl SerdeAttrs {
+    /// Parses the Serde attributes that are relevant to schema generation.
+    ///
+    /// Attributes may be spread over multiple `#[serde(...)]` annotations.
     pub fn from_attrs(attrs: &[Attribute]) -> Self {
-        for attr in attrs {
-            if attr.path.is_ident("serde") {
-                return syn::parse2::<Self>(attr.tokens.clone())
-                    .expect("Could not parse Serde field attributes");
-            }
-        }
-
-        Self::default()
+        attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("serde"))
+            .map(|attr| {
+                syn::parse2::<Self>(attr.tokens.clone())
+                    .expect("Could not parse Serde field attributes")
+            })
+            .fold(Self::default(), |result, attrs| Self {
+                content: attrs.content.or(result.content),
+                default: result.default || attrs.default,
+                flatten: result.flatten || attrs.flatten,
+                rename: attrs.rename.or(result.rename),
+                rename_all: match attrs.rename_all {
+                    Casing::Original => result.rename_all,
+                    casing => casing,
+                },
+                tag: attrs.tag.or(result.tag),
+                untagged: result.untagged || attrs.untagged,
+            })
     }
 }
 
@@ -336,11 +439,19 @@ impl Parse for SerdeAttrs {
         loop {
             let key: Ident = content.call(IdentExt::parse_any)?;
             match key.to_string().as_ref() {
+                "content" => result.content = Some(parse_value()?),
+                "default" => {
+                    parse_optional_value()?;
+                    result.default = true;
+                }
+                "flatten" => result.flatten = true,
                 "rename" => result.rename = Some(parse_value()?),
                 "rename_all" => {
                     result.rename_all = Casing::try_from(parse_value()?.as_ref())
                         .map_err(|err| Error::new(content.span(), err))?
                 }
+                "tag" => result.tag = Some(parse_value()?),
+                "untagged" => result.untagged = true,
                 _ => {
                     parse_optional_value()?;
                 }
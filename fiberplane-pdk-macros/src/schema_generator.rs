@@ -1,30 +1,66 @@
-use crate::schema_field::SchemaField;
-use crate::{field_attrs::FieldAttrs, schema_field::ArraySchema};
+use crate::field_attrs::{DefaultValue, FieldAttrs};
+use crate::schema_field::ArraySchema;
+use crate::schema_field::{SchemaField, SerdeAttrs};
 use fiberplane_models::providers::*;
-use proc_macro::TokenStream;
+use proc_macro2::TokenStream;
 use proc_macro_error::abort;
 use quote::quote;
-use syn::{
-    parse_macro_input, Field, GenericArgument, ItemStruct, PathArguments, PathSegment, Type,
-};
-
-/// Generates a schema from the given struct.
-pub fn generate_schema(field_enum: &str, struct_item: TokenStream) -> TokenStream {
-    let schema_struct = parse_macro_input!(struct_item as ItemStruct);
-    let fields: Vec<_> = schema_struct
-        .fields
-        .iter()
-        .map(|field: &Field| {
-            let schema_field = determine_field_type(field);
-            schema_field.to_token_stream(field_enum, &field.attrs, &schema_struct.attrs)
-        })
-        .collect();
+use syn::{Attribute, Field, GenericArgument, PathArguments, PathSegment, Type};
+
+/// Generates a schema from the given fields.
+///
+/// If `optional` is true, none of the fields are marked as required. This is
+/// used for the `optional_schema()` method, which is used when the type is
+/// flattened into an `Option`.
+pub fn generate_schema<'a>(
+    field_enum: &str,
+    fields: impl IntoIterator<Item = &'a Field>,
+    struct_attrs: &[Attribute],
+    optional: bool,
+) -> TokenStream {
+    let parts = generate_schema_parts(field_enum, fields, struct_attrs, optional, None);
+    quote! { vec![#(#parts),*].into_iter().flatten().collect() }
+}
 
-    let ts = quote! { vec![#(#fields),*] };
-    ts.into()
+/// Generates an expression for every field, each of which evaluates to a list
+/// of schema fields.
+///
+/// Fields annotated with `#[serde(flatten)]` are expanded into the fields
+/// returned by the `schema()` method of their type, or by its
+/// `optional_schema()` method if the field is optional.
+///
+/// If a `prerequisite` is given, it is added to the prerequisites of all
+/// fields that support them, except for flattened ones.
+pub fn generate_schema_parts<'a>(
+    field_enum: &str,
+    fields: impl IntoIterator<Item = &'a Field>,
+    struct_attrs: &[Attribute],
+    optional: bool,
+    prerequisite: Option<&str>,
+) -> Vec<TokenStream> {
+    fields
+        .into_iter()
+        .map(|field| {
+            if SerdeAttrs::from_attrs(&field.attrs).flatten {
+                let (flattened_type, flattened_optional) = get_flattened_type(field);
+                if optional || flattened_optional {
+                    quote! { <#flattened_type>::optional_schema() }
+                } else {
+                    quote! { <#flattened_type>::schema() }
+                }
+            } else {
+                let mut schema_field = determine_field_type(field, struct_attrs, optional);
+                if let Some(prerequisite) = prerequisite {
+                    schema_field = schema_field.with_prerequisite(prerequisite);
+                }
+                let field = schema_field.to_token_stream(field_enum, &field.attrs, struct_attrs);
+                quote! { vec![#field] }
+            }
+        })
+        .collect()
 }
 
-fn determine_field_type(field: &Field) -> SchemaField {
+fn determine_field_type(field: &Field, struct_attrs: &[Attribute], optional: bool) -> SchemaField {
     let name = match &field.ident {
         Some(ident) => ident.to_string(),
         None => abort!(field, "struct field must have an identifier"),
@@ -32,7 +68,27 @@ fn determine_field_type(field: &Field) -> SchemaField {
 
     let attrs = FieldAttrs::from_attrs(&field.attrs);
 
-    let (type_ident, required, multiple) = get_ident(field);
+    let (type_ident, mut required, multiple) = get_ident(field);
+
+    // Fields for which Serde provides a default don't need to be filled in.
+    if SerdeAttrs::from_attrs(&field.attrs).default || SerdeAttrs::from_attrs(struct_attrs).default
+    {
+        required = false;
+    } else if required && attrs.default.is_some() {
+        abort!(
+            field,
+            "fields with a default value must be optional or use `#[serde(default)]`"
+        );
+    }
+
+    // TODO: Mark secret fields as password inputs once `TextField` in
+    // `fiberplane-models` has a flag for it. Until then, they are only redacted.
+    if attrs.secret && !matches!(type_ident.as_str(), "String" | "Secret") {
+        abort!(
+            field.ty,
+            "secret fields must be of type `String` or `Secret`"
+        );
+    }
 
     let mut schema_field = match (type_ident.as_str(), multiple) {
         ("bool", false) => {
@@ -43,12 +99,14 @@ fn determine_field_type(field: &Field) -> SchemaField {
             }
             SchemaField::Checkbox(field)
         }
-        ("i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64", false) => {
+        (integer_type, false) if is_integer_type(integer_type) => {
+            let max = attrs.max.map(|max| integer_bound(field, max));
+            let min = attrs.min.map(|min| integer_bound(field, min));
             let mut field = IntegerField::new();
-            if let Some(max) = attrs.max {
+            if let Some(max) = max {
                 field = field.with_max(max);
             }
-            if let Some(min) = attrs.min {
+            if let Some(min) = min {
                 field = field.with_min(min);
             } else if type_ident.as_str().starts_with('u') {
                 field = field.with_min(0);
@@ -66,47 +124,52 @@ fn determine_field_type(field: &Field) -> SchemaField {
             }
             SchemaField::Label(field)
         }
-        ("String", multiple) => {
+        ("String" | "Secret", multiple) => {
             if attrs.select {
-                let mut field = SelectField::new();
-                if multiple {
-                    field = field.multiple();
-                }
+                let mut field = select_field(&attrs, multiple);
                 if !attrs.options.is_empty() {
                     field = field.with_options(attrs.options)
                 }
-                if !attrs.prerequisites.is_empty() {
-                    field = field.with_prerequisites(attrs.prerequisites);
-                }
-                if attrs.supports_suggestions {
-                    field = field.with_suggestions();
-                }
                 SchemaField::Select(field)
             } else {
-                let mut field = TextField::new();
-                if attrs.multiline {
-                    field = field.multiline();
-                }
-                if multiple {
-                    field = field.multiple();
-                }
-                if !attrs.prerequisites.is_empty() {
-                    field = field.with_prerequisites(attrs.prerequisites);
-                }
-                if attrs.supports_suggestions {
-                    field = field.with_suggestions();
-                }
-                SchemaField::Text(field)
+                SchemaField::Text(text_field(&attrs, multiple))
+            }
+        }
+        // Types that are parsed from text get a placeholder that shows the
+        // expected format, which may be overridden using the `placeholder`
+        // attribute.
+        (float_type, multiple) if is_float_type(float_type) => {
+            SchemaField::Text(text_field(&attrs, multiple).with_placeholder("e.g. 0.5"))
+        }
+        ("Duration", multiple) => SchemaField::Text(
+            text_field(&attrs, multiple).with_placeholder("e.g. 30s, 5m or 1h30m"),
+        ),
+        ("Url", multiple) => {
+            SchemaField::Text(text_field(&attrs, multiple).with_placeholder("https://"))
+        }
+        // Other types with the `select` attribute are enums implementing
+        // `SelectOptions`, while lists of other types are arrays of structs.
+        (enum_name, multiple) if attrs.select => {
+            if !attrs.options.is_empty() {
+                abort!(
+                    field.ty,
+                    "the options of enum select fields are derived from their variants"
+                );
             }
+            SchemaField::EnumSelect(select_field(&attrs, multiple), enum_name.to_owned())
         }
         (struct_name, true) => SchemaField::Array(ArraySchema {
             element_struct_type_name: struct_name.to_string(),
             name: name.clone(),
             label: String::new(),
             minimum_length: 0,
-            maximum_length: attrs.max.map(|val| val.max(0).try_into().unwrap()),
+            maximum_length: attrs.max.map(|val| val.max(0.0) as u32),
         }),
-        _ => abort!(field.ty, "unsupported type in schema"),
+        (_, false) => abort!(
+            field.ty,
+            "unsupported type in schema";
+            help = "use `#[pdk(select)]` for enums implementing `SelectOptions`"
+        ),
     };
 
     if let Some(label) = attrs.label {
@@ -117,14 +180,105 @@ fn determine_field_type(field: &Field) -> SchemaField {
         schema_field = schema_field.with_placeholder(&placeholder);
     }
 
-    if required {
+    if let Some(default) = &attrs.default {
+        schema_field = with_default(schema_field, default).unwrap_or_else(|| {
+            abort!(
+                field.ty,
+                "default value is not supported for this type of field"
+            )
+        });
+    }
+
+    if required && !optional {
         schema_field = schema_field.required();
     }
 
     schema_field.with_name(&name)
 }
 
-fn get_ident(field: &Field) -> (String, bool, bool) {
+/// Sets the default value as the field's initial value, or returns `None` if
+/// the field doesn't support the value.
+fn with_default(schema_field: SchemaField, default: &DefaultValue) -> Option<SchemaField> {
+    use SchemaField::*;
+    let schema_field = match (schema_field, default) {
+        (Checkbox(field), DefaultValue::Bool(true)) => Checkbox(field.checked_by_default()),
+        (Checkbox(field), DefaultValue::Bool(false)) => Checkbox(field),
+        (Integer(field), DefaultValue::Integer(value)) => Integer(field.with_initial_value(*value)),
+        (Select(field), DefaultValue::Text(value)) => Select(field.with_initial_value(value)),
+        (EnumSelect(field, type_name), DefaultValue::Text(value)) => {
+            EnumSelect(field.with_initial_value(value), type_name)
+        }
+        (Text(field), DefaultValue::Integer(value)) => {
+            Text(field.with_initial_value(&value.to_string()))
+        }
+        (Text(field), DefaultValue::Text(value)) => Text(field.with_initial_value(value)),
+        _ => return None,
+    };
+    Some(schema_field)
+}
+
+/// Creates a text field with the attributes shared by all types that are
+/// presented as text fields.
+fn text_field(attrs: &FieldAttrs, multiple: bool) -> TextField {
+    let mut field = TextField::new();
+    if attrs.multiline {
+        field = field.multiline();
+    }
+    if multiple {
+        field = field.multiple();
+    }
+    if !attrs.prerequisites.is_empty() {
+        field = field.with_prerequisites(attrs.prerequisites.clone());
+    }
+    if attrs.supports_suggestions {
+        field = field.with_suggestions();
+    }
+    field
+}
+
+/// Creates a select field with the attributes shared by string and enum select
+/// fields.
+fn select_field(attrs: &FieldAttrs, multiple: bool) -> SelectField {
+    let mut field = SelectField::new();
+    if multiple {
+        field = field.multiple();
+    }
+    if !attrs.prerequisites.is_empty() {
+        field = field.with_prerequisites(attrs.prerequisites.clone());
+    }
+    if attrs.supports_suggestions {
+        field = field.with_suggestions();
+    }
+    field
+}
+
+/// Converts a `min` or `max` bound for use on an integer field.
+fn integer_bound(field: &Field, bound: f64) -> i32 {
+    if bound.fract() != 0.0 || bound < i32::MIN.into() || bound > i32::MAX.into() {
+        abort!(field.ty, "bounds on integer fields must be whole numbers");
+    }
+    bound as i32
+}
+
+/// Returns whether the given type identifier refers to a supported integer
+/// type.
+pub fn is_integer_type(type_ident: &str) -> bool {
+    matches!(
+        type_ident,
+        "i8" | "i16" | "i32" | "i64" | "u8" | "u16" | "u32" | "u64"
+    )
+}
+
+/// Returns whether the given type identifier refers to a supported
+/// floating-point type.
+pub fn is_float_type(type_ident: &str) -> bool {
+    matches!(type_ident, "f32" | "f64")
+}
+
+/// Returns the identifier of the field's type, with `Option` and `Vec`
+/// wrappers stripped off, as well as whether the field is required and whether
+/// it accepts multiple values.
+pub fn get_ident(field: &Field) -> (String, bool, bool) {
     let path = match &field.ty {
         Type::Path(type_path) if type_path.qself.is_none() => &type_path.path,
         ty => abort!(ty, "unsupported type in schema"),
@@ -158,6 +312,30 @@ fn get_ident(field: &Field) -> (String, bool, bool) {
     (path_segment.ident.to_string(), required, multiple)
 }
 
+/// Returns the type of a flattened field, with any `Option` stripped off, as
+/// well as whether the field was optional.
+pub fn get_flattened_type(field: &Field) -> (Type, bool) {
+    let Type::Path(type_path) = &field.ty else {
+        abort!(field.ty, "unsupported type for flattened field")
+    };
+
+    let Some(segment) = type_path.path.segments.last() else {
+        abort!(field.ty, "unsupported type for flattened field")
+    };
+
+    if segment.ident == "Option" {
+        if let PathArguments::AngleBracketed(args) = &segment.arguments {
+            if let Some(GenericArgument::Type(nested_type)) = args.args.last() {
+                return (nested_type.clone(), true);
+            }
+        }
+
+        abort!(field.ty, "unsupported type for flattened field")
+    }
+
+    (field.ty.clone(), false)
+}
+
 fn get_nested_type(segment: &PathSegment, container_ident: &str) -> Option<PathSegment> {
     if segment.ident == container_ident {
         if let PathArguments::AngleBracketed(args) = &segment.arguments {
@@ -0,0 +1,63 @@
+use crate::field_attrs::FieldAttrs;
+use crate::schema_field::SerdeAttrs;
+use inflector::Inflector;
+use proc_macro::TokenStream;
+use proc_macro_error::abort;
+use quote::quote;
+use syn::{parse_macro_input, Fields, ItemEnum};
+
+pub fn derive_select_options(input: TokenStream) -> TokenStream {
+    let select_enum = parse_macro_input!(input as ItemEnum);
+    let ident = &select_enum.ident;
+
+    if select_enum.variants.is_empty() {
+        abort!(select_enum, "enum must have at least one variant");
+    }
+
+    if !select_enum.generics.params.is_empty() {
+        abort!(select_enum.generics, "generic enums are not supported");
+    }
+
+    let rename_all = SerdeAttrs::from_attrs(&select_enum.attrs).rename_all;
+
+    let mut variants = Vec::new();
+    let mut values = Vec::new();
+    let mut labels = Vec::new();
+    for variant in &select_enum.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            abort!(variant, "variants of select options may not have fields");
+        }
+
+        let name = variant.ident.to_string();
+        let value = SerdeAttrs::from_attrs(&variant.attrs)
+            .rename
+            .unwrap_or_else(|| rename_all.format_string(&name));
+        let label = FieldAttrs::from_attrs(&variant.attrs)
+            .label
+            .unwrap_or_else(|| name.to_sentence_case());
+
+        variants.push(&variant.ident);
+        values.push(value);
+        labels.push(label);
+    }
+
+    let output = quote! {
+        #[automatically_derived]
+        impl fiberplane_pdk::select_options::SelectOptions for #ident {
+            const VARIANTS: &'static [Self] = &[#(Self::#variants),*];
+
+            fn value(&self) -> &'static str {
+                match self {
+                    #(Self::#variants => #values),*
+                }
+            }
+
+            fn label(&self) -> &'static str {
+                match self {
+                    #(Self::#variants => #labels),*
+                }
+            }
+        }
+    };
+    output.into()
+}
@@ -0,0 +1,78 @@
+use crate::field_attrs::FieldAttrs;
+use crate::schema_field::{serde_field_name, SerdeAttrs};
+use crate::schema_generator::{get_flattened_type, get_ident, is_float_type, is_integer_type};
+use proc_macro2::{Ident, Literal, TokenStream};
+use quote::quote;
+use syn::{Attribute, Field};
+
+/// Generates an expression that validates the given fields according to their
+/// validation attributes.
+///
+/// `access` returns an expression that evaluates to a reference to the value
+/// of the field with the given identifier, such as `&self.field`.
+///
+/// Flattened fields are validated using the `validate()` method of their type.
+pub fn generate_validation<'a>(
+    fields: impl IntoIterator<Item = &'a Field>,
+    struct_attrs: &[Attribute],
+    access: impl Fn(&Ident) -> TokenStream,
+) -> TokenStream {
+    let checks = fields.into_iter().flat_map(|field| {
+        let mut checks = Vec::new();
+        let Some(ident) = &field.ident else {
+            return checks;
+        };
+        let value = access(ident);
+
+        if SerdeAttrs::from_attrs(&field.attrs).flatten {
+            checks.push(match get_flattened_type(field) {
+                (_, true) => quote! {
+                    .nested(Option::as_ref(#value).map_or(Ok(()), |value| value.validate()))
+                },
+                (_, false) => quote! { .nested((#value).validate()) },
+            });
+            return checks;
+        }
+
+        let attrs = FieldAttrs::from_attrs(&field.attrs);
+        let name = serde_field_name(&ident.to_string(), &field.attrs, struct_attrs);
+
+        if attrs.non_empty {
+            checks.push(quote! { .non_empty(#name, #value) });
+        }
+
+        if attrs.url {
+            checks.push(quote! { .url(#name, #value) });
+        }
+
+        if let Some(pattern) = &attrs.regex {
+            checks.push(quote! { .regex(#name, #value, #pattern) });
+        }
+
+        if !attrs.one_of.is_empty() {
+            let options = &attrs.one_of;
+            checks.push(quote! { .one_of(#name, #value, &[#(#options),*]) });
+        }
+
+        // On lists of structs, `max` limits the number of items instead.
+        let (type_ident, _, _) = get_ident(field);
+        if is_integer_type(&type_ident) || is_float_type(&type_ident) || type_ident == "Duration" {
+            if let Some(min) = attrs.min {
+                let min = Literal::f64_unsuffixed(min);
+                checks.push(quote! { .min(#name, #value, #min) });
+            }
+            if let Some(max) = attrs.max {
+                let max = Literal::f64_unsuffixed(max);
+                checks.push(quote! { .max(#name, #value, #max) });
+            }
+        }
+
+        checks
+    });
+
+    quote! {
+        fiberplane_pdk::validation::Validator::new()
+            #(#checks)*
+            .finish()
+    }
+}
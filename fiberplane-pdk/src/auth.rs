@@ -0,0 +1,214 @@
+//! Authentication settings that can be shared by providers.
+//!
+//! [`Auth`] is meant to be flattened into a provider's config, so that all
+//! providers use the same config keys and form fields for their credentials:
+//!
+//! ```no_run
+//! use fiberplane_pdk::prelude::*;
+//! use serde::Deserialize;
+//!
+//! #[derive(ConfigSchema, Deserialize)]
+//! #[serde(rename_all = "camelCase")]
+//! struct Config {
+//!     #[pdk(label = "Your API endpoint")]
+//!     pub url: String,
+//!
+//!     #[serde(flatten)]
+//!     pub auth: Option<Auth>,
+//! }
+//! ```
+
+use crate::macros::ConfigSchema;
+use crate::secret::Secret;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Credentials for authenticating requests to a provider's backend.
+///
+/// The scheme is determined by the keys present in the config:
+///
+/// * `username` and `password` for Basic auth.
+/// * `token` for Bearer auth.
+/// * `apiKey` for an API key. By default, the key is sent as
+///   `Authorization: ApiKey <key>`. If `apiKeyHeader` is given, the key is sent
+///   as-is in the header with that name instead.
+/// * `headerName` and `headerValue` for sending a custom header.
+///
+/// Passwords, tokens, keys and header values are [`Secret`]s, so they are
+/// redacted from log messages.
+///
+/// The derived `schema()` contains the fields of all schemes. None of them are
+/// required, since only the fields for a single scheme need to be filled in.
+#[derive(Clone, ConfigSchema, Debug, Deserialize, Eq, Hash, PartialEq)]
+#[pdk(nested)]
+#[serde(untagged)]
+pub enum Auth {
+    Basic {
+        #[pdk(label = "Username (if the API uses Basic auth)")]
+        username: String,
+        #[pdk(label = "Password (if the API uses Basic auth)")]
+        password: Secret,
+    },
+    Bearer {
+        #[pdk(label = "Token (if the API uses Bearer auth)")]
+        token: Secret,
+    },
+    #[serde(rename_all = "camelCase")]
+    ApiKey {
+        #[pdk(label = "API key (if the API uses API keys)")]
+        api_key: Secret,
+        #[serde(default)]
+        #[pdk(label = "Header to send the API key in (defaults to Authorization)")]
+        api_key_header: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    CustomHeader {
+        #[pdk(label = "Name of a custom header to send")]
+        header_name: String,
+        #[pdk(label = "Value of the custom header")]
+        header_value: Secret,
+    },
+}
+
+impl Auth {
+    /// Returns the headers to send along with requests.
+    pub fn to_headers(&self) -> BTreeMap<String, String> {
+        let (name, value) = match self {
+            Self::Basic { username, password } => (
+                "Authorization".to_owned(),
+                format!(
+                    "Basic {}",
+                    BASE64_STANDARD.encode(format!("{username}:{}", password.expose_secret()))
+                ),
+            ),
+            Self::Bearer { token } => (
+                "Authorization".to_owned(),
+                format!("Bearer {}", token.expose_secret()),
+            ),
+            Self::ApiKey {
+                api_key,
+                api_key_header: Some(header_name),
+            } => (header_name.clone(), api_key.expose_secret().to_owned()),
+            Self::ApiKey {
+                api_key,
+                api_key_header: None,
+            } => (
+                "Authorization".to_owned(),
+                format!("ApiKey {}", api_key.expose_secret()),
+            ),
+            Self::CustomHeader {
+                header_name,
+                header_value,
+            } => (header_name.clone(), header_value.expose_secret().to_owned()),
+        };
+
+        BTreeMap::from([(name, value)])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{ConfigField, TextField};
+    use serde_json::json;
+
+    #[test]
+    fn deserializes_schemes() {
+        let parse = |value| serde_json::from_value::<Auth>(value).unwrap();
+
+        assert_eq!(
+            parse(json!({ "username": "admin", "password": "secret" })),
+            Auth::Basic {
+                username: "admin".to_owned(),
+                password: "secret".into()
+            }
+        );
+        assert_eq!(
+            parse(json!({ "token": "secret" })),
+            Auth::Bearer {
+                token: "secret".into()
+            }
+        );
+        assert_eq!(
+            parse(json!({ "apiKey": "secret" })),
+            Auth::ApiKey {
+                api_key: "secret".into(),
+                api_key_header: None
+            }
+        );
+        assert_eq!(
+            parse(json!({ "headerName": "X-Token", "headerValue": "secret" })),
+            Auth::CustomHeader {
+                header_name: "X-Token".to_owned(),
+                header_value: "secret".into()
+            }
+        );
+    }
+
+    #[test]
+    fn converts_to_headers() {
+        let headers = |auth: Auth| auth.to_headers().into_iter().collect::<Vec<_>>();
+        let header = |name: &str, value: &str| vec![(name.to_owned(), value.to_owned())];
+
+        assert_eq!(
+            headers(Auth::Basic {
+                username: "admin".to_owned(),
+                password: "secret".into()
+            }),
+            header("Authorization", "Basic YWRtaW46c2VjcmV0")
+        );
+        assert_eq!(
+            headers(Auth::Bearer {
+                token: "secret".into()
+            }),
+            header("Authorization", "Bearer secret")
+        );
+        assert_eq!(
+            headers(Auth::ApiKey {
+                api_key: "secret".into(),
+                api_key_header: None
+            }),
+            header("Authorization", "ApiKey secret")
+        );
+        assert_eq!(
+            headers(Auth::ApiKey {
+                api_key: "secret".into(),
+                api_key_header: Some("X-API-Key".to_owned())
+            }),
+            header("X-API-Key", "secret")
+        );
+        assert_eq!(
+            headers(Auth::CustomHeader {
+                header_name: "X-Token".to_owned(),
+                header_value: "secret".into()
+            }),
+            header("X-Token", "secret")
+        );
+    }
+
+    #[test]
+    fn schema_contains_optional_fields_of_all_schemes() {
+        let text_field = |name: &str, label: &str| -> ConfigField {
+            TextField::new().with_name(name).with_label(label).into()
+        };
+
+        // Schema fields can't be compared directly, so compare them as JSON.
+        let to_json = |fields: Vec<ConfigField>| serde_json::to_value(fields).unwrap();
+        assert_eq!(
+            to_json(Auth::schema()),
+            to_json(vec![
+                text_field("username", "Username (if the API uses Basic auth)"),
+                text_field("password", "Password (if the API uses Basic auth)"),
+                text_field("token", "Token (if the API uses Bearer auth)"),
+                text_field("apiKey", "API key (if the API uses API keys)"),
+                text_field(
+                    "apiKeyHeader",
+                    "Header to send the API key in (defaults to Authorization)",
+                ),
+                text_field("headerName", "Name of a custom header to send"),
+                text_field("headerValue", "Value of the custom header"),
+            ])
+        );
+    }
+}
@@ -0,0 +1,303 @@
+//! Caching of responses that are requested repeatedly.
+//!
+//! Some lookups, such as the list of data sources or the metadata of all
+//! metrics, are made on every call even though their results rarely change. A
+//! [`Cache`] memoizes the results of such lookups for a given time to live.
+//!
+//! Entries are keyed by a hash of the provider config and the request, so
+//! different configs never share results. They are kept in memory for as long
+//! as the provider module instance lives: within a single invocation, and
+//! across invocations if the runtime keeps the instance alive.
+//!
+//! Values are stored serialized, so the memory used by all caches together is
+//! bounded by [`set_max_size()`]. When the limit is reached, expired entries
+//! are evicted first, followed by the least recently used ones.
+//!
+//! ```no_run
+//! # use fiberplane_pdk::http::HttpClient;
+//! # use fiberplane_pdk::prelude::*;
+//! # use fiberplane_pdk::serde_json::Value;
+//! use fiberplane_pdk::cache::Cache;
+//!
+//! static METADATA: Cache = Cache::new("metadata", Duration::from_secs(5 * 60));
+//!
+//! # async fn example(client: &HttpClient, base_url: &str) -> Result<()> {
+//! let metadata: Value = METADATA
+//!     .get_or_fetch(&base_url, &"api/v1/metadata", || {
+//!         client.get_json("api/v1/metadata")
+//!     })
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::host::now;
+use crate::types::{Duration, Result};
+use serde::{de::DeserializeOwned, Serialize};
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
+use time::OffsetDateTime;
+use tracing::debug;
+
+/// The default maximum size of all cached values together, in bytes.
+pub const DEFAULT_MAX_SIZE: usize = 4 * 1024 * 1024;
+
+thread_local! {
+    // Providers run single-threaded, so a thread-local store is shared by all
+    // invocations. It also keeps tests that run in parallel isolated.
+    static STORE: RefCell<Store> = RefCell::new(Store::default());
+    static MAX_SIZE: Cell<usize> = const { Cell::new(DEFAULT_MAX_SIZE) };
+}
+
+/// A cache for the results of a single kind of lookup.
+///
+/// Caches are cheap to create and are typically declared as a `static`, with a
+/// name that distinguishes their entries from those of other caches.
+#[derive(Clone, Copy, Debug)]
+pub struct Cache {
+    name: &'static str,
+    ttl: Duration,
+}
+
+impl Cache {
+    /// Creates a cache of which entries expire after the given time to live.
+    pub const fn new(name: &'static str, ttl: Duration) -> Self {
+        Self { name, ttl }
+    }
+
+    /// Returns the cached value for the given config and request, if it has
+    /// not expired yet.
+    pub fn get<V>(&self, config: &impl Hash, request: &impl Hash) -> Option<V>
+    where
+        V: DeserializeOwned,
+    {
+        let key = self.key(config, request);
+        let bytes = STORE.with(|store| store.borrow_mut().get(key, *now()))?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    /// Stores the value for the given config and request.
+    ///
+    /// Values that cannot be serialized or that are larger than the maximum
+    /// size of the cache are not stored.
+    pub fn insert<V>(&self, config: &impl Hash, request: &impl Hash, value: &V)
+    where
+        V: Serialize,
+    {
+        let bytes = match serde_json::to_vec(value) {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                debug!(cache = self.name, %error, "Cannot serialize value to cache");
+                return;
+            }
+        };
+
+        let key = self.key(config, request);
+        let inserted_at = *now();
+        let expires_at = inserted_at + std::time::Duration::from(self.ttl);
+        let max_size = MAX_SIZE.with(Cell::get);
+        STORE.with(|store| {
+            store
+                .borrow_mut()
+                .insert(key, bytes, expires_at, inserted_at, max_size)
+        });
+    }
+
+    /// Removes the cached value for the given config and request.
+    pub fn remove(&self, config: &impl Hash, request: &impl Hash) {
+        let key = self.key(config, request);
+        STORE.with(|store| store.borrow_mut().remove(key));
+    }
+
+    /// Returns the cached value for the given config and request, or fetches
+    /// and caches it if there is none.
+    ///
+    /// Errors are returned as is and are not cached.
+    pub async fn get_or_fetch<V, F, Fut>(
+        &self,
+        config: &impl Hash,
+        request: &impl Hash,
+        fetch: F,
+    ) -> Result<V>
+    where
+        V: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V>>,
+    {
+        if let Some(value) = self.get(config, request) {
+            return Ok(value);
+        }
+
+        let value = fetch().await?;
+        self.insert(config, request, &value);
+        Ok(value)
+    }
+
+    fn key(&self, config: &impl Hash, request: &impl Hash) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.name.hash(&mut hasher);
+        config.hash(&mut hasher);
+        request.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Sets the maximum size of all cached values together, in bytes.
+///
+/// Entries are evicted when the next value is inserted, if needed.
+pub fn set_max_size(max_size: usize) {
+    MAX_SIZE.with(|cell| cell.set(max_size));
+}
+
+/// Removes all entries from all caches.
+pub fn clear() {
+    STORE.with(|store| *store.borrow_mut() = Store::default());
+}
+
+#[derive(Default)]
+struct Store {
+    entries: BTreeMap<u64, Entry>,
+    size: usize,
+
+    /// Incremented on every access, to find the least recently used entry.
+    clock: u64,
+}
+
+struct Entry {
+    bytes: Vec<u8>,
+    expires_at: OffsetDateTime,
+    last_used: u64,
+}
+
+impl Store {
+    fn get(&mut self, key: u64, now: OffsetDateTime) -> Option<Vec<u8>> {
+        let entry = self.entries.get_mut(&key)?;
+        if entry.expires_at <= now {
+            self.remove(key);
+            return None;
+        }
+
+        self.clock += 1;
+        entry.last_used = self.clock;
+        Some(entry.bytes.clone())
+    }
+
+    fn insert(
+        &mut self,
+        key: u64,
+        bytes: Vec<u8>,
+        expires_at: OffsetDateTime,
+        now: OffsetDateTime,
+        max_size: usize,
+    ) {
+        self.remove(key);
+        if bytes.len() > max_size {
+            return;
+        }
+
+        if self.size + bytes.len() > max_size {
+            self.entries.retain(|_, entry| entry.expires_at > now);
+            self.size = self.entries.values().map(|entry| entry.bytes.len()).sum();
+        }
+        while self.size + bytes.len() > max_size {
+            let Some(lru_key) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| *key)
+            else {
+                break;
+            };
+            self.remove(lru_key);
+        }
+
+        self.clock += 1;
+        self.size += bytes.len();
+        self.entries.insert(
+            key,
+            Entry {
+                bytes,
+                expires_at,
+                last_used: self.clock,
+            },
+        );
+    }
+
+    fn remove(&mut self, key: u64) {
+        if let Some(entry) = self.entries.remove(&key) {
+            self.size -= entry.bytes.len();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(secs: i64) -> OffsetDateTime {
+        OffsetDateTime::from_unix_timestamp(secs).unwrap()
+    }
+
+    #[test]
+    fn expires_entries() {
+        let mut store = Store::default();
+        store.insert(1, b"\"a\"".to_vec(), time(60), time(0), 100);
+
+        assert_eq!(store.get(1, time(59)), Some(b"\"a\"".to_vec()));
+        assert_eq!(store.get(1, time(60)), None);
+        assert_eq!(store.size, 0);
+    }
+
+    #[test]
+    fn evicts_least_recently_used_entries() {
+        let mut store = Store::default();
+        store.insert(1, vec![0; 4], time(60), time(0), 10);
+        store.insert(2, vec![0; 4], time(60), time(0), 10);
+        store.get(1, time(1));
+        store.insert(3, vec![0; 4], time(60), time(1), 10);
+
+        assert!(store.get(1, time(2)).is_some());
+        assert!(store.get(2, time(2)).is_none());
+        assert!(store.get(3, time(2)).is_some());
+        assert_eq!(store.size, 8);
+
+        // Values that don't fit at all are not stored.
+        store.insert(4, vec![0; 11], time(60), time(2), 10);
+        assert!(store.get(4, time(2)).is_none());
+        assert_eq!(store.size, 8);
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn caches_per_config_and_request() {
+        use crate::testing::{block_on, MockHost};
+
+        static CACHE: Cache = Cache::new("test", Duration::from_secs(60));
+
+        let host = MockHost::new();
+        let _guard = host.install();
+        let fetches = Cell::new(0);
+        let fetch = |value: &str| {
+            fetches.set(fetches.get() + 1);
+            let value = value.to_owned();
+            async move { Ok(value) }
+        };
+
+        let first: String = block_on(CACHE.get_or_fetch(&"config", &"a", || fetch("1"))).unwrap();
+        let second: String = block_on(CACHE.get_or_fetch(&"config", &"a", || fetch("2"))).unwrap();
+        let other: String =
+            block_on(CACHE.get_or_fetch(&"other config", &"a", || fetch("3"))).unwrap();
+        assert_eq!(
+            (first.as_str(), second.as_str(), other.as_str()),
+            ("1", "1", "3")
+        );
+        assert_eq!(fetches.get(), 2);
+
+        host.advance(time::Duration::seconds(60));
+        let expired: String = block_on(CACHE.get_or_fetch(&"config", &"a", || fetch("4"))).unwrap();
+        assert_eq!(expired, "4");
+    }
+}
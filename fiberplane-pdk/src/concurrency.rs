@@ -0,0 +1,186 @@
+//! Running independent requests concurrently.
+//!
+//! Handlers that need the results of several requests that don't depend on
+//! each other can overlap them instead of waiting for one round trip after
+//! another. [`join_all()`] runs any number of futures of the same type, at most
+//! a given number at a time, and returns their results in order.
+//! [`try_join_all()`] does the same, but combines all failures into a single
+//! [`Error`]. [`make_http_requests()`] is a shorthand for sending a batch of
+//! HTTP requests through the host.
+//!
+//! For a fixed number of futures of different types, [`join()`] and
+//! [`try_join()`] can be used instead:
+//!
+//! ```no_run
+//! # use fiberplane_pdk::http::HttpClient;
+//! # use fiberplane_pdk::serde_json::Value;
+//! # type Series = Vec<Value>;
+//! # type Metadata = Value;
+//! use fiberplane_pdk::concurrency::join;
+//!
+//! # async fn example(client: &HttpClient) {
+//! let (series, metadata) = join(
+//!     client.get_json::<Series>("api/v1/series"),
+//!     client.get_json::<Metadata>("api/v1/metadata"),
+//! )
+//! .await;
+//! # }
+//! ```
+
+use crate::bindings::{Error, HttpRequest, HttpResponse};
+use crate::host::make_http_request;
+use crate::types::Result;
+use futures::stream::{self, StreamExt};
+use std::fmt::Write;
+use std::future::Future;
+
+pub use futures::future::{join, try_join};
+
+/// The default maximum number of requests that are in flight at once.
+pub const DEFAULT_CONCURRENCY: usize = 6;
+
+/// Runs the given futures concurrently, polling at most `limit` of them at
+/// once, and returns their outputs in the order of the futures.
+pub async fn join_all<I>(futures: I, limit: usize) -> Vec<<I::Item as Future>::Output>
+where
+    I: IntoIterator,
+    I::Item: Future,
+{
+    stream::iter(futures).buffered(limit.max(1)).collect().await
+}
+
+/// Runs the given futures concurrently, polling at most `limit` of them at
+/// once, and returns their values in the order of the futures.
+///
+/// All futures are run to completion, even if some of them fail. If one of
+/// them fails, its error is returned as is. If several of them fail, their
+/// errors are combined into a single [`Error::Other`] that lists every failure
+/// with the index of its future.
+pub async fn try_join_all<I, T>(futures: I, limit: usize) -> Result<Vec<T>>
+where
+    I: IntoIterator,
+    I::Item: Future<Output = Result<T>>,
+{
+    let results = join_all(futures, limit).await;
+    let total = results.len();
+
+    let mut values = Vec::with_capacity(total);
+    let mut errors = Vec::new();
+    for (index, result) in results.into_iter().enumerate() {
+        match result {
+            Ok(value) => values.push(value),
+            Err(error) => errors.push((index, error)),
+        }
+    }
+
+    match errors.len() {
+        0 => Ok(values),
+        1 => Err(errors.remove(0).1),
+        failed => {
+            let mut message = format!("{failed} of {total} requests failed:");
+            for (index, error) in errors {
+                let _ = write!(message, "\n- Request {}: {error:?}", index + 1);
+            }
+            Err(Error::Other { message })
+        }
+    }
+}
+
+/// Sends the given HTTP requests concurrently, with at most `limit` of them in
+/// flight at once, and returns the responses in the order of the requests.
+///
+/// Failures are combined as described for [`try_join_all()`].
+pub async fn make_http_requests<I>(requests: I, limit: usize) -> Result<Vec<HttpResponse>>
+where
+    I: IntoIterator<Item = HttpRequest>,
+{
+    try_join_all(
+        requests.into_iter().map(|request| async move {
+            make_http_request(request)
+                .await
+                .map_err(|error| Error::Http { error })
+        }),
+        limit,
+    )
+    .await
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::testing::block_on;
+    use std::cell::Cell;
+    use std::future::poll_fn;
+    use std::task::Poll;
+
+    /// Yields to the executor the given number of times before completing.
+    async fn yield_times(mut times: usize) {
+        poll_fn(|cx| {
+            if times == 0 {
+                Poll::Ready(())
+            } else {
+                times -= 1;
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        })
+        .await
+    }
+
+    #[test]
+    fn runs_futures_concurrently_in_order() {
+        let in_flight = Cell::new(0);
+        let max_in_flight = Cell::new(0);
+
+        // Later futures complete sooner, but the results are still returned in
+        // the order of the futures.
+        let futures = (0..5).map(|index| {
+            let in_flight = &in_flight;
+            let max_in_flight = &max_in_flight;
+            async move {
+                in_flight.set(in_flight.get() + 1);
+                max_in_flight.set(max_in_flight.get().max(in_flight.get()));
+                yield_times(5 - index).await;
+                in_flight.set(in_flight.get() - 1);
+                index * 10
+            }
+        });
+
+        let results = block_on(join_all(futures, 2));
+        assert_eq!(results, vec![0, 10, 20, 30, 40]);
+        assert_eq!(max_in_flight.get(), 2);
+    }
+
+    #[test]
+    fn combines_errors() {
+        let result = block_on(try_join_all(
+            vec![
+                Ok(1),
+                Err(Error::UnsupportedRequest),
+                Ok(3),
+                Err(Error::Other {
+                    message: "Timeout".to_owned(),
+                }),
+            ]
+            .into_iter()
+            .map(std::future::ready),
+            DEFAULT_CONCURRENCY,
+        ));
+
+        match result {
+            Err(Error::Other { message }) => assert_eq!(
+                message,
+                "2 of 4 requests failed:\n\
+                 - Request 2: UnsupportedRequest\n\
+                 - Request 4: Other { message: \"Timeout\" }"
+            ),
+            other => panic!("unexpected result: {other:?}"),
+        }
+
+        let result = block_on(try_join_all(
+            [Ok(1), Err(Error::UnsupportedRequest)].map(std::future::ready),
+            DEFAULT_CONCURRENCY,
+        ));
+        assert!(matches!(result, Err(Error::UnsupportedRequest)));
+    }
+}
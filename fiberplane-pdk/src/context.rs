@@ -0,0 +1,114 @@
+//! Values that query handlers can receive from a provider request.
+//!
+//! Handlers declared with `pdk_query_types!` may take any combination of
+//! typed query data, a typed config, the [`QueryContext`], the
+//! `ProviderRequest` itself and the query type as a `String`. Apart from the
+//! latter two, all of these are extracted from the request through the
+//! [`FromRequest`] trait, so their order in the handler's signature doesn't
+//! matter.
+//!
+//! Suggestion handlers receive the `AutoSuggestRequest` instead of query data,
+//! so apart from the `ProviderRequest` and the query type for which
+//! suggestions are requested, they may only take types implementing
+//! [`FromConfig`].
+
+use crate::bindings::Blob;
+use crate::provider_data::str_eq;
+use crate::providers::{AutoSuggestRequest, ProviderConfig, ProviderRequest, QuerySchema};
+use crate::types::Result;
+
+/// Types that can be extracted from a `ProviderRequest` and passed to a query
+/// handler.
+///
+/// This trait is implemented by `#[derive(QuerySchema)]`, which parses the
+/// query data, and by `#[derive(ConfigSchema)]`, which parses the config.
+pub trait FromRequest: Sized {
+    /// Extracts the value from the given request.
+    fn from_request(request: &ProviderRequest) -> Result<Self>;
+
+    /// The names of the fields in the schema of the query data, if this type
+    /// represents the query data of the request.
+    ///
+    /// `pdk_query_types!` uses these to verify at compile time that
+    /// suggestions are only provided for fields that exist.
+    #[doc(hidden)]
+    const QUERY_FIELDS: QueryFieldNames = QueryFieldNames::NONE;
+
+    /// Returns the schema of the query data, if this type represents the
+    /// query data of the request.
+    fn query_schema() -> Option<QuerySchema> {
+        None
+    }
+}
+
+/// Types that can be extracted from the config of a provider request.
+///
+/// This trait is implemented by `#[derive(ConfigSchema)]`. Unlike the query
+/// data, the config can also be passed to suggestion handlers.
+pub trait FromConfig: Sized {
+    /// Extracts the value from the given config.
+    fn from_config(config: &ProviderConfig) -> Result<Self>;
+}
+
+/// The names of the fields in a query schema, including those of flattened
+/// types.
+#[doc(hidden)]
+#[derive(Clone, Copy, Debug)]
+pub struct QueryFieldNames {
+    pub names: &'static [&'static str],
+    pub flattened: &'static [QueryFieldNames],
+}
+
+impl QueryFieldNames {
+    pub const NONE: Self = Self {
+        names: &[],
+        flattened: &[],
+    };
+
+    /// Returns whether a field with the given name exists. This function can
+    /// be evaluated at compile time.
+    pub const fn contains(&self, name: &str) -> bool {
+        let mut i = 0;
+        while i < self.names.len() {
+            if str_eq(self.names[i], name) {
+                return true;
+            }
+            i += 1;
+        }
+
+        let mut i = 0;
+        while i < self.flattened.len() {
+            if self.flattened[i].contains(name) {
+                return true;
+            }
+            i += 1;
+        }
+        false
+    }
+}
+
+/// Information about the current invocation that is not part of the query
+/// data or the config.
+#[derive(Clone, Debug)]
+pub struct QueryContext {
+    /// The query type of the request.
+    pub query_type: String,
+
+    /// The response of the previous invocation for the same query, if any.
+    pub previous_response: Option<Blob>,
+}
+
+impl FromRequest for QueryContext {
+    fn from_request(request: &ProviderRequest) -> Result<Self> {
+        Ok(Self {
+            query_type: request.query_type.clone(),
+            previous_response: request.previous_response.clone(),
+        })
+    }
+}
+
+impl FromRequest for AutoSuggestRequest {
+    fn from_request(request: &ProviderRequest) -> Result<Self> {
+        Self::parse(request.query_data.clone())
+    }
+}
@@ -0,0 +1,35 @@
+//! Functions provided by the host runtime.
+//!
+//! Inside the provider runtime these forward to the generated bindings. When
+//! the `testing` feature is enabled for a native build, they are served by the
+//! [`MockHost`](crate::testing::MockHost) installed for the current thread
+//! instead, so provider handlers can be exercised in plain `cargo test`.
+//!
+//! Providers should call these through the [prelude](crate::prelude) rather
+//! than using the bindings directly.
+
+use crate::bindings::{HttpRequest, HttpRequestError, HttpResponse, Timestamp};
+use crate::secret::redact;
+
+#[cfg(not(all(feature = "testing", not(target_arch = "wasm32"))))]
+use crate::bindings as imp;
+#[cfg(all(feature = "testing", not(target_arch = "wasm32")))]
+use crate::testing::host as imp;
+
+/// Performs an HTTP request through the host.
+pub async fn make_http_request(request: HttpRequest) -> Result<HttpResponse, HttpRequestError> {
+    imp::make_http_request(request).await
+}
+
+/// Logs a message to the (development) console.
+///
+/// Known secrets and the values of sensitive headers are
+/// [redacted](crate::secret::redact) from the message.
+pub fn log(message: String) {
+    imp::log(redact(&message))
+}
+
+/// Returns the current timestamp according to the host.
+pub fn now() -> Timestamp {
+    imp::now()
+}
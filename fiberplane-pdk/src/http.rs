@@ -0,0 +1,511 @@
+//! A typed HTTP client for talking to the services providers query.
+//!
+//! The client takes care of joining paths to the configured base URL,
+//! encoding query parameters and bodies, adding default headers, decoding JSON
+//! responses and mapping errors, so that every provider reports failures the
+//! same way:
+//!
+//! * Invalid URLs result in [`Error::Config`].
+//! * Failed requests result in [`Error::Http`]. For responses with an error
+//!   status code, this includes the response body, so it can be shown to the
+//!   user.
+//! * Responses that cannot be decoded result in [`Error::Deserialization`],
+//!   with an excerpt of the response body for diagnostics.
+//!
+//! Requests are not retried, unless the client is configured with a
+//! [`RetryPolicy`].
+//!
+//! ```no_run
+//! # async fn example() -> fiberplane_pdk::prelude::Result<()> {
+//! use fiberplane_pdk::http::{parse_url, HttpClient};
+//! use fiberplane_pdk::serde_json::Value;
+//!
+//! let client = HttpClient::builder(parse_url("http://localhost:9090/prometheus/")?)
+//!     .bearer_auth("my-token")
+//!     .build();
+//!
+//! // Requests http://localhost:9090/prometheus/api/v1/query?query=up
+//! let result: Value = client
+//!     .get("api/v1/query")
+//!     .query("query", "up")
+//!     .send_json()
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::auth::Auth;
+use crate::bindings::{Blob, Error, HttpRequest, HttpRequestMethod, HttpResponse};
+use crate::retry::RetryPolicy;
+use crate::types::Result;
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::BTreeMap;
+use tracing::{debug, debug_span, Instrument};
+use url::Url;
+
+/// Maximum number of characters from a response body to include in error
+/// messages.
+const MAX_BODY_EXCERPT_LENGTH: usize = 1_000;
+
+/// Parses a URL, typically one that comes from a provider's config.
+pub fn parse_url(url: &str) -> Result<Url> {
+    Url::parse(url).map_err(|err| Error::Config {
+        message: format!("Invalid URL {url:?}: {err}"),
+    })
+}
+
+/// HTTP client with a base URL and default headers.
+///
+/// Use [`HttpClient::builder()`] to create one.
+#[derive(Clone, Debug)]
+pub struct HttpClient {
+    base_url: Url,
+    default_headers: BTreeMap<String, String>,
+    retry_policy: RetryPolicy,
+}
+
+impl HttpClient {
+    /// Creates a builder for a client that sends its requests relative to
+    /// the given base URL.
+    ///
+    /// Paths are resolved the same way as with [`Url::join()`], so the base
+    /// URL needs a trailing slash for requests to be sent below its path:
+    /// for path `api/datasources`, `http://localhost/grafana/` results in
+    /// requests to `http://localhost/grafana/api/datasources`, while
+    /// `http://localhost/grafana` results in requests to
+    /// `http://localhost/api/datasources`.
+    pub fn builder(base_url: Url) -> HttpClientBuilder {
+        HttpClientBuilder::new(base_url)
+    }
+
+    /// Returns the base URL that request paths are resolved against.
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// Resolves a path against the base URL.
+    ///
+    /// Relative paths replace the last segment of the base URL's path, unless
+    /// it ends with a slash. Paths starting with a slash are resolved against
+    /// the root of the base URL's host, while absolute URLs are returned as
+    /// is.
+    pub fn url(&self, path: &str) -> Result<Url> {
+        self.base_url.join(path).map_err(|err| Error::Config {
+            message: format!("Invalid URL for path {path:?}: {err}"),
+        })
+    }
+
+    /// Starts building a request with the given method and path.
+    pub fn request(&self, method: HttpRequestMethod, path: &str) -> RequestBuilder<'_> {
+        RequestBuilder {
+            client: self,
+            method,
+            url: self.url(path),
+            query: Vec::new(),
+            headers: BTreeMap::new(),
+            body: None,
+        }
+    }
+
+    /// Starts building a `GET` request for the given path.
+    pub fn get(&self, path: &str) -> RequestBuilder<'_> {
+        self.request(HttpRequestMethod::Get, path)
+    }
+
+    /// Starts building a `POST` request for the given path.
+    pub fn post(&self, path: &str) -> RequestBuilder<'_> {
+        self.request(HttpRequestMethod::Post, path)
+    }
+
+    /// Sends a `GET` request for the given path and decodes the JSON response.
+    pub async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        self.get(path).send_json().await
+    }
+
+    /// Sends a `POST` request with a JSON body to the given path and decodes
+    /// the JSON response.
+    pub async fn post_json<B, T>(&self, path: &str, body: &B) -> Result<T>
+    where
+        B: Serialize + ?Sized,
+        T: DeserializeOwned,
+    {
+        self.post(path).json(body)?.send_json().await
+    }
+
+    /// Sends a prepared request through the host.
+    ///
+    /// Default headers are added to the request, unless the request already
+    /// contains a header with the same name. Failed requests are retried
+    /// according to the client's retry policy.
+    pub async fn execute(&self, mut request: HttpRequest) -> Result<HttpResponse> {
+        let headers = request.headers.get_or_insert_with(BTreeMap::new);
+        for (name, value) in &self.default_headers {
+            if !headers.keys().any(|key| key.eq_ignore_ascii_case(name)) {
+                headers.insert(name.clone(), value.clone());
+            }
+        }
+
+        send_with_retries(request, &self.retry_policy).await
+    }
+
+    /// Sends a prepared request through the host and decodes the JSON
+    /// response.
+    pub async fn execute_json<T: DeserializeOwned>(&self, request: HttpRequest) -> Result<T> {
+        let url = request.url.clone();
+        let response = self.execute(request).await?;
+        decode_json(&url, &response)
+    }
+}
+
+/// Sends a request through the host, mapping errors the same way as
+/// [`HttpClient`] does.
+///
+/// This is useful for requests that need to be built by hand, such as
+/// requests that are signed.
+pub async fn send(request: HttpRequest) -> Result<HttpResponse> {
+    send_with_retries(request, &RetryPolicy::none()).await
+}
+
+/// Sends a request through the host like [`send()`] does, retrying it
+/// according to the given policy.
+pub async fn send_with_retries(request: HttpRequest, policy: &RetryPolicy) -> Result<HttpResponse> {
+    let span = debug_span!("http_request", method = ?request.method, url = %request.url);
+    async move {
+        debug!("Sending request");
+        let result = policy.send(request).await;
+        match &result {
+            Ok(response) => debug!(status = response.status_code, "Received response"),
+            Err(error) => debug!(?error, "Request failed"),
+        }
+        result.map_err(|error| Error::Http { error })
+    }
+    .instrument(span)
+    .await
+}
+
+/// Sends a request through the host and decodes the JSON response.
+pub async fn send_json<T: DeserializeOwned>(request: HttpRequest) -> Result<T> {
+    let url = request.url.clone();
+    let response = send(request).await?;
+    decode_json(&url, &response)
+}
+
+/// Builder for [`HttpClient`].
+#[derive(Clone, Debug)]
+pub struct HttpClientBuilder {
+    base_url: Url,
+    default_headers: BTreeMap<String, String>,
+    retry_policy: RetryPolicy,
+}
+
+impl HttpClientBuilder {
+    fn new(base_url: Url) -> Self {
+        Self {
+            base_url,
+            default_headers: BTreeMap::new(),
+            retry_policy: RetryPolicy::none(),
+        }
+    }
+
+    /// Adds a header that is sent with every request.
+    pub fn default_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.default_headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Adds headers that are sent with every request.
+    pub fn default_headers(mut self, headers: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.default_headers.extend(headers);
+        self
+    }
+
+    /// Sends the headers for the given credentials with every request.
+    pub fn auth(self, auth: &Auth) -> Self {
+        self.default_headers(auth.to_headers())
+    }
+
+    /// Sends Basic authentication credentials with every request.
+    pub fn basic_auth(self, username: &str, password: &str) -> Self {
+        self.auth(&Auth::Basic {
+            username: username.to_owned(),
+            password: password.into(),
+        })
+    }
+
+    /// Sends a bearer token with every request.
+    pub fn bearer_auth(self, token: &str) -> Self {
+        self.auth(&Auth::Bearer {
+            token: token.into(),
+        })
+    }
+
+    /// Retries failed requests according to the given policy.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    pub fn build(self) -> HttpClient {
+        HttpClient {
+            base_url: self.base_url,
+            default_headers: self.default_headers,
+            retry_policy: self.retry_policy,
+        }
+    }
+}
+
+/// Builder for a single request, created through one of the [`HttpClient`]
+/// methods.
+#[must_use = "requests are only sent when calling `send()` or `send_json()`"]
+pub struct RequestBuilder<'a> {
+    client: &'a HttpClient,
+    method: HttpRequestMethod,
+    url: Result<Url>,
+    query: Vec<(String, String)>,
+    headers: BTreeMap<String, String>,
+    body: Option<Vec<u8>>,
+}
+
+impl RequestBuilder<'_> {
+    /// Appends a query parameter to the URL.
+    pub fn query(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.query.push((name.into(), value.into()));
+        self
+    }
+
+    /// Appends query parameters to the URL.
+    pub fn query_pairs<K, V>(mut self, pairs: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.query.extend(
+            pairs
+                .into_iter()
+                .map(|(name, value)| (name.into(), value.into())),
+        );
+        self
+    }
+
+    /// Sets a header for this request, overriding any default header with the
+    /// same name.
+    ///
+    /// Header names are compared case-insensitively, so this also replaces a
+    /// header that was set with different casing.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        let name = name.into();
+        self.headers
+            .retain(|key, _| !key.eq_ignore_ascii_case(&name));
+        self.headers.insert(name, value.into());
+        self
+    }
+
+    /// Sets the body of the request, along with its `Content-Type`.
+    pub fn body(self, content_type: &str, body: impl Into<Vec<u8>>) -> Self {
+        let mut builder = self.header("Content-Type", content_type);
+        builder.body = Some(body.into());
+        builder
+    }
+
+    /// Uses the data of the given blob as the body of the request.
+    pub fn blob(self, blob: Blob) -> Self {
+        self.body(&blob.mime_type, blob.data.to_vec())
+    }
+
+    /// Serializes the given value as the JSON body of the request.
+    pub fn json<T: Serialize + ?Sized>(self, body: &T) -> Result<Self> {
+        let body = serde_json::to_vec(body).map_err(|err| Error::Data {
+            message: format!("Could not serialize request body: {err}"),
+        })?;
+        Ok(self.body("application/json", body))
+    }
+
+    /// Uses the given pairs as a form-encoded body for the request.
+    pub fn form<K, V>(self, pairs: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let body = url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(pairs)
+            .finish();
+        self.body("application/x-www-form-urlencoded", body)
+    }
+
+    /// Builds the request without sending it.
+    pub fn build(self) -> Result<HttpRequest> {
+        let mut url = self.url?;
+        if !self.query.is_empty() {
+            url.query_pairs_mut().extend_pairs(&self.query);
+        }
+
+        let mut request = HttpRequest::default();
+        request.method = self.method;
+        request.url = url.to_string();
+        request.headers = Some(self.headers);
+        request.body = self.body.map(Into::into);
+        Ok(request)
+    }
+
+    /// Sends the request.
+    pub async fn send(self) -> Result<HttpResponse> {
+        let client = self.client;
+        client.execute(self.build()?).await
+    }
+
+    /// Sends the request and decodes the JSON response.
+    pub async fn send_json<T: DeserializeOwned>(self) -> Result<T> {
+        let client = self.client;
+        client.execute_json(self.build()?).await
+    }
+}
+
+/// Decodes a JSON response, including an excerpt of the body in the error if
+/// decoding fails.
+pub fn decode_json<T: DeserializeOwned>(url: &str, response: &HttpResponse) -> Result<T> {
+    serde_json::from_slice(&response.body).map_err(|err| Error::Deserialization {
+        message: format!(
+            "Could not parse response from {url}: {err}. Response body: {}",
+            body_excerpt(&response.body)
+        ),
+    })
+}
+
+/// Returns the body of a response as text, truncated to a length that is
+/// suitable for inclusion in error messages.
+pub fn body_excerpt(body: &[u8]) -> String {
+    let body = String::from_utf8_lossy(body);
+    match body.char_indices().nth(MAX_BODY_EXCERPT_LENGTH) {
+        Some((index, _)) => format!("{}...", &body[..index]),
+        None => body.into_owned(),
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::bindings::HttpRequestError;
+    use crate::testing::{block_on, HttpMock, MockHost};
+    use serde_json::{json, Value};
+
+    #[test]
+    fn joins_paths_to_base_url() {
+        let client = HttpClient::builder(parse_url("http://localhost/grafana/").unwrap()).build();
+        assert_eq!(
+            client.url("api/datasources").unwrap().as_str(),
+            "http://localhost/grafana/api/datasources"
+        );
+        assert_eq!(
+            client.url("/api/datasources").unwrap().as_str(),
+            "http://localhost/api/datasources"
+        );
+        assert_eq!(
+            client.url("http://example.com/health").unwrap().as_str(),
+            "http://example.com/health"
+        );
+
+        let request = client
+            .get("api/v1/query")
+            .query("query", "up{job=\"node\"}")
+            .build()
+            .unwrap();
+        assert_eq!(
+            request.url,
+            "http://localhost/grafana/api/v1/query?query=up%7Bjob%3D%22node%22%7D"
+        );
+
+        // Without a trailing slash, the last segment of the base URL is
+        // replaced, as with `Url::join()`.
+        let client = HttpClient::builder(parse_url("http://localhost/grafana").unwrap()).build();
+        assert_eq!(
+            client.url("api/datasources").unwrap().as_str(),
+            "http://localhost/api/datasources"
+        );
+        assert_eq!(client.url("").unwrap().as_str(), "http://localhost/grafana");
+    }
+
+    #[test]
+    fn sends_default_headers_and_decodes_json() {
+        let host = MockHost::new();
+        host.mock(
+            HttpMock::post("http://localhost:9200/_search")
+                .with_header("authorization", "Bearer token")
+                .with_header("content-type", "application/json")
+                .with_body(r#"{"size":30}"#)
+                .respond_with_json(&json!({ "took": 4 })),
+        );
+        let _guard = host.install();
+
+        let client = HttpClient::builder(parse_url("http://localhost:9200").unwrap())
+            .bearer_auth("token")
+            .build();
+        let response: Value =
+            block_on(client.post_json("_search", &json!({ "size": 30 }))).unwrap();
+
+        assert_eq!(response, json!({ "took": 4 }));
+    }
+
+    #[test]
+    fn replaces_headers_regardless_of_case() {
+        let client = HttpClient::builder(parse_url("http://localhost").unwrap()).build();
+        let request = client
+            .post("query")
+            .header("content-type", "text/plain")
+            .json(&json!({}))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            request.headers,
+            Some(BTreeMap::from([(
+                "Content-Type".to_owned(),
+                "application/json".to_owned()
+            )]))
+        );
+    }
+
+    #[test]
+    fn maps_errors() {
+        let host = MockHost::new();
+        host.mock(HttpMock::get("http://localhost/error").respond_with_body(500, "oops"))
+            .mock(HttpMock::get("http://localhost/html").respond_with_body(200, "<html>"));
+        let _guard = host.install();
+
+        let client = HttpClient::builder(parse_url("http://localhost").unwrap()).build();
+        let result = block_on(client.get_json::<Value>("error"));
+        assert!(matches!(
+            result,
+            Err(Error::Http {
+                error: HttpRequestError::ServerError { status_code: 500, response }
+            }) if response.as_ref() == b"oops"
+        ));
+
+        let result = block_on(client.get_json::<Value>("html"));
+        assert!(matches!(
+            result,
+            Err(Error::Deserialization { message }) if message.ends_with("Response body: <html>")
+        ));
+    }
+
+    #[test]
+    fn retries_according_to_policy() {
+        let host = MockHost::new();
+        host.mock(
+            HttpMock::get("http://localhost/flaky")
+                .times(2)
+                .respond_with_body(503, "unavailable"),
+        )
+        .mock(HttpMock::get("http://localhost/flaky").respond_with_json(&json!({ "ok": true })));
+        let _guard = host.install();
+
+        let client = HttpClient::builder(parse_url("http://localhost").unwrap())
+            .retry_policy(RetryPolicy::default())
+            .build();
+        let response: Value = block_on(client.get_json("flaky")).unwrap();
+
+        assert_eq!(response, json!({ "ok": true }));
+        assert_eq!(host.requests().len(), 3);
+        host.assert_all_mocks_called();
+    }
+}
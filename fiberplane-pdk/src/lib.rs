@@ -21,14 +21,36 @@ The main traits and macros you will interact with from this crate are:
 * [`ProviderData`](https://docs.rs/fiberplane-pdk/latest/fiberplane_pdk/provider_data/trait.ProviderData.html) -
   A trait and derive macro for types that you  wish to pass around as provider data using
   [`Blob`s](https://docs.rs/fiberplane-models/latest/fiberplane_models/blobs/struct.Blob.html).
+* [`SelectOptions`](https://docs.rs/fiberplane-pdk/latest/fiberplane_pdk/select_options/trait.SelectOptions.html) -
+  A trait and derive macro for enums that you wish to use as the type of select fields.
 
 */
 
+pub mod auth;
+pub mod cache;
+pub mod concurrency;
+pub mod context;
+pub mod host;
+pub mod http;
+pub mod logging;
+pub mod otel;
+pub mod pagination;
 pub mod panic;
 mod parse_query;
 pub mod prelude;
 pub mod provider_data;
+pub mod retry;
+pub mod secret;
+pub mod select_options;
+pub mod status;
+pub mod templating;
+#[cfg(all(feature = "testing", not(target_arch = "wasm32")))]
+pub mod testing;
 mod types;
+pub mod validation;
+
+// Allows the derive macros to be used within the PDK itself.
+extern crate self as fiberplane_pdk;
 
 // Fiberplane-specific re-exports.
 pub use fiberplane_models::providers;
@@ -39,4 +61,6 @@ pub use parse_query::parse_query;
 
 // Re-exported third-party crates. Provider authors may use these instead of
 // depending on them directly, so they always use the same version as the PDK.
+pub use serde;
 pub use serde_json;
+pub use tracing;
@@ -0,0 +1,296 @@
+//! Structured logging through [`tracing`].
+//!
+//! [`init_logging()`] installs a subscriber that forwards events to the host's
+//! [`log()`] function, so providers can use the `tracing` macros and spans
+//! instead of formatting log messages by hand:
+//!
+//! ```no_run
+//! # use fiberplane_pdk::http::HttpClient;
+//! # use fiberplane_pdk::prelude::*;
+//! # type Series = Vec<fiberplane_pdk::serde_json::Value>;
+//! use fiberplane_pdk::tracing::{debug, info_span, Instrument};
+//!
+//! async fn fetch_series(client: &HttpClient, query: &str) -> Result<Series> {
+//!     debug!(query, "Fetching series");
+//!     client
+//!         .get_json("api/v1/series")
+//!         .instrument(info_span!("fetch_series"))
+//!         .await
+//! }
+//! ```
+//!
+//! Events are logged as a single line with their level, the spans they were
+//! recorded in, their target and their fields:
+//!
+//! ```text
+//! DEBUG fetch_series:http_request{method=Get url=https://example.com/api/v1/series}: fiberplane_pdk::http: Received response status=200
+//! ```
+//!
+//! The verbosity is controlled by a config field of the [`LogLevel`] type,
+//! which is applied when the config is parsed. Events are logged at the `info`
+//! level and above, unless configured otherwise.
+//!
+//! Handlers generated by `pdk_query_types!` call [`init_logging()`]
+//! automatically. Providers that implement `invoke2()` themselves should call
+//! it next to `init_panic_hook()`, before parsing the config.
+//!
+//! [`log()`]: crate::host::log
+
+use crate::host::log;
+use crate::macros::SelectOptions;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Once;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::Interest;
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+/// The level at and above which events are currently logged.
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(LogLevel::Info as u8);
+
+/// The most verbose level of events that are logged.
+///
+/// This can be used as the type of a config field, which is presented as a
+/// select field. `#[derive(ConfigSchema)]` applies the level using
+/// [`set_log_level()`] when the config is parsed:
+///
+/// ```no_run
+/// # use fiberplane_pdk::prelude::*;
+/// # use serde::Deserialize;
+/// #[derive(ConfigSchema, Deserialize)]
+/// #[serde(rename_all = "camelCase")]
+/// pub struct Config {
+///     #[pdk(label = "Log level", select)]
+///     #[serde(default)]
+///     pub log_level: LogLevel,
+/// }
+/// ```
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Deserialize,
+    Eq,
+    Hash,
+    Ord,
+    PartialEq,
+    PartialOrd,
+    SelectOptions,
+    Serialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    #[pdk(label = "Errors only")]
+    Error = 1,
+
+    #[pdk(label = "Warnings and errors")]
+    Warn,
+
+    #[default]
+    Info,
+
+    Debug,
+
+    #[pdk(label = "Trace (very verbose)")]
+    Trace,
+}
+
+impl LogLevel {
+    fn current() -> Self {
+        match MAX_LEVEL.load(Ordering::Relaxed) {
+            1 => Self::Error,
+            2 => Self::Warn,
+            4 => Self::Debug,
+            5 => Self::Trace,
+            _ => Self::Info,
+        }
+    }
+
+    fn enables(self, level: &Level) -> bool {
+        let event_level = match *level {
+            Level::ERROR => Self::Error,
+            Level::WARN => Self::Warn,
+            Level::INFO => Self::Info,
+            Level::DEBUG => Self::Debug,
+            Level::TRACE => Self::Trace,
+        };
+        event_level <= self
+    }
+}
+
+/// Installs the subscriber that forwards `tracing` events to the host, and
+/// resets the log level to the default.
+///
+/// The subscriber is only installed once, but the log level is reset on every
+/// call, since the config of the next request may not set it.
+pub fn init_logging() {
+    set_log_level(LogLevel::default());
+
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        // This fails if the provider installed a subscriber of its own, which
+        // we respect.
+        let _ = tracing::subscriber::set_global_default(subscriber());
+    });
+}
+
+/// Sets the most verbose level of events that are logged, until the next call
+/// to [`init_logging()`].
+pub fn set_log_level(level: LogLevel) {
+    MAX_LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+/// Returns a subscriber that forwards events to the host's `log()` function,
+/// using the level set by [`init_logging()`].
+pub fn subscriber() -> impl Subscriber + Send + Sync {
+    tracing_subscriber::registry().with(HostLogLayer)
+}
+
+/// Layer that formats events and forwards them to the host.
+struct HostLogLayer;
+
+/// Formatted fields of a span, stored in the span's extensions.
+struct SpanFields(String);
+
+impl<S> Layer<S> for HostLogLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn register_callsite(&self, _metadata: &'static Metadata<'static>) -> Interest {
+        // The level may change between requests, so we cannot let `tracing`
+        // cache whether a callsite is enabled.
+        Interest::sometimes()
+    }
+
+    fn enabled(&self, metadata: &Metadata<'_>, _ctx: Context<'_, S>) -> bool {
+        LogLevel::current().enables(metadata.level())
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut fields = FieldFormatter::default();
+        attrs.record(&mut fields);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanFields(fields.fields));
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+
+        let mut extensions = span.extensions_mut();
+        if let Some(SpanFields(fields)) = extensions.get_mut::<SpanFields>() {
+            let mut formatter = FieldFormatter {
+                fields: std::mem::take(fields),
+                ..Default::default()
+            };
+            values.record(&mut formatter);
+            *fields = formatter.fields;
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut line = format!("{} ", metadata.level());
+
+        let spans = ctx
+            .event_scope(event)
+            .into_iter()
+            .flat_map(|scope| scope.from_root());
+        for span in spans {
+            line.push_str(span.name());
+            if let Some(SpanFields(fields)) = span.extensions().get::<SpanFields>() {
+                if !fields.is_empty() {
+                    let _ = write!(line, "{{{fields}}}");
+                }
+            }
+            line.push(':');
+        }
+        if line.ends_with(':') {
+            line.push(' ');
+        }
+
+        let mut fields = FieldFormatter::default();
+        event.record(&mut fields);
+
+        let _ = write!(line, "{}: {}", metadata.target(), fields.message);
+        if !fields.fields.is_empty() {
+            let _ = write!(line, " {}", fields.fields);
+        }
+
+        log(line);
+    }
+}
+
+/// Formats fields as space-separated `name=value` pairs, keeping the message
+/// separate.
+#[derive(Default)]
+struct FieldFormatter {
+    message: String,
+    fields: String,
+}
+
+impl Visit for FieldFormatter {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.record_debug(field, &format_args!("{value}"))
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            if !self.fields.is_empty() {
+                self.fields.push(' ');
+            }
+            let _ = write!(self.fields, "{}={value:?}", field.name());
+        }
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::testing::MockHost;
+    use tracing::{debug, info, info_span, warn};
+
+    #[test]
+    fn forwards_events_to_the_host() {
+        let host = MockHost::new();
+        let _guard = host.install();
+
+        // Both cases are tested together, since the log level is global. The
+        // subscriber is only installed for this thread, so the test doesn't
+        // install a global subscriber through `init_logging()`.
+        set_log_level(LogLevel::default());
+        tracing::subscriber::with_default(subscriber(), || {
+            let span = info_span!("request", query_type = "x-instants");
+            let _entered = span.enter();
+
+            info!(status = 200, path = "api/v1/query", "Received response");
+            debug!("Not logged at the default level");
+        });
+
+        set_log_level(LogLevel::Warn);
+        tracing::subscriber::with_default(subscriber(), || {
+            info!("Not logged");
+            warn!(count = 3, "Retrying");
+        });
+        set_log_level(LogLevel::default());
+
+        assert_eq!(
+            host.logs(),
+            vec![
+                "INFO request{query_type=x-instants}: fiberplane_pdk::logging::tests: \
+                 Received response status=200 path=api/v1/query"
+                    .to_owned(),
+                "WARN fiberplane_pdk::logging::tests: Retrying count=3".to_owned(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,313 @@
+//! Mapping log records to OpenTelemetry metadata.
+//!
+//! Log providers receive records as (nested) JSON, but events carry their
+//! fields as two flat maps: the resource, which describes the entity that
+//! produced the record, and the attributes of the record itself. This module
+//! helps to map records to that shape consistently:
+//!
+//! * A [`Flattener`] flattens nested objects and arrays into a single map, with
+//!   keys such as `service.name` and `tags[0]`.
+//! * A [`ResourceClassifier`] splits flattened fields into resource fields and
+//!   attributes, based on the namespaces of the OpenTelemetry resource
+//!   semantic conventions, such as `service.*`, `host.*` and `k8s.*`.
+//!
+//! ```
+//! use fiberplane_pdk::otel::{Flattener, ResourceClassifier};
+//! use fiberplane_pdk::serde_json::json;
+//!
+//! let fields = Flattener::new().flatten(json!({
+//!     "service": { "name": "checkout" },
+//!     "http": { "response": { "status_code": 402 } },
+//! }));
+//! let otel = ResourceClassifier::new().to_metadata(fields);
+//!
+//! assert_eq!(otel.resource["service.name"], json!("checkout"));
+//! assert_eq!(otel.attributes["http.response.status_code"], json!(402));
+//! ```
+
+use crate::bindings::OtelMetadata;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Namespaces of the OpenTelemetry resource semantic conventions.
+///
+/// See: https://opentelemetry.io/docs/specs/semconv/resource/
+pub const RESOURCE_NAMESPACES: &[&str] = &[
+    "cloud",
+    "container",
+    "deployment",
+    "device",
+    "faas",
+    "host",
+    "k8s",
+    "os",
+    "process",
+    "service",
+    "telemetry",
+    "webengine",
+];
+
+/// Flattens nested JSON values into a map of which the keys are the paths to
+/// the values.
+///
+/// By default, object keys are joined with a dot and array indices are
+/// appended in brackets: `{"a": {"b": [1]}}` becomes `{"a.b[0]": 1}`.
+#[derive(Clone, Debug)]
+pub struct Flattener {
+    object_separator: String,
+    array_prefix: String,
+    array_suffix: String,
+}
+
+impl Default for Flattener {
+    fn default() -> Self {
+        Self {
+            object_separator: ".".to_owned(),
+            array_prefix: "[".to_owned(),
+            array_suffix: "]".to_owned(),
+        }
+    }
+}
+
+impl Flattener {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the separator between the keys of nested objects.
+    pub fn with_object_separator(self, separator: impl Into<String>) -> Self {
+        Self {
+            object_separator: separator.into(),
+            ..self
+        }
+    }
+
+    /// Sets the text that is put before and after array indices.
+    ///
+    /// Use `(".", "")` to format indices like object keys, as in `tags.0`.
+    pub fn with_array_index_format(
+        self,
+        prefix: impl Into<String>,
+        suffix: impl Into<String>,
+    ) -> Self {
+        Self {
+            array_prefix: prefix.into(),
+            array_suffix: suffix.into(),
+            ..self
+        }
+    }
+
+    /// Flattens the fields of the given object.
+    ///
+    /// Values other than objects are stored under an empty key.
+    pub fn flatten(&self, value: Value) -> BTreeMap<String, Value> {
+        let mut output = BTreeMap::new();
+        match value {
+            Value::Object(object) => {
+                for (key, value) in object {
+                    self.flatten_into(&mut output, key, value);
+                }
+            }
+            other => self.flatten_into(&mut output, String::new(), other),
+        }
+        output
+    }
+
+    /// Flattens the value into the given map, prefixing its keys with the
+    /// given key.
+    pub fn flatten_into(&self, output: &mut BTreeMap<String, Value>, key: String, value: Value) {
+        match value {
+            Value::Object(object) => {
+                for (sub_key, value) in object {
+                    let key = format!("{key}{}{sub_key}", self.object_separator);
+                    self.flatten_into(output, key, value);
+                }
+            }
+            Value::Array(array) => {
+                for (index, value) in array.into_iter().enumerate() {
+                    let key = format!("{key}{}{index}{}", self.array_prefix, self.array_suffix);
+                    self.flatten_into(output, key, value);
+                }
+            }
+            primitive => {
+                output.insert(key, primitive);
+            }
+        }
+    }
+}
+
+/// Decides which flattened fields describe the resource that produced a
+/// record, as opposed to the record itself.
+///
+/// Fields are part of the resource if their key starts with one of the
+/// namespaces, followed by a separator, as in `service.name`. By default, the
+/// [`RESOURCE_NAMESPACES`] are used.
+#[derive(Clone, Debug)]
+pub struct ResourceClassifier {
+    namespaces: Vec<String>,
+    exceptions: Vec<String>,
+    separators: Vec<String>,
+}
+
+impl Default for ResourceClassifier {
+    fn default() -> Self {
+        Self {
+            namespaces: RESOURCE_NAMESPACES
+                .iter()
+                .map(|ns| (*ns).to_owned())
+                .collect(),
+            exceptions: Vec::new(),
+            separators: vec![".".to_owned()],
+        }
+    }
+}
+
+impl ResourceClassifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the namespaces of which the fields are part of the resource,
+    /// replacing the [`RESOURCE_NAMESPACES`].
+    ///
+    /// This is useful for data that follows another schema, such as the
+    /// Elastic Common Schema.
+    pub fn with_namespaces<I>(self, namespaces: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self {
+            namespaces: namespaces.into_iter().map(Into::into).collect(),
+            ..self
+        }
+    }
+
+    /// Adds keys that are attributes even though they are in one of the
+    /// resource namespaces.
+    pub fn with_exceptions<I>(mut self, exceptions: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.exceptions
+            .extend(exceptions.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets the separators that may follow a namespace.
+    ///
+    /// Services that don't allow dots in field names, such as Loki for its
+    /// labels, typically replace them with underscores, as in `service_name`.
+    pub fn with_separators<I>(self, separators: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        Self {
+            separators: separators
+                .into_iter()
+                .map(Into::into)
+                .filter(|separator: &String| !separator.is_empty())
+                .collect(),
+            ..self
+        }
+    }
+
+    /// Returns whether the field with the given key is part of the resource.
+    pub fn is_resource(&self, key: &str) -> bool {
+        let has_prefix = |prefix: &str| match key.strip_prefix(prefix) {
+            Some(rest) => self
+                .separators
+                .iter()
+                .any(|separator| rest.starts_with(separator.as_str())),
+            None => false,
+        };
+
+        self.namespaces
+            .iter()
+            .any(|namespace| has_prefix(namespace))
+            && !self.exceptions.iter().any(|exception| key == exception)
+    }
+
+    /// Splits the fields into the resource and the attributes.
+    pub fn partition<I>(&self, fields: I) -> (BTreeMap<String, Value>, BTreeMap<String, Value>)
+    where
+        I: IntoIterator<Item = (String, Value)>,
+    {
+        fields
+            .into_iter()
+            .partition(|(key, _)| self.is_resource(key))
+    }
+
+    /// Returns the metadata for a record with the given fields.
+    ///
+    /// The trace and span IDs are left empty.
+    pub fn to_metadata<I>(&self, fields: I) -> OtelMetadata
+    where
+        I: IntoIterator<Item = (String, Value)>,
+    {
+        let (resource, attributes) = self.partition(fields);
+        OtelMetadata::builder()
+            .attributes(attributes)
+            .resource(resource)
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn flattens_nested_values() {
+        let flattener = Flattener::new();
+        let mut fields = BTreeMap::new();
+        flattener.flatten_into(&mut fields, "a".to_owned(), json!(1));
+        assert_eq!(fields.get("a").unwrap(), &json!(1));
+
+        flattener.flatten_into(&mut fields, "b".to_owned(), json!({ "c": true }));
+        assert_eq!(fields.get("b.c").unwrap(), &json!(true));
+
+        flattener.flatten_into(&mut fields, "e.f".to_owned(), json!({ "g": { "h": null } }));
+        assert_eq!(fields.get("e.f.g.h").unwrap(), &json!(null));
+
+        flattener.flatten_into(&mut fields, "j.arr".to_owned(), json!(["apple", "banana"]));
+        assert_eq!(fields.get("j.arr[0]").unwrap(), "apple");
+        assert_eq!(fields.get("j.arr[1]").unwrap(), "banana");
+
+        let fields = Flattener::new()
+            .with_object_separator("_")
+            .with_array_index_format("_", "")
+            .flatten(json!({ "k8s": { "pod": { "names": ["a"] } } }));
+        assert_eq!(
+            fields,
+            BTreeMap::from([("k8s_pod_names_0".to_owned(), json!("a"))])
+        );
+    }
+
+    #[test]
+    fn classifies_resource_fields() {
+        let classifier = ResourceClassifier::new();
+        assert!(classifier.is_resource("service.name"));
+        assert!(classifier.is_resource("k8s.pod.name"));
+        assert!(!classifier.is_resource("agent.version"));
+        assert!(!classifier.is_resource("service"));
+        assert!(!classifier.is_resource("services.name"));
+        assert!(!classifier.is_resource("http.response.status_code"));
+
+        let classifier = ResourceClassifier::new()
+            .with_namespaces(["agent", "container", "host"])
+            .with_exceptions(["host.uptime", "container.labels"]);
+        assert!(classifier.is_resource("agent.version"));
+        assert!(classifier.is_resource("container.labels.team"));
+        assert!(!classifier.is_resource("k8s.pod.name"));
+        assert!(!classifier.is_resource("host.uptime"));
+        assert!(!classifier.is_resource("container.labels"));
+
+        let classifier = ResourceClassifier::new().with_separators([".", "_"]);
+        assert!(classifier.is_resource("service_name"));
+        assert!(!classifier.is_resource("job"));
+    }
+}
@@ -0,0 +1,344 @@
+//! Fetching results that are spread across multiple pages.
+//!
+//! A [`Paginator`] calls a function that fetches a single page until all
+//! results have been fetched, the limit on the number of results is reached, or
+//! the budget of requests is spent. Every page yields its items and the cursor
+//! of the next page, if there is one. The cursor can be anything that
+//! identifies the next page, so the strategies of different APIs can be
+//! supported:
+//!
+//! * Continuation tokens that are returned with every page:
+//!   [`Page::with_token()`].
+//! * Offsets from the start of the results: [`Page::with_offset()`].
+//! * The sort key of the last item, for APIs that support `search_after` or
+//!   that can be queried for results before a given timestamp:
+//!   [`Page::after_last()`].
+//! * Cursors that are returned in a `Link` header: [`next_link()`].
+//!
+//! ```no_run
+//! # async fn example() -> fiberplane_pdk::prelude::Result<()> {
+//! use fiberplane_pdk::http::{parse_url, HttpClient};
+//! use fiberplane_pdk::pagination::{Page, Paginator};
+//! use fiberplane_pdk::serde_json::Value;
+//!
+//! let client = HttpClient::builder(parse_url("http://localhost:8080")?).build();
+//!
+//! // Fetches up to 250 items, 100 at a time.
+//! let items: Vec<Value> = Paginator::new(100)
+//!     .with_limit(250)
+//!     .fetch(0, |offset, page_size| {
+//!         let client = &client;
+//!         async move {
+//!             let items: Vec<Value> = client
+//!                 .get("api/items")
+//!                 .query("offset", offset.to_string())
+//!                 .query("limit", page_size.to_string())
+//!                 .send_json()
+//!                 .await?;
+//!             Ok(Page::with_offset(items, offset, page_size))
+//!         }
+//!     })
+//!     .await?
+//!     .items;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::types::Result;
+use std::collections::BTreeMap;
+use std::future::Future;
+
+/// The default maximum number of requests made by a [`Paginator`].
+pub const DEFAULT_MAX_REQUESTS: usize = 10;
+
+/// A page of results, together with the cursor of the next page.
+///
+/// This is also returned by [`Paginator::fetch()`], in which case it contains
+/// the items of all the fetched pages.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Page<T, C> {
+    pub items: Vec<T>,
+
+    /// The cursor of the next page, or `None` if there are no more results.
+    pub next: Option<C>,
+}
+
+impl<T, C> Page<T, C> {
+    pub fn new(items: Vec<T>, next: Option<C>) -> Self {
+        Self { items, next }
+    }
+
+    /// Returns a page that is known to be the last one.
+    pub fn last(items: Vec<T>) -> Self {
+        Self { items, next: None }
+    }
+
+    /// Returns a page of which the next page starts after its last item.
+    ///
+    /// If the page is not full, it is assumed to be the last one. Otherwise,
+    /// the cursor of the next page is derived from the last item, such as
+    /// its `search_after` sort values or its timestamp.
+    pub fn after_last(items: Vec<T>, page_size: usize, cursor: impl FnOnce(&T) -> C) -> Self {
+        let next = match items.last() {
+            Some(last) if items.len() >= page_size => Some(cursor(last)),
+            _ => None,
+        };
+        Self { items, next }
+    }
+}
+
+impl<T> Page<T, String> {
+    /// Returns a page with a continuation token for the next page.
+    ///
+    /// Empty tokens are treated the same as missing tokens, since some APIs
+    /// return those on the last page.
+    pub fn with_token(items: Vec<T>, token: Option<String>) -> Self {
+        Self {
+            items,
+            next: token.filter(|token| !token.is_empty()),
+        }
+    }
+}
+
+impl<T> Page<T, usize> {
+    /// Returns a page that was fetched at the given offset.
+    ///
+    /// If the page is not full, it is assumed to be the last one.
+    pub fn with_offset(items: Vec<T>, offset: usize, page_size: usize) -> Self {
+        let next = (items.len() >= page_size).then(|| offset + items.len());
+        Self { items, next }
+    }
+}
+
+/// Fetches pages of results until enough results have been fetched.
+#[derive(Clone, Copy, Debug)]
+#[must_use = "nothing is fetched unless `fetch()` is called"]
+pub struct Paginator {
+    page_size: usize,
+    limit: Option<usize>,
+    max_requests: usize,
+}
+
+impl Paginator {
+    /// Creates a paginator that fetches pages of the given size, without a
+    /// limit on the number of results.
+    pub fn new(page_size: usize) -> Self {
+        Self {
+            page_size: page_size.max(1),
+            limit: None,
+            max_requests: DEFAULT_MAX_REQUESTS,
+        }
+    }
+
+    /// Sets the maximum number of results to fetch.
+    pub fn with_limit(self, limit: usize) -> Self {
+        Self {
+            limit: Some(limit),
+            ..self
+        }
+    }
+
+    /// Sets the maximum number of requests to make, even if more results are
+    /// available.
+    pub fn with_max_requests(self, max_requests: usize) -> Self {
+        Self {
+            max_requests,
+            ..self
+        }
+    }
+
+    /// Fetches pages, starting at the given cursor.
+    ///
+    /// The given function is called with the cursor of the page to fetch and
+    /// the number of results to fetch, which is never larger than the page
+    /// size, but may be smaller for the last page within the limit.
+    ///
+    /// Returns the fetched items, truncated to the limit, together with the
+    /// cursor of the page after the last fetched page, if there are more
+    /// results. Errors are returned as soon as a page cannot be fetched.
+    pub async fn fetch<T, C, F, Fut>(&self, cursor: C, mut fetch_page: F) -> Result<Page<T, C>>
+    where
+        F: FnMut(C, usize) -> Fut,
+        Fut: Future<Output = Result<Page<T, C>>>,
+    {
+        let mut items = Vec::new();
+        let mut next = Some(cursor);
+        let mut requests = 0;
+
+        while requests < self.max_requests {
+            let remaining = self
+                .limit
+                .map_or(usize::MAX, |limit| limit.saturating_sub(items.len()));
+            if remaining == 0 {
+                break;
+            }
+
+            let Some(cursor) = next.take() else {
+                break;
+            };
+
+            let page = fetch_page(cursor, remaining.min(self.page_size)).await?;
+            requests += 1;
+
+            items.extend(page.items);
+            next = page.next;
+        }
+
+        if let Some(limit) = self.limit {
+            items.truncate(limit);
+        }
+
+        Ok(Page { items, next })
+    }
+}
+
+/// A link from a `Link` header, as specified in RFC 8288.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Link {
+    pub url: String,
+    pub params: BTreeMap<String, String>,
+}
+
+impl Link {
+    /// Returns the value of the given parameter, such as `rel`.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+}
+
+/// Returns the link to the next page from the value of a `Link` header.
+///
+/// Links that have a `results="false"` parameter are ignored, since APIs
+/// such as Sentry's use that to indicate the next page is empty.
+pub fn next_link(header: &str) -> Option<Link> {
+    parse_links(header)
+        .into_iter()
+        .find(|link| link.param("rel") == Some("next") && link.param("results") != Some("false"))
+}
+
+/// Parses the value of a `Link` header.
+pub fn parse_links(header: &str) -> Vec<Link> {
+    let mut links = Vec::new();
+    let mut rest = header;
+    while let Some(start) = rest.find('<') {
+        let Some(len) = rest[start..].find('>') else {
+            break;
+        };
+        let url = rest[start + 1..start + len].to_owned();
+        rest = &rest[start + len + 1..];
+
+        let params_end = rest.find('<').unwrap_or(rest.len());
+        let params = rest[..params_end]
+            .split(';')
+            .filter_map(|param| param.trim().trim_end_matches(',').split_once('='))
+            .map(|(name, value)| {
+                let value = value.trim().trim_matches('"');
+                (name.trim().to_ascii_lowercase(), value.to_owned())
+            })
+            .collect();
+        rest = &rest[params_end..];
+
+        links.push(Link { url, params });
+    }
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_cursors_from_pages() {
+        let page = Page::with_token(vec![1, 2], Some(String::new()));
+        assert_eq!(page.next, None);
+
+        let page = Page::with_offset(vec![1, 2], 4, 2);
+        assert_eq!(page.next, Some(6));
+        let page = Page::with_offset(vec![1], 6, 2);
+        assert_eq!(page.next, None);
+
+        let page = Page::after_last(vec![30, 20, 10], 3, |last| *last);
+        assert_eq!(page.next, Some(10));
+        let page = Page::after_last(Vec::<u32>::new(), 3, |last| *last);
+        assert_eq!(page.next, None);
+    }
+
+    #[test]
+    fn finds_next_link() {
+        let header = r#"<https://sentry.io/api/0/issues/?&cursor=0:0:1>; rel="previous"; results="false"; cursor="0:0:1", <https://sentry.io/api/0/issues/?&cursor=0:100:0>; rel="next"; results="true"; cursor="0:100:0""#;
+        let link = next_link(header).unwrap();
+        assert_eq!(link.url, "https://sentry.io/api/0/issues/?&cursor=0:100:0");
+        assert_eq!(link.param("cursor"), Some("0:100:0"));
+
+        let header =
+            r#"<https://sentry.io/api/0/issues/?&cursor=0:100:0>; rel="next"; results="false""#;
+        assert_eq!(next_link(header), None);
+    }
+
+    #[cfg(feature = "testing")]
+    mod fetch {
+        use super::*;
+        use crate::bindings::Error;
+        use crate::testing::block_on;
+        use std::cell::RefCell;
+
+        /// Serves pages of the numbers up to 25 and records the requests.
+        fn fetch_numbers(
+            requests: &RefCell<Vec<(usize, usize)>>,
+        ) -> impl FnMut(usize, usize) -> std::future::Ready<Result<Page<usize, usize>>> + '_
+        {
+            move |offset, page_size| {
+                requests.borrow_mut().push((offset, page_size));
+                let items = (offset..25).take(page_size).collect();
+                std::future::ready(Ok(Page::with_offset(items, offset, page_size)))
+            }
+        }
+
+        #[test]
+        fn fetches_until_limit() {
+            let requests = RefCell::new(Vec::new());
+            let page = block_on(
+                Paginator::new(10)
+                    .with_limit(15)
+                    .fetch(0, fetch_numbers(&requests)),
+            )
+            .unwrap();
+
+            assert_eq!(page.items, (0..15).collect::<Vec<_>>());
+            assert_eq!(page.next, Some(15));
+            assert_eq!(requests.into_inner(), vec![(0, 10), (10, 5)]);
+        }
+
+        #[test]
+        fn fetches_until_last_page() {
+            let requests = RefCell::new(Vec::new());
+            let page = block_on(Paginator::new(10).fetch(0, fetch_numbers(&requests))).unwrap();
+
+            assert_eq!(page.items, (0..25).collect::<Vec<_>>());
+            assert_eq!(page.next, None);
+            assert_eq!(requests.into_inner().len(), 3);
+        }
+
+        #[test]
+        fn respects_request_budget() {
+            let requests = RefCell::new(Vec::new());
+            let page = block_on(
+                Paginator::new(5)
+                    .with_max_requests(2)
+                    .fetch(0, fetch_numbers(&requests)),
+            )
+            .unwrap();
+
+            assert_eq!(page.items, (0..10).collect::<Vec<_>>());
+            assert_eq!(page.next, Some(10));
+        }
+
+        #[test]
+        fn returns_errors() {
+            let result = block_on(Paginator::new(5).fetch(0, |_, _| async {
+                Err::<Page<usize, usize>, _>(Error::UnsupportedRequest)
+            }));
+            assert!(matches!(result, Err(Error::UnsupportedRequest)));
+        }
+    }
+}
@@ -2,10 +2,23 @@
 //! sets up the panic handler so that unrecoverable
 //! errors from the provider show better messages.
 
-use fiberplane_provider_bindings::log;
+use crate::host::log;
+use crate::secret::clear_known_secrets;
 use std::panic;
 
+/// Sets up the panic handler, if that didn't happen yet.
+///
+/// This is called at the start of every invocation, so it also forgets the
+/// secrets that were registered by earlier invocations.
 pub fn init_panic_hook() {
+    clear_known_secrets();
+
+    // Native tests should keep the default hook, so panics end up in the test
+    // output instead of the mock host's captured logs.
+    if cfg!(all(feature = "testing", not(target_arch = "wasm32"))) {
+        return;
+    }
+
     use std::sync::Once;
     static SET_HOOK: Once = Once::new();
     SET_HOOK.call_once(|| {
@@ -1,6 +1,15 @@
+pub use crate::auth::Auth;
 pub use crate::bindings::*;
+pub use crate::context::{FromConfig, FromRequest, QueryContext};
+pub use crate::host::{log, make_http_request, now}; // shadow the bindings
+pub use crate::logging::{init_logging, set_log_level, LogLevel};
 pub use crate::macros::*;
 pub use crate::panic::*;
-pub use crate::provider_data::ProviderData;
+pub use crate::pdk_build_info;
+pub use crate::provider_data::{Encoding, ProviderData};
 pub use crate::providers::{ConfigSchema, ProviderConfig, QuerySchema, *}; // avoid ambiguity
+pub use crate::retry::RetryPolicy;
+pub use crate::secret::Secret;
+pub use crate::select_options::SelectOptions;
+pub use crate::status::{BuildInfo, StatusReport};
 pub use crate::types::*;
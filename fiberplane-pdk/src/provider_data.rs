@@ -8,60 +8,259 @@ use serde::{de::DeserializeOwned, Serialize};
 ///
 /// Implementations can be derived using the `ProviderData` derive macro.
 pub trait ProviderData: Sized {
+    /// The MIME type of the data, without the suffix for its encoding.
+    const MIME_TYPE: &'static str;
+
+    /// The encoding used by `to_blob()`.
+    ///
+    /// Can be overridden with `#[pdk(encoding = ...)]` when deriving.
+    const ENCODING: Encoding = Encoding::MessagePack;
+
     /// Parses a `Blob` with the correct MIME type into the struct implementing
     /// this trait.
     fn parse_blob(blob: Blob) -> Result<Self>;
 
     /// Serializes an instance of this type and stores the result in a `Blob`.
-    fn to_blob(&self) -> Result<Blob>;
+    fn to_blob(&self) -> Result<Blob> {
+        self.to_blob_with_encoding(Self::ENCODING)
+    }
+
+    /// Serializes an instance of this type using the given encoding and stores
+    /// the result in a `Blob`.
+    fn to_blob_with_encoding(&self, encoding: Encoding) -> Result<Blob>;
 }
 
 impl ProviderData for ProviderStatus {
+    const MIME_TYPE: &'static str = STATUS_MIME_TYPE;
+
     fn parse_blob(blob: Blob) -> Result<Self> {
-        parse_blob(STATUS_MIME_TYPE, blob)
+        parse_blob(Self::MIME_TYPE, blob)
     }
 
-    fn to_blob(&self) -> Result<Blob> {
-        to_blob(STATUS_MIME_TYPE, &self)
+    fn to_blob_with_encoding(&self, encoding: Encoding) -> Result<Blob> {
+        to_blob_with_encoding(Self::MIME_TYPE, &self, encoding)
     }
 }
 
-/// Parses a `Blob` with the correct MIME type into a custom struct.
+/// The encoding of the data inside a `Blob`.
 ///
-/// You probably want to use the `ProviderData` derive macro and use the
-/// struct's `parse()` method.
-pub fn parse_blob<T: DeserializeOwned>(mime_type: &str, blob: Blob) -> Result<T> {
-    if blob.mime_type == format!("{mime_type}+msgpack") {
-        return rmp_serde::from_read(blob.data.as_ref()).map_err(|err| Error::Data {
-            message: format!("Could not parse blob: {err}"),
-        });
+/// The encoding is indicated by a suffix on the MIME type of the blob, such as
+/// `+json` or `+msgpack`.
+///
+/// Variants depend on the enabled features of the PDK, so matches on the
+/// encoding need a wildcard arm.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Encoding {
+    Json,
+    #[default]
+    MessagePack,
+    #[cfg(feature = "cbor")]
+    Cbor,
+}
+
+/// Expands to `Encoding::Cbor`, or to a compile error if the `cbor` feature is
+/// not enabled. Used by `#[derive(ProviderData)]`, which cannot tell which
+/// features of the PDK are enabled.
+#[cfg(feature = "cbor")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pdk_cbor_encoding {
+    () => {
+        $crate::provider_data::Encoding::Cbor
+    };
+}
+
+#[cfg(not(feature = "cbor"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __pdk_cbor_encoding {
+    () => {
+        compile_error!("`encoding = cbor` requires the `cbor` feature of `fiberplane-pdk`")
+    };
+}
+
+impl Encoding {
+    /// Returns the suffix that is appended to a MIME type to indicate this
+    /// encoding.
+    pub const fn suffix(self) -> &'static str {
+        match self {
+            Self::Json => "+json",
+            Self::MessagePack => "+msgpack",
+            #[cfg(feature = "cbor")]
+            Self::Cbor => "+cbor",
+        }
+    }
+
+    /// Returns the given MIME type with the suffix for this encoding.
+    pub fn mime_type(self, mime_type: &str) -> String {
+        format!("{mime_type}{}", self.suffix())
     }
 
-    if blob.mime_type == format!("{mime_type}+json") {
-        return serde_json::from_reader(blob.data.as_ref()).map_err(|err| Error::Data {
+    /// Determines the encoding of a blob with the given MIME type, given the
+    /// MIME type of the data it is expected to contain.
+    ///
+    /// A blob without an encoding suffix is assumed to be encoded as JSON.
+    /// Returns `None` if the MIME type doesn't match.
+    pub fn from_mime_type(blob_mime_type: &str, mime_type: &str) -> Option<Self> {
+        match blob_mime_type.strip_prefix(mime_type)? {
+            "" | "+json" => Some(Self::Json),
+            "+msgpack" => Some(Self::MessagePack),
+            #[cfg(feature = "cbor")]
+            "+cbor" => Some(Self::Cbor),
+            _ => None,
+        }
+    }
+
+    /// Serializes the given data using this encoding.
+    pub fn encode<T: Serialize>(self, data: &T) -> Result<Vec<u8>> {
+        match self {
+            Self::Json => Ok(serde_json::to_vec(data)?),
+            Self::MessagePack => Ok(rmp_serde::to_vec_named(data)?),
+            #[cfg(feature = "cbor")]
+            Self::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::ser::into_writer(data, &mut bytes).map_err(|err| Error::Data {
+                    message: format!("Could not serialize blob: {err}"),
+                })?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Deserializes the given data using this encoding.
+    pub fn decode<T: DeserializeOwned>(self, data: &[u8]) -> Result<T> {
+        let result = match self {
+            Self::Json => serde_json::from_slice(data).map_err(|err| err.to_string()),
+            Self::MessagePack => rmp_serde::from_slice(data).map_err(|err| err.to_string()),
+            #[cfg(feature = "cbor")]
+            Self::Cbor => ciborium::de::from_reader(data).map_err(|err| err.to_string()),
+        };
+        result.map_err(|err| Error::Data {
             message: format!("Could not parse blob: {err}"),
-        });
+        })
     }
+}
 
-    Err(Error::Data {
-        message: format!("Incorrect MIME type: {}", blob.mime_type),
-    })
+/// Parses a `Blob` with the correct MIME type into a custom struct.
+///
+/// The blob may use any of the supported encodings. A blob without an encoding
+/// suffix on its MIME type is parsed as JSON.
+///
+/// You probably want to use the `ProviderData` derive macro and use the
+/// struct's `parse_blob()` method.
+pub fn parse_blob<T: DeserializeOwned>(mime_type: &str, blob: Blob) -> Result<T> {
+    let Some(encoding) = Encoding::from_mime_type(&blob.mime_type, mime_type) else {
+        return Err(Error::Data {
+            message: format!("Incorrect MIME type: {}", blob.mime_type),
+        });
+    };
+
+    encoding.decode(&blob.data)
 }
 
-/// Serializes a custom struct and stores the result in a `Blob`.
+/// Serializes a custom struct using the default encoding and stores the result
+/// in a `Blob`.
 ///
 /// You probably want to use the `ProviderData` derive macro and use the
-/// struct's `serialize()` method.
+/// struct's `to_blob()` method.
 pub fn to_blob<T: Serialize>(mime_type: &str, data: &T) -> Result<Blob> {
-    if cfg!(debug_assertions) {
-        Ok(Blob::builder()
-            .data(serde_json::to_vec(data)?)
-            .mime_type(format!("{mime_type}+json"))
-            .build())
-    } else {
-        Ok(Blob::builder()
-            .data(rmp_serde::to_vec_named(data)?)
-            .mime_type(format!("{mime_type}+msgpack"))
-            .build())
+    to_blob_with_encoding(mime_type, data, Encoding::default())
+}
+
+/// Serializes a custom struct using the given encoding and stores the result
+/// in a `Blob`.
+pub fn to_blob_with_encoding<T: Serialize>(
+    mime_type: &str,
+    data: &T,
+    encoding: Encoding,
+) -> Result<Blob> {
+    Ok(Blob::builder()
+        .data(encoding.encode(data)?)
+        .mime_type(encoding.mime_type(mime_type))
+        .build())
+}
+
+/// Returns whether `mime_type` is one of the given MIME types.
+///
+/// This function can be evaluated at compile time, which `pdk_query_types!`
+/// uses to verify that the data returned by a handler matches the supported
+/// MIME types of its query type.
+pub const fn contains_mime_type(mime_types: &[&str], mime_type: &str) -> bool {
+    let mut i = 0;
+    while i < mime_types.len() {
+        if str_eq(mime_types[i], mime_type) {
+            return true;
+        }
+        i += 1;
+    }
+    false
+}
+
+pub(crate) const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::{EVENTS_MIME_TYPE, TIMESERIES_MIME_TYPE};
+    use serde::Deserialize;
+
+    #[test]
+    fn contains_mime_type_compares_whole_mime_types() {
+        const SUPPORTED: &[&str] = &[EVENTS_MIME_TYPE, STATUS_MIME_TYPE];
+
+        assert!(contains_mime_type(SUPPORTED, STATUS_MIME_TYPE));
+        assert!(!contains_mime_type(SUPPORTED, TIMESERIES_MIME_TYPE));
+        assert!(!contains_mime_type(SUPPORTED, &STATUS_MIME_TYPE[1..]));
+        assert!(!contains_mime_type(&[], STATUS_MIME_TYPE));
+    }
+
+    #[test]
+    fn parse_blob_accepts_every_encoding() {
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct Data {
+            value: u32,
+        }
+
+        const MIME_TYPE: &str = "application/vnd.fiberplane.test";
+        let data = Data { value: 42 };
+
+        let encodings = [
+            Encoding::Json,
+            Encoding::MessagePack,
+            #[cfg(feature = "cbor")]
+            Encoding::Cbor,
+        ];
+        for encoding in encodings {
+            let blob = to_blob_with_encoding(MIME_TYPE, &data, encoding).unwrap();
+            assert_eq!(blob.mime_type, encoding.mime_type(MIME_TYPE));
+            assert_eq!(parse_blob::<Data>(MIME_TYPE, blob).unwrap(), data);
+        }
+
+        let bare = Blob::builder()
+            .data(br#"{"value":42}"#.to_vec())
+            .mime_type(MIME_TYPE.to_owned())
+            .build();
+        assert_eq!(parse_blob::<Data>(MIME_TYPE, bare).unwrap(), data);
+
+        let other = Blob::builder()
+            .data(Vec::new())
+            .mime_type(format!("{MIME_TYPE}+xml"))
+            .build();
+        assert!(parse_blob::<Data>(MIME_TYPE, other).is_err());
     }
 }
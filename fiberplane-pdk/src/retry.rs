@@ -0,0 +1,204 @@
+//! Retrying requests that fail for transient reasons.
+//!
+//! A [`RetryPolicy`] decides whether a failed request is sent again. Requests
+//! are retried if:
+//!
+//! * The service rejected them because of rate limiting, either with a `429`
+//!   status code or with an AWS throttling error such as
+//!   `ThrottlingException`. Those requests were not processed, so they are
+//!   retried regardless of their method.
+//! * The service or a proxy in front of it was temporarily unavailable (`502`,
+//!   `503` or `504`), or the request timed out. Those requests may have been
+//!   processed, so `POST` requests are only retried if the policy is told they
+//!   are idempotent.
+//!
+//! Failed requests are retried immediately. The runtime doesn't offer timers,
+//! so waiting between attempts would keep the provider spinning instead. For
+//! the same reason, `Retry-After` is not honored, which the host wouldn't pass
+//! on anyway: responses with an error status code are returned without their
+//! headers.
+//!
+//! Policies can be used with the [`HttpClient`](crate::http::HttpClient), with
+//! [`send_with_retries()`](crate::http::send_with_retries) for requests that
+//! are built by hand, or directly through [`RetryPolicy::send()`]:
+//!
+//! ```no_run
+//! # fn example(config_num_retries: u32) -> fiberplane_pdk::prelude::Result<()> {
+//! use fiberplane_pdk::http::{parse_url, HttpClient};
+//! use fiberplane_pdk::retry::RetryPolicy;
+//!
+//! let client = HttpClient::builder(parse_url("http://localhost:3100")?)
+//!     .retry_policy(RetryPolicy::default().with_max_attempts(config_num_retries + 1))
+//!     .build();
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::bindings::{HttpRequest, HttpRequestError, HttpRequestMethod, HttpResponse};
+use crate::host::make_http_request;
+use tracing::debug;
+
+/// Error codes that AWS services use to indicate a request was throttled.
+const AWS_THROTTLING_ERROR_CODES: &[&str] = &[
+    "LimitExceededException",
+    "ProvisionedThroughputExceededException",
+    "RequestLimitExceeded",
+    "RequestThrottled",
+    "RequestThrottledException",
+    "SlowDown",
+    "ThrottledException",
+    "Throttling",
+    "ThrottlingException",
+    "TooManyRequestsException",
+];
+
+/// Determines whether failed requests are retried.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    /// Makes up to three attempts.
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Returns a policy that never retries.
+    pub fn none() -> Self {
+        Self::default().with_max_attempts(1)
+    }
+
+    /// Sets the maximum number of attempts, including the first one.
+    pub fn with_max_attempts(self, max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            ..self
+        }
+    }
+
+    /// Retries `POST` requests after failures that may have occurred after the
+    /// request was processed.
+    ///
+    /// This is safe for APIs that only use `POST` to read data, such as search
+    /// and query APIs that accept their query in the body.
+    pub fn with_non_idempotent_requests(self, retry_non_idempotent: bool) -> Self {
+        Self {
+            retry_non_idempotent,
+            ..self
+        }
+    }
+
+    /// Sends the request through the host, retrying it according to this
+    /// policy.
+    ///
+    /// Returns the outcome of the last attempt.
+    pub async fn send(&self, request: HttpRequest) -> Result<HttpResponse, HttpRequestError> {
+        let mut attempt = 1;
+        loop {
+            let outcome = make_http_request(request.clone()).await;
+            if !self.should_retry(&request, &outcome, attempt) {
+                return outcome;
+            }
+
+            debug!(attempt, "Retrying request");
+            attempt += 1;
+        }
+    }
+
+    /// Returns whether the request should be retried, given the outcome of the
+    /// given attempt.
+    ///
+    /// Only errors are retried, since the host returns responses with an error
+    /// status code as [`HttpRequestError::ServerError`].
+    pub fn should_retry(
+        &self,
+        request: &HttpRequest,
+        outcome: &Result<HttpResponse, HttpRequestError>,
+        attempt: u32,
+    ) -> bool {
+        if attempt >= self.max_attempts {
+            return false;
+        }
+
+        match outcome {
+            Err(HttpRequestError::ServerError {
+                status_code,
+                response,
+            }) if *status_code == 429 || is_throttling_error(response) => true,
+            Err(HttpRequestError::ServerError { status_code, .. }) => {
+                is_unavailable(*status_code) && self.may_retry(request)
+            }
+            Err(HttpRequestError::Timeout) => self.may_retry(request),
+            _ => false,
+        }
+    }
+
+    fn may_retry(&self, request: &HttpRequest) -> bool {
+        self.retry_non_idempotent || !matches!(request.method, HttpRequestMethod::Post)
+    }
+}
+
+fn is_unavailable(status_code: u16) -> bool {
+    matches!(status_code, 502..=504)
+}
+
+/// Returns whether the body of an error response contains one of the error
+/// codes AWS services use for throttled requests.
+///
+/// Depending on the service, the code is returned in a JSON `__type` or `code`
+/// field, possibly prefixed with a namespace, or in an XML `Code` element.
+pub fn is_throttling_error(body: &[u8]) -> bool {
+    let body = String::from_utf8_lossy(body);
+    AWS_THROTTLING_ERROR_CODES.iter().any(|code| {
+        body.contains(&format!("\"{code}\""))
+            || body.contains(&format!("#{code}\""))
+            || body.contains(&format!("<Code>{code}</Code>"))
+    })
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+
+    fn request(method: HttpRequestMethod) -> HttpRequest {
+        let mut request = HttpRequest::default();
+        request.method = method;
+        request.url = "http://localhost/api".to_owned();
+        request
+    }
+
+    fn server_error(status_code: u16, body: &str) -> Result<HttpResponse, HttpRequestError> {
+        Err(HttpRequestError::ServerError {
+            status_code,
+            response: body.as_bytes().to_vec().into(),
+        })
+    }
+
+    #[test]
+    fn retries_transient_failures() {
+        let policy = RetryPolicy::default();
+        let get = request(HttpRequestMethod::Get);
+        let post = request(HttpRequestMethod::Post);
+
+        assert!(policy.should_retry(&get, &server_error(502, ""), 1));
+        assert!(policy.should_retry(&get, &Err(HttpRequestError::Timeout), 2));
+        assert!(!policy.should_retry(&get, &server_error(500, ""), 1));
+        assert!(!policy.should_retry(&get, &server_error(404, ""), 1));
+        assert!(!policy.should_retry(&get, &server_error(429, ""), 3));
+
+        // Non-idempotent requests are only retried if they were rejected.
+        assert!(!policy.should_retry(&post, &server_error(503, ""), 1));
+        assert!(policy.should_retry(&post, &server_error(429, ""), 1));
+        let throttled = r#"{"__type":"com.amazon.coral.availability#ThrottlingException"}"#;
+        assert!(policy.should_retry(&post, &server_error(400, throttled), 1));
+        let idempotent = policy.with_non_idempotent_requests(true);
+        assert!(idempotent.should_retry(&post, &server_error(503, ""), 1));
+    }
+}
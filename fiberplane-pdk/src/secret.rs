@@ -0,0 +1,274 @@
+//! Secret config values and redaction of log messages.
+//!
+//! Values of type [`Secret`] are registered as known secrets when they are
+//! created, and every message logged through [`log()`](crate::host::log) is
+//! passed through [`redact()`] first. This scrubs known secrets as well as the
+//! values of headers such as `Authorization`, so accidentally logging a config
+//! or a request doesn't leak credentials.
+//!
+//! Known secrets are forgotten at the start of every invocation, when
+//! [`init_panic_hook()`](crate::prelude::init_panic_hook) is called, so they
+//! don't accumulate for as long as the module instance lives.
+
+use serde::{Deserialize, Deserializer};
+use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+use std::fmt;
+
+/// Value that replaces redacted secrets in log messages.
+pub const REDACTED: &str = "<redacted>";
+
+/// Headers of which the values are redacted from log messages.
+///
+/// Header names are compared case-insensitively.
+pub static SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "cookie",
+    "proxy-authorization",
+    "set-cookie",
+    "x-amz-security-token",
+    "x-api-key",
+];
+
+/// Maximum number of known secrets. When more secrets are registered, the
+/// ones that were registered first are forgotten.
+pub const MAX_KNOWN_SECRETS: usize = 64;
+
+/// Minimum length of known secrets, in characters. Shorter values are not
+/// redacted, since they are likely to occur in unrelated text.
+pub const MIN_SECRET_LENGTH: usize = 4;
+
+thread_local! {
+    // Secrets in the order in which they were registered.
+    static KNOWN_SECRETS: RefCell<VecDeque<String>> = RefCell::new(VecDeque::new());
+}
+
+/// A string that should not be revealed, such as a password or a token.
+///
+/// The value is not printed by the `Debug` implementation, and it is redacted
+/// from any log messages. Use [`Secret::expose_secret()`] to access it.
+#[derive(Clone, Eq, Hash, PartialEq)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        let value = value.into();
+        register_secret(&value);
+        Self(value)
+    }
+
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl<'de> Deserialize<'de> for Secret {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer).map(Self::new)
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+/// Registers a value that should be redacted from log messages.
+///
+/// There is no need to call this for [`Secret`]s, nor for config fields
+/// annotated with `#[pdk(secret)]`, since those are registered automatically.
+/// Values shorter than [`MIN_SECRET_LENGTH`] are ignored.
+pub fn register_secret(value: &str) {
+    if value.chars().count() < MIN_SECRET_LENGTH {
+        return;
+    }
+
+    KNOWN_SECRETS.with(|secrets| {
+        let mut secrets = secrets.borrow_mut();
+        secrets.retain(|secret| secret != value);
+        if secrets.len() >= MAX_KNOWN_SECRETS {
+            secrets.pop_front();
+        }
+        secrets.push_back(value.to_owned());
+    });
+}
+
+/// Forgets all known secrets.
+pub fn clear_known_secrets() {
+    KNOWN_SECRETS.with(|secrets| secrets.borrow_mut().clear());
+}
+
+/// Redacts known secrets and the values of sensitive headers from the given
+/// message.
+pub fn redact(message: &str) -> String {
+    let message = KNOWN_SECRETS.with(|secrets| {
+        let secrets = secrets.borrow();
+
+        // Longer secrets are redacted first, so secrets that occur within
+        // them cannot leave parts of them behind.
+        let mut secrets: Vec<&String> = secrets.iter().collect();
+        secrets.sort_by_key(|secret| Reverse(secret.len()));
+        secrets
+            .into_iter()
+            .fold(message.to_owned(), |message, secret| {
+                message.replace(secret.as_str(), REDACTED)
+            })
+    });
+
+    redact_headers(&message)
+}
+
+/// Redacts the values of sensitive headers, as they appear in both the `Debug`
+/// output of requests (`"Authorization": "Bearer ..."`) and in plain text
+/// (`Authorization: Bearer ...`).
+fn redact_headers(message: &str) -> String {
+    let lowercase = message.to_ascii_lowercase();
+    let mut redacted = String::with_capacity(message.len());
+    let mut position = 0;
+    while let Some((value_start, value_end)) = find_sensitive_value(&lowercase, position) {
+        redacted.push_str(&message[position..value_start]);
+        redacted.push_str(REDACTED);
+        position = value_end;
+    }
+
+    redacted.push_str(&message[position..]);
+    redacted
+}
+
+/// Finds the byte range of the next sensitive header value, starting from the
+/// given position.
+fn find_sensitive_value(lowercase: &str, mut position: usize) -> Option<(usize, usize)> {
+    loop {
+        let (start, name) = SENSITIVE_HEADERS
+            .iter()
+            .filter_map(|name| Some((position + lowercase[position..].find(name)?, *name)))
+            .min_by_key(|(start, name)| (*start, usize::MAX - name.len()))?;
+
+        let end = start + name.len();
+        position = end;
+
+        let is_name_start = lowercase[..start]
+            .chars()
+            .next_back()
+            .map_or(true, |c| !c.is_ascii_alphanumeric() && c != '-');
+        if !is_name_start {
+            continue;
+        }
+
+        let rest = &lowercase[end..];
+        let rest = rest.strip_prefix('"').unwrap_or(rest);
+        let rest = rest.trim_start_matches([' ', '\t']);
+        let Some(rest) = rest.strip_prefix([':', '=']) else {
+            continue;
+        };
+        let rest = rest.trim_start_matches([' ', '\t']);
+        let value_start = lowercase.len() - rest.len();
+
+        // Quoted values are redacted without their quotes.
+        let (value_start, value_end) = if let Some(quoted) = rest.strip_prefix('"') {
+            let mut escaped = false;
+            let length = quoted
+                .char_indices()
+                .find(|(_, c)| match c {
+                    '\\' if !escaped => {
+                        escaped = true;
+                        false
+                    }
+                    '"' if !escaped => true,
+                    _ => {
+                        escaped = false;
+                        false
+                    }
+                })
+                .map_or(quoted.len(), |(index, _)| index);
+            (value_start + 1, value_start + 1 + length)
+        } else {
+            let length = rest.find(['\n', '\r']).unwrap_or(rest.len());
+            (value_start, value_start + length)
+        };
+
+        if value_end > value_start {
+            return Some((value_start, value_end));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_known_secrets() {
+        let secret = Secret::new("hunter2");
+
+        assert_eq!(format!("{secret:?}"), REDACTED);
+        assert_eq!(secret.expose_secret(), "hunter2");
+        assert_eq!(
+            redact("Logging in with password hunter2"),
+            "Logging in with password <redacted>"
+        );
+    }
+
+    #[test]
+    fn redacts_longer_secrets_first() {
+        clear_known_secrets();
+        register_secret("cd12");
+        register_secret("abcd1234");
+        register_secret("abc");
+
+        assert_eq!(
+            redact("token=abcd1234, pin=cd12, name=abc"),
+            "token=<redacted>, pin=<redacted>, name=abc"
+        );
+    }
+
+    #[test]
+    fn forgets_oldest_secrets() {
+        clear_known_secrets();
+        for index in 0..=MAX_KNOWN_SECRETS {
+            register_secret(&format!("secret-{index}"));
+        }
+
+        assert_eq!(redact("secret-0"), "secret-0");
+        assert_eq!(redact("secret-1"), REDACTED);
+        assert_eq!(
+            KNOWN_SECRETS.with(|secrets| secrets.borrow().len()),
+            MAX_KNOWN_SECRETS
+        );
+    }
+
+    #[test]
+    fn redacts_sensitive_headers() {
+        assert_eq!(
+            redact(
+                r#"HttpRequest { headers: Some({"Accept": "*/*", "Authorization": "AWS4-HMAC-SHA256 Credential=abc, Signature=\"def\"", "X-Amz-Security-Token": "token"}) }"#
+            ),
+            r#"HttpRequest { headers: Some({"Accept": "*/*", "Authorization": "<redacted>", "X-Amz-Security-Token": "<redacted>"}) }"#
+        );
+        assert_eq!(
+            redact("Proxy-Authorization: Basic YWRtaW46c2VjcmV0\nAccept: */*"),
+            "Proxy-Authorization: <redacted>\nAccept: */*"
+        );
+        assert_eq!(
+            redact("Request is missing authorization"),
+            "Request is missing authorization"
+        );
+    }
+}
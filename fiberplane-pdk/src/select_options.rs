@@ -0,0 +1,68 @@
+//! Enums that can be used as the type of select fields.
+//!
+//! Fields of a type implementing [`SelectOptions`] that have the `select`
+//! attribute are presented as select fields by the `QuerySchema` and
+//! `ConfigSchema` derive macros, with an option for every variant. Use `Vec<T>`
+//! for a select field that accepts multiple options:
+//!
+//! ```no_run
+//! use fiberplane_pdk::prelude::*;
+//! use serde::Deserialize;
+//!
+//! #[derive(Clone, Copy, Deserialize, SelectOptions)]
+//! #[serde(rename_all = "snake_case")]
+//! enum Aggregation {
+//!     #[pdk(label = "Average")]
+//!     Avg,
+//!     #[pdk(label = "Maximum")]
+//!     Max,
+//! }
+//!
+//! #[derive(Deserialize, QuerySchema)]
+//! struct Query {
+//!     #[pdk(label = "Aggregation to apply", select)]
+//!     aggregation: Aggregation,
+//!
+//!     #[pdk(label = "Additional aggregations", select)]
+//!     extra_aggregations: Vec<Aggregation>,
+//! }
+//! ```
+
+/// Trait for fieldless enums of which the variants are the options of a
+/// select field.
+///
+/// Implementations can be derived using the `SelectOptions` derive macro,
+/// which honors `#[serde(rename)]` and `#[serde(rename_all)]`, so option values
+/// are deserialized into the matching variant. Labels can be given per variant
+/// using `#[pdk(label = "...")]`, and otherwise default to the variant name in
+/// sentence case.
+///
+/// Note that select fields in the schema only carry the values of their
+/// options, so the labels are not part of the schema until `SelectField` in
+/// `fiberplane-models` supports them. Until then, they can be used wherever
+/// the provider presents an option itself, such as in cells.
+pub trait SelectOptions: Sized + 'static {
+    /// All variants, in the order in which they are presented.
+    const VARIANTS: &'static [Self];
+
+    /// Returns the value of the option, as it is (de)serialized.
+    fn value(&self) -> &'static str;
+
+    /// Returns the human-readable label of the option.
+    fn label(&self) -> &'static str;
+
+    /// Returns the values of all options, as used in the schema.
+    fn options() -> Vec<String> {
+        Self::VARIANTS
+            .iter()
+            .map(|variant| variant.value().to_owned())
+            .collect()
+    }
+
+    /// Returns the variant with the given value, if any.
+    fn from_value(value: &str) -> Option<&'static Self> {
+        Self::VARIANTS
+            .iter()
+            .find(|variant| variant.value() == value)
+    }
+}
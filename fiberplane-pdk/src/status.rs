@@ -0,0 +1,170 @@
+//! Helpers for answering requests for the `STATUS_QUERY_TYPE`.
+//!
+//! Providers capture their build information once using [`pdk_build_info!`]
+//! and use it to run their status checks:
+//!
+//! ```ignore
+//! use fiberplane_pdk::prelude::*;
+//!
+//! const BUILD_INFO: BuildInfo = pdk_build_info!();
+//!
+//! pdk_query_types! {
+//!     STATUS_QUERY_TYPE => {
+//!         handler: check_status(Config).await -> StatusReport
+//!     }
+//! }
+//!
+//! async fn check_status(config: Config) -> Result<StatusReport> {
+//!     BUILD_INFO.check(fetch_backend_version(&config)).await
+//! }
+//! ```
+//!
+//! [`pdk_build_info!`]: crate::pdk_build_info
+
+use crate as fiberplane_pdk; // To satisfy the `ProviderData` macro output.
+use crate::concurrency::join;
+use crate::macros::ProviderData;
+use crate::providers::{ProviderStatus, STATUS_MIME_TYPE};
+use crate::types::Result;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+/// Captures the build information of the provider that invokes it.
+///
+/// The information is taken from the environment variables set by `vergen`
+/// in the provider's build script.
+#[macro_export]
+macro_rules! pdk_build_info {
+    () => {
+        $crate::status::BuildInfo::new(env!("VERGEN_GIT_SHA"), env!("VERGEN_BUILD_TIMESTAMP"))
+    };
+}
+
+/// Build information of a provider, which is included in its status.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BuildInfo {
+    /// The commit hash the provider was built from.
+    pub version: &'static str,
+
+    /// The timestamp at which the provider was built.
+    pub built_at: &'static str,
+}
+
+impl BuildInfo {
+    pub const fn new(version: &'static str, built_at: &'static str) -> Self {
+        Self { version, built_at }
+    }
+
+    /// Returns the status of a provider that is ready to be used.
+    pub fn status(&self) -> StatusReport {
+        StatusReport {
+            status: ProviderStatus::builder()
+                .status(Ok(()))
+                .version(self.version.to_owned())
+                .built_at(self.built_at.to_owned())
+                .build(),
+            backend_version: None,
+        }
+    }
+
+    /// Runs the given probe and returns the status of the provider.
+    ///
+    /// The probe checks whether the backend can be reached and resolves to
+    /// the version of the backend, if it is known. Errors returned by the
+    /// probe are returned as the result of the status check.
+    pub async fn check<F>(&self, probe: F) -> Result<StatusReport>
+    where
+        F: Future<Output = Result<Option<String>>>,
+    {
+        let backend_version = probe.await?;
+        Ok(self.status().with_backend_version(backend_version))
+    }
+
+    /// Runs the given probe together with the query for the version of the
+    /// backend, and returns the status of the provider.
+    ///
+    /// Both run concurrently, since the version doesn't depend on the result
+    /// of the probe. Errors returned by the probe are returned as the result
+    /// of the status check, while the version is only included if it resolves
+    /// to one.
+    pub async fn check_with_version<P, T, V>(&self, probe: P, version: V) -> Result<StatusReport>
+    where
+        P: Future<Output = Result<T>>,
+        V: Future<Output = Option<String>>,
+    {
+        self.check(async {
+            let (status, version) = join(probe, version).await;
+            status.map(|_| version)
+        })
+        .await
+    }
+}
+
+/// The status of a provider, together with the version of the backend it
+/// queries.
+///
+/// This is encoded the same way as `ProviderStatus`, with an additional
+/// `backendVersion` field when the version is known.
+#[derive(Clone, Debug, Deserialize, ProviderData, Serialize)]
+#[pdk(mime_type = STATUS_MIME_TYPE)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusReport {
+    #[serde(flatten)]
+    pub status: ProviderStatus,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backend_version: Option<String>,
+}
+
+impl StatusReport {
+    pub fn with_backend_version(self, backend_version: Option<String>) -> Self {
+        Self {
+            backend_version,
+            ..self
+        }
+    }
+}
+
+#[cfg(all(test, feature = "testing"))]
+mod tests {
+    use super::*;
+    use crate::bindings::Error;
+    use crate::testing::block_on;
+
+    const BUILD_INFO: BuildInfo = BuildInfo::new("0123abc", "2024-03-01T12:00:00Z");
+
+    #[test]
+    fn check_includes_backend_version() {
+        let report = block_on(BUILD_INFO.check(async { Ok(Some("2.45.0".to_owned())) })).unwrap();
+
+        let value = serde_json::to_value(&report).unwrap();
+        assert_eq!(value["version"], "0123abc");
+        assert_eq!(value["backendVersion"], "2.45.0");
+
+        let status: ProviderStatus = serde_json::from_value(value).unwrap();
+        assert_eq!(status.version, "0123abc");
+    }
+
+    #[test]
+    fn check_returns_probe_errors() {
+        let result = block_on(BUILD_INFO.check(async { Err(Error::UnsupportedRequest) }));
+        assert!(matches!(result, Err(Error::UnsupportedRequest)));
+    }
+
+    #[test]
+    fn check_with_version_runs_probe_and_version_query() {
+        let report = block_on(
+            BUILD_INFO.check_with_version(async { Ok(()) }, async { Some("2.9.4".to_owned()) }),
+        )
+        .unwrap();
+        assert_eq!(report.backend_version.as_deref(), Some("2.9.4"));
+
+        let report =
+            block_on(BUILD_INFO.check_with_version(async { Ok(()) }, async { None })).unwrap();
+        assert_eq!(report.backend_version, None);
+
+        let probe = async { Err::<(), _>(Error::UnsupportedRequest) };
+        let result = block_on(BUILD_INFO.check_with_version(probe, async { None }));
+        assert!(matches!(result, Err(Error::UnsupportedRequest)));
+    }
+}
@@ -0,0 +1,177 @@
+//! Interpolation of template variables in queries.
+//!
+//! Queries may refer to the time range they are executed for using the
+//! following variables, which can be written either as `$__name` or as
+//! `${__name}`:
+//!
+//! * `$__interval` - The step between data points, such as `30s`.
+//! * `$__range` - The duration of the time range, such as `1h`.
+//! * `$__from` and `$__to` - The start and end of the time range. These are
+//!   formatted as milliseconds since the Unix epoch, unless another
+//!   [`TimestampFormat`] is configured.
+//!
+//! Any other variable starting with `$__` results in a validation error, while
+//! a `$` that is not followed by `__` is left as is.
+//!
+//! ```
+//! use fiberplane_pdk::prelude::*;
+//! use fiberplane_pdk::templating::TemplateVariables;
+//!
+//! let time_range: DateTimeRange = "2023-03-01T12:00:00Z 2023-03-01T13:00:00Z"
+//!     .parse()
+//!     .unwrap();
+//! let query = TemplateVariables::new(&time_range, time_range.step_for(120))
+//!     .interpolate("query", "rate(http_requests_total[$__interval])")
+//!     .unwrap();
+//! assert_eq!(query, "rate(http_requests_total[30s])");
+//! ```
+
+use crate::bindings::{Error, ValidationError};
+use crate::types::{DateTimeRange, Duration, Result, TimestampFormat};
+
+/// The values of the variables that may be used in a query.
+#[derive(Clone, Debug)]
+pub struct TemplateVariables {
+    time_range: DateTimeRange,
+    interval: Duration,
+    timestamp_format: TimestampFormat,
+}
+
+impl TemplateVariables {
+    pub fn new(time_range: &DateTimeRange, interval: Duration) -> Self {
+        Self {
+            time_range: time_range.clone(),
+            interval,
+            timestamp_format: TimestampFormat::UnixMillis,
+        }
+    }
+
+    /// Sets the format used for the `$__from` and `$__to` variables.
+    pub fn with_timestamp_format(self, timestamp_format: TimestampFormat) -> Self {
+        Self {
+            timestamp_format,
+            ..self
+        }
+    }
+
+    /// Substitutes the variables in the given template.
+    ///
+    /// Returns an [`Error::ValidationError`] for the given field if the
+    /// template refers to unknown variables.
+    pub fn interpolate(&self, field_name: &str, template: &str) -> Result<String> {
+        let mut result = String::with_capacity(template.len());
+        let mut unknown = Vec::new();
+
+        let mut rest = template;
+        while let Some(index) = rest.find('$') {
+            result.push_str(&rest[..index]);
+            rest = &rest[index..];
+
+            let Some((name, len)) = parse_variable(rest) else {
+                result.push('$');
+                rest = &rest[1..];
+                continue;
+            };
+
+            match self.value(name) {
+                Some(value) => result.push_str(&value),
+                None => unknown.push(format!("`{}`", &rest[..len])),
+            }
+            rest = &rest[len..];
+        }
+        result.push_str(rest);
+
+        if unknown.is_empty() {
+            Ok(result)
+        } else {
+            Err(Error::ValidationError {
+                errors: vec![ValidationError::builder()
+                    .field_name(field_name.to_owned())
+                    .message(format!("Unknown variables: {}", unknown.join(", ")))
+                    .build()],
+            })
+        }
+    }
+
+    fn value(&self, name: &str) -> Option<String> {
+        match name {
+            "interval" => Some(self.interval.to_string()),
+            "range" => Some(self.time_range.duration().to_string()),
+            "from" => Some(self.timestamp_format.format(&self.time_range.from)),
+            "to" => Some(self.timestamp_format.format(&self.time_range.to)),
+            _ => None,
+        }
+    }
+}
+
+/// Parses the variable reference at the start of the text.
+///
+/// Returns the name of the variable, without its `__` prefix, and the length
+/// of the reference.
+fn parse_variable(text: &str) -> Option<(&str, usize)> {
+    if let Some(braced) = text.strip_prefix("${__") {
+        let end = braced.find('}')?;
+        Some((&braced[..end], end + "${__}".len()))
+    } else {
+        let name = text.strip_prefix("$__")?;
+        let end = name
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .unwrap_or(name.len());
+        Some((&name[..end], end + "$__".len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables() -> TemplateVariables {
+        let time_range: DateTimeRange =
+            "2023-03-01T12:00:00Z 2023-03-01T15:00:00Z".parse().unwrap();
+        TemplateVariables::new(&time_range, Duration::from_secs(90))
+    }
+
+    #[test]
+    fn substitutes_variables() {
+        let query = variables()
+            .interpolate(
+                "query",
+                "sum(rate(x[$__interval])) / count_over_time(y[${__range}]) @ $__to",
+            )
+            .unwrap();
+        assert_eq!(
+            query,
+            "sum(rate(x[1m30s])) / count_over_time(y[3h]) @ 1677682800000"
+        );
+
+        let query = variables()
+            .with_timestamp_format(TimestampFormat::UnixSeconds)
+            .interpolate("query", "$__from-${__to}")
+            .unwrap();
+        assert_eq!(query, "1677672000-1677682800");
+    }
+
+    #[test]
+    fn leaves_other_dollar_signs_alone() {
+        let query = variables()
+            .interpolate("query", r#"{job="$job"} |~ "\\$[0-9]+$""#)
+            .unwrap();
+        assert_eq!(query, r#"{job="$job"} |~ "\\$[0-9]+$""#);
+    }
+
+    #[test]
+    fn rejects_unknown_variables() {
+        let result = variables().interpolate("query", "rate(x[$__rate_interval]) @ ${__now} $__");
+        match result {
+            Err(Error::ValidationError { errors }) => {
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].field_name, "query");
+                assert_eq!(
+                    errors[0].message,
+                    "Unknown variables: `$__rate_interval`, `${__now}`, `$__`"
+                );
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+    }
+}
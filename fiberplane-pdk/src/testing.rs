@@ -0,0 +1,296 @@
+//! Native test harness for providers.
+//!
+//! Providers talk to the host runtime through [`make_http_request()`],
+//! [`now()`] and [`log()`]. With the `testing` feature enabled, the versions of
+//! these functions that are exported through the [prelude](crate::prelude) are
+//! served by the [`MockHost`] installed for the current thread. This allows
+//! handlers such as `invoke2`, `get_supported_query_types` and `create_cells`
+//! to be driven end to end from a regular `cargo test`.
+//!
+//! Enable the feature for your provider's tests only:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! fiberplane-pdk = { workspace = true, features = ["testing"] }
+//! ```
+//!
+//! And use it like this:
+//!
+//! ```no_run
+//! use fiberplane_pdk::prelude::*;
+//! use fiberplane_pdk::testing::{block_on, HttpMock, MockHost};
+//!
+//! let host = MockHost::new();
+//! host.mock(HttpMock::get("http://localhost:3100/ready").respond_with_body(200, "ready"));
+//! let _guard = host.install();
+//!
+//! let response = block_on(make_http_request(HttpRequest::get("http://localhost:3100/ready")));
+//! assert_eq!(response.unwrap().status_code, 200);
+//! assert_eq!(host.requests().len(), 1);
+//! ```
+//!
+//! ## Cassettes
+//!
+//! Instead of registering mocks by hand, tests can replay a [`Cassette`]: a
+//! JSON file with HTTP interactions that were recorded from a real session:
+//!
+//! ```no_run
+//! use fiberplane_pdk::testing::{Cassette, MockHost};
+//!
+//! let host = MockHost::new();
+//! host.replay(Cassette::load(concat!(
+//!     env!("CARGO_MANIFEST_DIR"),
+//!     "/cassettes/fetch_logs.json"
+//! )));
+//! let _guard = host.install();
+//! ```
+//!
+//! Replaying panics on any request that is not part of the cassette. To
+//! (re-)record a cassette, use [`MockHost::record()`] with a transport that
+//! performs the actual requests and save the result of
+//! [`MockHost::recorded_cassette()`]. The `record-cassettes` feature provides
+//! [`live_transport()`] for this purpose. `Authorization` headers and AWS
+//! SigV4 headers are redacted before they end up in the cassette.
+//!
+//! [`make_http_request()`]: crate::host::make_http_request
+//! [`now()`]: crate::host::now
+//! [`log()`]: crate::host::log
+
+mod cassette;
+pub(crate) mod host;
+mod http_mock;
+#[cfg(feature = "record-cassettes")]
+mod live_transport;
+
+pub use cassette::*;
+pub use http_mock::HttpMock;
+#[cfg(feature = "record-cassettes")]
+pub use live_transport::live_transport;
+
+use crate::bindings::{HttpRequest, HttpRequestError, HttpResponse, Timestamp};
+use std::cell::RefCell;
+use std::future::Future;
+use std::rc::Rc;
+use time::{Duration, OffsetDateTime};
+
+thread_local! {
+    static CURRENT_HOST: RefCell<Option<MockHost>> = const { RefCell::new(None) };
+}
+
+/// A programmable stand-in for the provider runtime.
+///
+/// The mock host answers HTTP requests using the [`HttpMock`]s registered
+/// with it, keeps a frozen clock that only moves when told to, and captures
+/// all log lines. Cloning a `MockHost` yields a handle to the same state, so
+/// tests can keep inspecting it after it has been installed.
+#[derive(Clone)]
+pub struct MockHost {
+    state: Rc<RefCell<HostState>>,
+}
+
+/// Performs requests on behalf of the mock host while recording a cassette.
+pub type Transport = Box<dyn FnMut(&HttpRequest) -> Result<HttpResponse, HttpRequestError>>;
+
+struct HostState {
+    mocks: Vec<HttpMock>,
+    replay: Vec<(Interaction, bool)>,
+    recorder: Option<Transport>,
+    recorded: Vec<Interaction>,
+    requests: Vec<HttpRequest>,
+    logs: Vec<String>,
+    now: OffsetDateTime,
+}
+
+impl MockHost {
+    /// Creates a new mock host without any mocks, with its clock frozen at
+    /// the current time.
+    pub fn new() -> Self {
+        Self {
+            state: Rc::new(RefCell::new(HostState {
+                mocks: Vec::new(),
+                replay: Vec::new(),
+                recorder: None,
+                recorded: Vec::new(),
+                requests: Vec::new(),
+                logs: Vec::new(),
+                now: OffsetDateTime::now_utc(),
+            })),
+        }
+    }
+
+    /// Installs the mock host for the current thread.
+    ///
+    /// The host remains installed until the returned guard is dropped, after
+    /// which any previously installed host is restored. Installing a host
+    /// clears the [caches](crate::cache), so tests don't see each other's
+    /// cached responses.
+    #[must_use = "the mock host is uninstalled when the guard is dropped"]
+    pub fn install(&self) -> MockHostGuard {
+        crate::cache::clear();
+        let previous = CURRENT_HOST.with(|current| current.replace(Some(self.clone())));
+        MockHostGuard { previous }
+    }
+
+    /// Registers a mock for HTTP requests.
+    ///
+    /// Mocks are tried in the order in which they were registered, and the
+    /// first one that matches a request provides its response.
+    pub fn mock(&self, mock: HttpMock) -> &Self {
+        self.state.borrow_mut().mocks.push(mock);
+        self
+    }
+
+    /// Replays the interactions from the given cassette.
+    ///
+    /// Every interaction is replayed at most once, in the order in which
+    /// matching requests are made. Mocks registered with [`Self::mock()`]
+    /// take precedence over the cassette.
+    pub fn replay(&self, cassette: Cassette) -> &Self {
+        self.state.borrow_mut().replay.extend(
+            cassette
+                .interactions
+                .into_iter()
+                .map(|interaction| (interaction, false)),
+        );
+        self
+    }
+
+    /// Records all requests that are not answered by a mock or a replayed
+    /// cassette, using the given transport to perform them.
+    pub fn record(
+        &self,
+        transport: impl FnMut(&HttpRequest) -> Result<HttpResponse, HttpRequestError> + 'static,
+    ) -> &Self {
+        self.state.borrow_mut().recorder = Some(Box::new(transport));
+        self
+    }
+
+    /// Returns a cassette with all the interactions that were recorded so far.
+    pub fn recorded_cassette(&self) -> Cassette {
+        Cassette {
+            interactions: self.state.borrow().recorded.clone(),
+        }
+    }
+
+    /// Returns all the HTTP requests that were made through this host.
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.state.borrow().requests.clone()
+    }
+
+    /// Returns all the lines that were logged through this host.
+    pub fn logs(&self) -> Vec<String> {
+        self.state.borrow().logs.clone()
+    }
+
+    /// Returns the current time of the host's clock.
+    pub fn now(&self) -> Timestamp {
+        Timestamp::from(self.state.borrow().now)
+    }
+
+    /// Sets the host's clock to the given time.
+    pub fn set_now(&self, now: OffsetDateTime) -> &Self {
+        self.state.borrow_mut().now = now;
+        self
+    }
+
+    /// Moves the host's clock forward by the given duration.
+    pub fn advance(&self, duration: Duration) -> &Self {
+        self.state.borrow_mut().now += duration;
+        self
+    }
+
+    /// Asserts that every mock registered with [`HttpMock::times()`] has been
+    /// hit as often as it expected, and that every interaction from a replayed
+    /// cassette has been played.
+    pub fn assert_all_mocks_called(&self) {
+        let state = self.state.borrow();
+        for mock in &state.mocks {
+            mock.assert_exhausted();
+        }
+        for (interaction, played) in &state.replay {
+            assert!(
+                played,
+                "Recorded {} request to {} was never made",
+                interaction.request.method, interaction.request.url
+            );
+        }
+    }
+
+    fn handle_request(&self, request: HttpRequest) -> Result<HttpResponse, HttpRequestError> {
+        let mut state = self.state.borrow_mut();
+        state.requests.push(request.clone());
+
+        if let Some(mock) = state.mocks.iter_mut().find(|mock| mock.matches(&request)) {
+            return mock.respond();
+        }
+
+        if let Some((interaction, played)) = state
+            .replay
+            .iter_mut()
+            .find(|(interaction, played)| !played && interaction.request.matches(&request))
+        {
+            *played = true;
+            return interaction.response.to_result();
+        }
+
+        // The transport is taken out of the state while it runs, so it may use
+        // the host itself (for logging, for instance).
+        if let Some(mut transport) = state.recorder.take() {
+            drop(state);
+            let result = transport(&request);
+
+            let mut state = self.state.borrow_mut();
+            state.recorded.push(Interaction::record(&request, &result));
+            state.recorder = Some(transport);
+            return result;
+        }
+
+        panic!(
+            "Unexpected {:?} request to {} (headers: {:?}, body: {:?}).\n\
+            Registered mocks: {:#?}\n\
+            Unplayed cassette interactions: {:#?}",
+            request.method,
+            request.url,
+            request.headers,
+            request.body.as_deref().map(String::from_utf8_lossy),
+            state.mocks,
+            state
+                .replay
+                .iter()
+                .filter(|(_, played)| !played)
+                .map(|(interaction, _)| &interaction.request)
+                .collect::<Vec<_>>()
+        )
+    }
+
+    fn log(&self, message: String) {
+        self.state.borrow_mut().logs.push(message);
+    }
+}
+
+impl Default for MockHost {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Guard returned by [`MockHost::install()`].
+pub struct MockHostGuard {
+    previous: Option<MockHost>,
+}
+
+impl Drop for MockHostGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CURRENT_HOST.with(|current| current.replace(previous));
+    }
+}
+
+/// Runs a future returned by a provider handler to completion.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    futures::executor::block_on(future)
+}
+
+fn current_host() -> Option<MockHost> {
+    CURRENT_HOST.with(|current| current.borrow().clone())
+}
@@ -0,0 +1,393 @@
+use crate::bindings::{HttpRequest, HttpRequestError, HttpResponse};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use url::Url;
+
+/// Value that replaces redacted header values and query parameters.
+pub const REDACTED: &str = "<redacted>";
+
+/// Headers that are redacted before an interaction is recorded. This covers
+/// the usual credentials, as well as the headers used by AWS SigV4 signing.
+///
+/// Header names are compared case-insensitively.
+pub static REDACTED_HEADERS: &[&str] = &[
+    "authorization",
+    "cookie",
+    "proxy-authorization",
+    "set-cookie",
+    "x-amz-content-sha256",
+    "x-amz-date",
+    "x-amz-security-token",
+    "x-api-key",
+];
+
+/// Query parameters that are redacted before an interaction is recorded. These
+/// are used by AWS for presigned URLs.
+pub static REDACTED_QUERY_PARAMS: &[&str] = &[
+    "X-Amz-Credential",
+    "X-Amz-Date",
+    "X-Amz-Security-Token",
+    "X-Amz-Signature",
+];
+
+/// A recorded session of HTTP interactions between a provider and the
+/// services it talks to.
+///
+/// Cassettes are stored on disk as JSON. They can be recorded with
+/// [`MockHost::record()`](super::MockHost::record) and replayed with
+/// [`MockHost::replay()`](super::MockHost::replay). Credentials are redacted
+/// while recording, so cassettes are safe to commit.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    /// Loads a cassette from a JSON file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file cannot be read or doesn't contain a valid cassette.
+    pub fn load(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path)
+            .unwrap_or_else(|err| panic!("Could not read cassette {}: {err}", path.display()));
+        Self::from_json(&json)
+            .unwrap_or_else(|err| panic!("Invalid cassette {}: {err}", path.display()))
+    }
+
+    /// Parses a cassette from its JSON representation.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Saves the cassette to a JSON file, overwriting any existing file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the file cannot be written.
+    pub fn save(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let mut json = serde_json::to_string_pretty(self).expect("Could not serialize cassette");
+        json.push('\n');
+        fs::write(path, json)
+            .unwrap_or_else(|err| panic!("Could not write cassette {}: {err}", path.display()));
+    }
+}
+
+/// A single request and the outcome it produced.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+impl Interaction {
+    pub(crate) fn record(
+        request: &HttpRequest,
+        outcome: &Result<HttpResponse, HttpRequestError>,
+    ) -> Self {
+        let response = match outcome {
+            Ok(response) => RecordedResponse::Response {
+                status_code: response.status_code,
+                headers: redact_headers(&response.headers),
+                body: RecordedBody::from_bytes(&response.body),
+            },
+            Err(HttpRequestError::ServerError {
+                status_code,
+                response,
+            }) => RecordedResponse::Response {
+                status_code: *status_code,
+                headers: BTreeMap::new(),
+                body: RecordedBody::from_bytes(response),
+            },
+            Err(error) => RecordedResponse::Error {
+                error: serde_json::to_value(error).expect("Could not serialize HTTP error"),
+            },
+        };
+
+        Self {
+            request: RecordedRequest {
+                method: format!("{:?}", request.method).to_uppercase(),
+                url: redact_url(&request.url),
+                headers: request
+                    .headers
+                    .as_ref()
+                    .map(redact_headers)
+                    .unwrap_or_default(),
+                body: request.body.as_deref().map(RecordedBody::from_bytes),
+            },
+            response,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<RecordedBody>,
+}
+
+impl RecordedRequest {
+    /// Returns whether the given request matches the recorded one.
+    ///
+    /// Requests are matched by method, URL and body. Headers are ignored,
+    /// since they tend to contain volatile values such as signatures and
+    /// dates. Query parameters are compared regardless of order, and redacted
+    /// query parameters match any value.
+    pub fn matches(&self, request: &HttpRequest) -> bool {
+        format!("{:?}", request.method).eq_ignore_ascii_case(&self.method)
+            && urls_match(&self.url, &request.url)
+            && match (&self.body, request.body.as_deref()) {
+                (Some(recorded), Some(body)) => recorded.matches(body),
+                (None, None) => true,
+                _ => false,
+            }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum RecordedResponse {
+    Response {
+        #[serde(rename = "statusCode")]
+        status_code: u16,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        headers: BTreeMap<String, String>,
+        body: RecordedBody,
+    },
+    /// Errors other than server errors, stored in their serialized form.
+    Error { error: Value },
+}
+
+impl RecordedResponse {
+    /// Converts the recorded response back into the result the host would
+    /// return.
+    ///
+    /// Like the real host, status codes outside the `2xx` and `3xx` ranges
+    /// result in a [`HttpRequestError::ServerError`].
+    pub fn to_result(&self) -> Result<HttpResponse, HttpRequestError> {
+        match self {
+            Self::Response {
+                status_code,
+                headers,
+                body,
+            } if *status_code < 400 => Ok(HttpResponse::builder()
+                .body(body.to_bytes())
+                .headers(headers.clone())
+                .status_code(*status_code)
+                .build()),
+            Self::Response {
+                status_code, body, ..
+            } => Err(HttpRequestError::ServerError {
+                status_code: *status_code,
+                response: body.to_bytes().into(),
+            }),
+            Self::Error { error } => Err(serde_json::from_value(error.clone())
+                .expect("Could not deserialize recorded HTTP error")),
+        }
+    }
+}
+
+/// Request or response body.
+///
+/// Recorded bodies are stored as they were sent or received: as text if they
+/// are valid UTF-8, and as base64 otherwise. Bodies can also be written as
+/// JSON, which keeps hand-written cassettes readable. Those are replayed in
+/// their compact form and match any request body with the same JSON value.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RecordedBody {
+    Json(Value),
+    Text(String),
+    Base64(String),
+}
+
+impl RecordedBody {
+    fn from_bytes(bytes: &[u8]) -> Self {
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Self::Text(text.to_owned()),
+            Err(_) => Self::Base64(BASE64_STANDARD.encode(bytes)),
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::Json(json) => serde_json::to_vec(json).expect("Could not serialize JSON body"),
+            Self::Text(text) => text.as_bytes().to_vec(),
+            Self::Base64(encoded) => BASE64_STANDARD
+                .decode(encoded)
+                .expect("Invalid base64 body in cassette"),
+        }
+    }
+
+    /// Returns whether the given body matches the recorded one.
+    fn matches(&self, body: &[u8]) -> bool {
+        match self {
+            Self::Json(json) => {
+                matches!(serde_json::from_slice::<Value>(body), Ok(body) if &body == json)
+            }
+            _ => self.to_bytes() == body,
+        }
+    }
+}
+
+fn redact_headers(headers: &BTreeMap<String, String>) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let is_redacted = REDACTED_HEADERS
+                .iter()
+                .any(|redacted| name.eq_ignore_ascii_case(redacted));
+            let value = if is_redacted { REDACTED } else { value };
+            (name.clone(), value.to_owned())
+        })
+        .collect()
+}
+
+fn redact_url(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(url) else {
+        return url.to_owned();
+    };
+    if !parsed
+        .query_pairs()
+        .any(|(name, _)| is_redacted_param(&name))
+    {
+        return url.to_owned();
+    }
+
+    let pairs: Vec<(String, String)> = parsed
+        .query_pairs()
+        .map(|(name, value)| {
+            let value = if is_redacted_param(&name) {
+                REDACTED.to_owned()
+            } else {
+                value.into_owned()
+            };
+            (name.into_owned(), value)
+        })
+        .collect();
+    parsed.query_pairs_mut().clear().extend_pairs(pairs);
+    parsed.to_string()
+}
+
+fn is_redacted_param(name: &str) -> bool {
+    REDACTED_QUERY_PARAMS
+        .iter()
+        .any(|redacted| name.eq_ignore_ascii_case(redacted))
+}
+
+fn urls_match(recorded: &str, actual: &str) -> bool {
+    let (Ok(recorded), Ok(actual)) = (Url::parse(recorded), Url::parse(actual)) else {
+        return recorded == actual;
+    };
+
+    let mut recorded_pairs: Vec<_> = recorded.query_pairs().collect();
+    let mut actual_pairs: Vec<_> = actual.query_pairs().collect();
+    if recorded_pairs.len() != actual_pairs.len() {
+        return false;
+    }
+    recorded_pairs.sort();
+    actual_pairs.sort();
+
+    recorded.scheme() == actual.scheme()
+        && recorded.host() == actual.host()
+        && recorded.port_or_known_default() == actual.port_or_known_default()
+        && recorded.path() == actual.path()
+        && recorded_pairs.iter().zip(actual_pairs.iter()).all(
+            |((recorded_name, recorded_value), (actual_name, actual_value))| {
+                recorded_name == actual_name
+                    && (recorded_value == actual_value
+                        || (recorded_value == REDACTED && is_redacted_param(recorded_name)))
+            },
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_credentials() {
+        let request = HttpRequest::get(
+            "https://example.com/logs?X-Amz-Signature=abc&X-Amz-Date=20230101T000000Z&q=1",
+        )
+        .with_headers([
+            ("Authorization".to_owned(), "Bearer secret".to_owned()),
+            ("X-Amz-Security-Token".to_owned(), "token".to_owned()),
+            ("Accept".to_owned(), "application/json".to_owned()),
+        ]);
+        let interaction = Interaction::record(&request, &Err(HttpRequestError::Offline));
+
+        assert_eq!(
+            interaction.request.headers,
+            BTreeMap::from([
+                ("Accept".to_owned(), "application/json".to_owned()),
+                ("Authorization".to_owned(), REDACTED.to_owned()),
+                ("X-Amz-Security-Token".to_owned(), REDACTED.to_owned()),
+            ])
+        );
+        assert!(!interaction.request.url.contains("abc"));
+        assert!(!interaction.request.url.contains("20230101T000000Z"));
+        assert!(interaction.request.matches(&request));
+    }
+
+    #[test]
+    fn matches_query_params_in_any_order() {
+        let recorded = Interaction::record(
+            &HttpRequest::get("http://localhost:9090/api/v1/query?query=up&time=1"),
+            &Err(HttpRequestError::Offline),
+        )
+        .request;
+
+        assert!(recorded.matches(&HttpRequest::get(
+            "http://localhost:9090/api/v1/query?time=1&query=up"
+        )));
+        assert!(!recorded.matches(&HttpRequest::get(
+            "http://localhost:9090/api/v1/query?time=2&query=up"
+        )));
+        assert!(!recorded.matches(&HttpRequest::get(
+            "http://localhost:9090/api/v1/query?time=1&query=up&step=15"
+        )));
+    }
+
+    #[test]
+    fn bodies_round_trip_unchanged() {
+        for body in [
+            &b"{ \"b\": 1.50,\n  \"a\": [] }"[..],
+            b"plain text",
+            &[0xff, 0x00, 0x9f],
+        ] {
+            let recorded = RecordedBody::from_bytes(body);
+            assert_eq!(recorded.to_bytes(), body);
+            assert!(recorded.matches(body));
+        }
+
+        let recorded = RecordedBody::Json(serde_json::json!({ "a": 1 }));
+        assert!(recorded.matches(b"{ \"a\": 1 }"));
+        assert!(!recorded.matches(b"{ \"a\": 2 }"));
+    }
+
+    #[test]
+    fn server_errors_round_trip() {
+        let response = RecordedResponse::Response {
+            status_code: 400,
+            headers: BTreeMap::new(),
+            body: RecordedBody::Text("bad request".to_owned()),
+        };
+
+        assert!(matches!(
+            response.to_result(),
+            Err(HttpRequestError::ServerError { status_code: 400, response })
+                if response.as_ref() == b"bad request"
+        ));
+    }
+}
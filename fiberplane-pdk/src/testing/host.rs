@@ -0,0 +1,52 @@
+//! Native implementations of the host functions, served by the installed
+//! [`MockHost`](super::MockHost).
+
+use super::current_host;
+use crate::bindings::common::mem::FatPtr;
+use crate::bindings::{HttpRequest, HttpRequestError, HttpResponse, Timestamp};
+use time::OffsetDateTime;
+
+pub async fn make_http_request(request: HttpRequest) -> Result<HttpResponse, HttpRequestError> {
+    match current_host() {
+        Some(host) => host.handle_request(request),
+        None => panic!(
+            "No mock host installed while making a {:?} request to {}. \
+            Call `MockHost::install()` first.",
+            request.method, request.url
+        ),
+    }
+}
+
+pub fn log(message: String) {
+    match current_host() {
+        Some(host) => host.log(message),
+        None => eprintln!("{message}"),
+    }
+}
+
+pub fn now() -> Timestamp {
+    match current_host() {
+        Some(host) => host.now(),
+        None => Timestamp::from(OffsetDateTime::now_utc()),
+    }
+}
+
+// The exported provider functions still reference the imported host symbols,
+// so we provide stubs for them to make native test binaries link. None of them
+// are called, since all host interaction goes through the functions above.
+
+#[no_mangle]
+unsafe fn __fp_gen_log(_: FatPtr) {}
+
+#[no_mangle]
+unsafe fn __fp_gen_make_http_request(_: FatPtr) -> FatPtr {
+    unreachable!("HTTP requests are handled by the mock host")
+}
+
+#[no_mangle]
+unsafe fn __fp_gen_now() -> FatPtr {
+    unreachable!("the current time is provided by the mock host")
+}
+
+#[no_mangle]
+unsafe fn __fp_host_resolve_async_value(_: FatPtr, _: FatPtr) {}
@@ -0,0 +1,173 @@
+use crate::bindings::{HttpRequest, HttpRequestError, HttpRequestMethod, HttpResponse};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use url::Url;
+
+/// A canned response for HTTP requests matching a method, URL and (optionally)
+/// headers and body.
+///
+/// URLs are matched on their scheme, host, port and path. Query parameters
+/// that are part of the mock's URL must be present in the request, but the
+/// request may contain additional ones, in any order.
+#[derive(Debug)]
+pub struct HttpMock {
+    method: HttpRequestMethod,
+    url: Url,
+    headers: BTreeMap<String, String>,
+    body: Option<Vec<u8>>,
+    response: Result<HttpResponse, HttpRequestError>,
+    expected_hits: Option<usize>,
+    hits: usize,
+}
+
+impl HttpMock {
+    /// Creates a mock for requests with the given method and URL.
+    ///
+    /// Until one of the `respond_with` methods is called, the mock responds
+    /// with an empty `200 OK` response.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the given URL cannot be parsed.
+    pub fn new(method: HttpRequestMethod, url: &str) -> Self {
+        Self {
+            method,
+            url: Url::parse(url).unwrap_or_else(|err| panic!("Invalid mock URL {url}: {err}")),
+            headers: BTreeMap::new(),
+            body: None,
+            response: Ok(response(200, BTreeMap::new(), Vec::new())),
+            expected_hits: None,
+            hits: 0,
+        }
+    }
+
+    /// Creates a mock for `GET` requests to the given URL.
+    pub fn get(url: &str) -> Self {
+        Self::new(HttpRequestMethod::Get, url)
+    }
+
+    /// Creates a mock for `POST` requests to the given URL.
+    pub fn post(url: &str) -> Self {
+        Self::new(HttpRequestMethod::Post, url)
+    }
+
+    /// Only matches requests that carry the given header. Header names are
+    /// compared case-insensitively.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers
+            .insert(name.into().to_ascii_lowercase(), value.into());
+        self
+    }
+
+    /// Only matches requests with exactly the given body.
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    /// Expects the mock to be hit exactly `times` times. Once exhausted, the
+    /// mock no longer matches any requests.
+    pub fn times(mut self, times: usize) -> Self {
+        self.expected_hits = Some(times);
+        self
+    }
+
+    /// Responds with the given response.
+    pub fn respond_with(mut self, response: HttpResponse) -> Self {
+        self.response = Ok(response);
+        self
+    }
+
+    /// Responds with the given status code and body.
+    ///
+    /// Like the real host, status codes outside the `2xx` and `3xx` ranges
+    /// result in a [`HttpRequestError::ServerError`].
+    pub fn respond_with_body(mut self, status_code: u16, body: impl Into<Vec<u8>>) -> Self {
+        let body = body.into();
+        self.response = if status_code < 400 {
+            Ok(response(status_code, BTreeMap::new(), body))
+        } else {
+            Err(HttpRequestError::ServerError {
+                status_code,
+                response: body.into(),
+            })
+        };
+        self
+    }
+
+    /// Responds with `200 OK` and the given value serialized as JSON.
+    pub fn respond_with_json<T: Serialize>(mut self, value: &T) -> Self {
+        let body = serde_json::to_vec(value).expect("Could not serialize mock response");
+        let headers = BTreeMap::from([("content-type".to_owned(), "application/json".to_owned())]);
+        self.response = Ok(response(200, headers, body));
+        self
+    }
+
+    /// Fails the request with the given error.
+    pub fn respond_with_error(mut self, error: HttpRequestError) -> Self {
+        self.response = Err(error);
+        self
+    }
+
+    pub(crate) fn matches(&self, request: &HttpRequest) -> bool {
+        if self
+            .expected_hits
+            .map_or(false, |expected| self.hits >= expected)
+        {
+            return false;
+        }
+
+        std::mem::discriminant(&request.method) == std::mem::discriminant(&self.method)
+            && self.matches_url(&request.url)
+            && self.headers.iter().all(|(name, value)| {
+                request
+                    .headers
+                    .iter()
+                    .flatten()
+                    .any(|(request_name, request_value)| {
+                        request_name.eq_ignore_ascii_case(name) && request_value == value
+                    })
+            })
+            && self.body.as_ref().map_or(true, |body| {
+                request.body.as_ref().map(|b| b.as_ref()) == Some(body.as_slice())
+            })
+    }
+
+    fn matches_url(&self, url: &str) -> bool {
+        let Ok(url) = Url::parse(url) else {
+            return false;
+        };
+
+        url.scheme() == self.url.scheme()
+            && url.host() == self.url.host()
+            && url.port_or_known_default() == self.url.port_or_known_default()
+            && url.path() == self.url.path()
+            && self
+                .url
+                .query_pairs()
+                .all(|pair| url.query_pairs().any(|request_pair| request_pair == pair))
+    }
+
+    pub(crate) fn respond(&mut self) -> Result<HttpResponse, HttpRequestError> {
+        self.hits += 1;
+        self.response.clone()
+    }
+
+    pub(crate) fn assert_exhausted(&self) {
+        if let Some(expected) = self.expected_hits {
+            assert_eq!(
+                self.hits, expected,
+                "Expected {:?} request to {} to be made {expected} time(s), but it was made {} time(s)",
+                self.method, self.url, self.hits
+            );
+        }
+    }
+}
+
+fn response(status_code: u16, headers: BTreeMap<String, String>, body: Vec<u8>) -> HttpResponse {
+    HttpResponse::builder()
+        .body(body)
+        .headers(headers)
+        .status_code(status_code)
+        .build()
+}
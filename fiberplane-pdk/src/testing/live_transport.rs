@@ -0,0 +1,55 @@
+use crate::bindings::{HttpRequest, HttpRequestError, HttpResponse};
+use std::collections::BTreeMap;
+use std::io::Read;
+
+/// Returns a transport that performs requests against the actual services,
+/// for use with [`MockHost::record()`](super::MockHost::record).
+pub fn live_transport() -> impl FnMut(&HttpRequest) -> Result<HttpResponse, HttpRequestError> {
+    let agent = ureq::AgentBuilder::new().build();
+
+    move |request| {
+        let method = format!("{:?}", request.method).to_uppercase();
+        let mut live_request = agent.request(&method, &request.url);
+        for (name, value) in request.headers.iter().flatten() {
+            live_request = live_request.set(name, value);
+        }
+
+        let result = match &request.body {
+            Some(body) => live_request.send_bytes(body),
+            None => live_request.call(),
+        };
+
+        match result {
+            Ok(response) => {
+                let status_code = response.status();
+                let headers: BTreeMap<String, String> = response
+                    .headers_names()
+                    .into_iter()
+                    .filter_map(|name| {
+                        let value = response.header(&name)?.to_owned();
+                        Some((name, value))
+                    })
+                    .collect();
+                Ok(HttpResponse::builder()
+                    .body(read_body(response))
+                    .headers(headers)
+                    .status_code(status_code)
+                    .build())
+            }
+            Err(ureq::Error::Status(status_code, response)) => Err(HttpRequestError::ServerError {
+                status_code,
+                response: read_body(response).into(),
+            }),
+            Err(ureq::Error::Transport(_)) => Err(HttpRequestError::Offline),
+        }
+    }
+}
+
+fn read_body(response: ureq::Response) -> Vec<u8> {
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut body)
+        .expect("Could not read response body");
+    body
+}
@@ -1,9 +1,21 @@
 mod deserialize;
 mod serialize;
 
+use super::Duration;
 use fiberplane_provider_bindings::Timestamp;
+use std::cell::Cell;
+use std::str::FromStr;
+use time::OffsetDateTime;
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+const NANOS_PER_MILLI: i128 = 1_000_000;
 
 /// Type to use for query data fields of type "date_time_range".
+///
+/// Time ranges are parsed from two timestamps separated by a single space.
+/// Each timestamp is either in RFC 3339 format, or an expression relative to
+/// the current time, such as `now`, `now-1h` or `now+30m`. The offset uses the
+/// same format as [`Duration`].
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct DateTimeRange {
     /// Start time of the range, inclusive.
@@ -13,6 +25,181 @@ pub struct DateTimeRange {
     pub to: Timestamp,
 }
 
+impl DateTimeRange {
+    pub fn new(from: impl Into<Timestamp>, to: impl Into<Timestamp>) -> Self {
+        Self {
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+
+    /// Parses a time range, resolving relative expressions against the given
+    /// time.
+    pub fn parse_at(value: &str, now: Timestamp) -> Result<Self, String> {
+        Self::parse_with(value, || *now)
+    }
+
+    fn parse_with(value: &str, now: impl Fn() -> OffsetDateTime) -> Result<Self, String> {
+        let Some((from, to)) = value.split_once(' ') else {
+            return Err("wrong format. The correct format is '{from_rfc_3339_timestamp} {to_rfc_3339_timestamp}'. There is a single space between the timestamps.".to_owned());
+        };
+
+        let from = parse_timestamp(from, &now)
+            .map_err(|e| format!("could not parse the 'from' timestamp: {e}"))?;
+        let to = parse_timestamp(to, &now)
+            .map_err(|e| format!("could not parse the 'to' timestamp: {e}"))?;
+        Ok(Self { from, to })
+    }
+
+    /// Returns the duration of the range, or zero if the range ends before it
+    /// starts.
+    pub fn duration(&self) -> Duration {
+        std::time::Duration::try_from(*self.to - *self.from)
+            .unwrap_or_default()
+            .into()
+    }
+
+    /// Returns the range moved forward in time by the given offset, or
+    /// backward if the offset is negative.
+    pub fn shift(&self, offset: time::Duration) -> Self {
+        Self::new(*self.from + offset, *self.to + offset)
+    }
+
+    /// Returns the range expanded to a grid with intervals of the given step,
+    /// so the start is rounded down and the end is rounded up to a multiple of
+    /// the step since the Unix epoch.
+    ///
+    /// Aligning ranges avoids buckets shifting as the range moves forward or
+    /// backward in time, which would make graphs jump unexpectedly.
+    pub fn align_to(&self, step: Duration) -> Self {
+        let step = std::time::Duration::from(step).as_nanos() as i128;
+        if step == 0 {
+            return self.clone();
+        }
+
+        let from = self.from.unix_timestamp_nanos().div_euclid(step) * step;
+        let to = -(-self.to.unix_timestamp_nanos()).div_euclid(step) * step;
+        match (
+            OffsetDateTime::from_unix_timestamp_nanos(from),
+            OffsetDateTime::from_unix_timestamp_nanos(to),
+        ) {
+            (Ok(from), Ok(to)) => Self::new(from, to),
+            _ => self.clone(),
+        }
+    }
+
+    /// Returns a step that divides the range into roughly the given number of
+    /// points.
+    ///
+    /// The step is rounded up to a whole number of seconds, minutes or hours,
+    /// depending on its size, and is at least one second.
+    pub fn step_for(&self, points: u32) -> Duration {
+        let seconds = self.duration().as_millis() as f64 / 1000.0 / f64::from(points.max(1));
+        let unit = if seconds >= 3600.0 {
+            3600.0
+        } else if seconds >= 60.0 {
+            60.0
+        } else {
+            1.0
+        };
+
+        let step = f64::ceil(seconds / unit) * unit;
+        Duration::from_secs((step as u64).max(1))
+    }
+
+    /// Formats the start and end of the range in the given format.
+    pub fn format(&self, format: TimestampFormat) -> (String, String) {
+        (format.format(&self.from), format.format(&self.to))
+    }
+}
+
+impl FromStr for DateTimeRange {
+    type Err = String;
+
+    /// Parses a time range, resolving relative expressions against the current
+    /// time according to the host.
+    ///
+    /// The host's clock is only read if the range contains a relative
+    /// expression, and at most once, so both ends are resolved against the
+    /// same time.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let now = Cell::new(None);
+        Self::parse_with(value, || match now.get() {
+            Some(now) => now,
+            None => {
+                let current = *crate::host::now();
+                now.set(Some(current));
+                current
+            }
+        })
+    }
+}
+
+/// Parses a timestamp in RFC 3339 format or relative to the current time.
+fn parse_timestamp(value: &str, now: impl Fn() -> OffsetDateTime) -> Result<Timestamp, String> {
+    let Some(offset) = value.strip_prefix("now") else {
+        return Timestamp::parse(value).map_err(|e| e.to_string());
+    };
+
+    let now = now();
+    let time = match offset.chars().next() {
+        None => Some(now),
+        Some('-') => now.checked_sub(parse_offset(&offset[1..])?),
+        Some('+') => now.checked_add(parse_offset(&offset[1..])?),
+        Some(_) => {
+            return Err(format!(
+                "invalid relative time `{value}`, expected a format like `now-1h`"
+            ))
+        }
+    };
+
+    time.map(Timestamp::from)
+        .ok_or_else(|| format!("relative time `{value}` is out of range"))
+}
+
+fn parse_offset(value: &str) -> Result<time::Duration, String> {
+    let duration: Duration = value.parse()?;
+    time::Duration::try_from(std::time::Duration::from(duration)).map_err(|e| e.to_string())
+}
+
+/// Formats in which timestamps can be passed to external services.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TimestampFormat {
+    /// Seconds since the Unix epoch, with a fractional part if the timestamp
+    /// is not a whole number of seconds.
+    UnixSeconds,
+
+    /// Whole milliseconds since the Unix epoch.
+    UnixMillis,
+
+    /// Nanoseconds since the Unix epoch.
+    UnixNanos,
+
+    /// RFC 3339, as used when serializing a `DateTimeRange`.
+    Rfc3339,
+}
+
+impl TimestampFormat {
+    pub fn format(self, timestamp: &Timestamp) -> String {
+        let nanos = timestamp.unix_timestamp_nanos();
+        match self {
+            Self::UnixSeconds => {
+                let seconds = nanos.div_euclid(NANOS_PER_SECOND);
+                let fraction = nanos.rem_euclid(NANOS_PER_SECOND);
+                if fraction == 0 {
+                    seconds.to_string()
+                } else {
+                    let fraction = format!("{fraction:09}");
+                    format!("{seconds}.{}", fraction.trim_end_matches('0'))
+                }
+            }
+            Self::UnixMillis => nanos.div_euclid(NANOS_PER_MILLI).to_string(),
+            Self::UnixNanos => nanos.to_string(),
+            Self::Rfc3339 => timestamp.to_string(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use serde::Deserialize;
@@ -69,4 +256,111 @@ mod tests {
             }
         )
     }
+
+    fn range(from: OffsetDateTime, to: OffsetDateTime) -> DateTimeRange {
+        DateTimeRange::new(from, to)
+    }
+
+    #[test]
+    fn parses_relative_ranges() {
+        let parse = |value: &str| {
+            DateTimeRange::parse_at(value, datetime!(2023-02-08 09:30:00 +00:00).into())
+        };
+
+        assert_eq!(
+            parse("now-1h now"),
+            Ok(range(
+                datetime!(2023-02-08 08:30:00 +00:00),
+                datetime!(2023-02-08 09:30:00 +00:00)
+            ))
+        );
+        assert_eq!(
+            parse("2023-02-08T09:00:00Z now+1h30m"),
+            Ok(range(
+                datetime!(2023-02-08 09:00:00 +00:00),
+                datetime!(2023-02-08 11:00:00 +00:00)
+            ))
+        );
+        assert!(parse("now").is_err());
+        assert!(parse("now-1x now").is_err());
+        assert!(parse("now*2 now").is_err());
+        assert!(parse("yesterday now").is_err());
+    }
+
+    #[test]
+    fn computes_durations_and_shifts() {
+        let range = range(
+            datetime!(2023-02-08 09:00:00 +00:00),
+            datetime!(2023-02-08 10:30:00 +00:00),
+        );
+        assert_eq!(range.duration(), Duration::from_secs(5400));
+        assert_eq!(
+            range.shift(time::Duration::hours(-1)),
+            DateTimeRange::new(
+                datetime!(2023-02-08 08:00:00 +00:00),
+                datetime!(2023-02-08 09:30:00 +00:00)
+            )
+        );
+
+        let reversed = DateTimeRange::new(
+            datetime!(2023-02-08 10:30:00 +00:00),
+            datetime!(2023-02-08 09:00:00 +00:00),
+        );
+        assert_eq!(reversed.duration(), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn aligns_to_grid() {
+        let range = range(
+            datetime!(2023-02-08 09:16:27.794 +00:00),
+            datetime!(2023-02-08 09:31:27.794 +00:00),
+        );
+        assert_eq!(
+            range.align_to(Duration::from_secs(60)),
+            DateTimeRange::new(
+                datetime!(2023-02-08 09:16:00 +00:00),
+                datetime!(2023-02-08 09:32:00 +00:00)
+            )
+        );
+        assert_eq!(range.align_to(Duration::from_secs(0)), range);
+    }
+
+    #[test]
+    fn calculates_steps() {
+        let from = datetime!(2023-02-08 00:00:00 +00:00);
+        let step_for = |duration: time::Duration| range(from, from + duration).step_for(120);
+
+        assert_eq!(step_for(time::Duration::minutes(1)), Duration::from_secs(1));
+        assert_eq!(step_for(time::Duration::hours(1)), Duration::from_secs(30));
+        assert_eq!(step_for(time::Duration::hours(3)), Duration::from_secs(120));
+        assert_eq!(step_for(time::Duration::days(7)), Duration::from_secs(7200));
+        assert_eq!(step_for(time::Duration::ZERO), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn formats_timestamps() {
+        let range = range(
+            datetime!(2023-02-08 09:16:27.794 +00:00),
+            datetime!(2023-02-08 09:31:27 +00:00),
+        );
+        assert_eq!(
+            range.format(TimestampFormat::UnixSeconds),
+            ("1675847787.794".to_owned(), "1675848687".to_owned())
+        );
+        assert_eq!(
+            range.format(TimestampFormat::UnixMillis),
+            ("1675847787794".to_owned(), "1675848687000".to_owned())
+        );
+        assert_eq!(
+            range.format(TimestampFormat::UnixNanos),
+            (
+                "1675847787794000000".to_owned(),
+                "1675848687000000000".to_owned()
+            )
+        );
+        assert_eq!(
+            TimestampFormat::Rfc3339.format(&range.from),
+            range.from.to_string()
+        );
+    }
 }
@@ -0,0 +1,222 @@
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use std::time;
+
+/// Units that may be used in durations, from largest to smallest.
+const UNITS: &[(&str, u64)] = &[
+    ("w", 7 * 24 * 60 * 60 * 1000),
+    ("d", 24 * 60 * 60 * 1000),
+    ("h", 60 * 60 * 1000),
+    ("m", 60 * 1000),
+    ("s", 1000),
+    ("ms", 1),
+];
+
+/// Type to use for query data fields that contain a duration, such as a step
+/// or a period.
+///
+/// Durations are entered in a human-friendly format, consisting of one or more
+/// numbers followed by a unit: `30s`, `5m` or `1h30m`. Supported units are
+/// `w`, `d`, `h`, `m`, `s` and `ms`. A number without a unit is interpreted as
+/// a number of seconds.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Duration(time::Duration);
+
+impl Duration {
+    pub const fn from_secs(secs: u64) -> Self {
+        Self(time::Duration::from_secs(secs))
+    }
+
+    pub const fn from_millis(millis: u64) -> Self {
+        Self(time::Duration::from_millis(millis))
+    }
+
+    /// Returns the number of whole seconds in the duration.
+    pub const fn as_secs(&self) -> u64 {
+        self.0.as_secs()
+    }
+
+    /// Returns the number of whole milliseconds in the duration.
+    pub const fn as_millis(&self) -> u128 {
+        self.0.as_millis()
+    }
+}
+
+impl fmt::Display for Duration {
+    /// Formats the duration in the same format as it is parsed, using the
+    /// largest units possible.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut remainder = self.as_millis();
+        if remainder == 0 {
+            return f.write_str("0s");
+        }
+
+        for (unit, unit_millis) in UNITS {
+            let unit_millis = u128::from(*unit_millis);
+            if remainder >= unit_millis {
+                write!(f, "{}{unit}", remainder / unit_millis)?;
+                remainder %= unit_millis;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Duration {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Err("duration may not be empty".to_owned());
+        }
+
+        if let Ok(secs) = value.parse::<u64>() {
+            return Ok(Self::from_secs(secs));
+        }
+
+        let mut millis: u64 = 0;
+        let mut rest = value;
+        while !rest.is_empty() {
+            let number_len = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let unit_len = rest[number_len..]
+                .find(|c: char| !c.is_ascii_alphabetic())
+                .unwrap_or(rest.len() - number_len);
+            let (number, unit) = (&rest[..number_len], &rest[number_len..][..unit_len]);
+
+            let (Ok(number), false) = (number.parse::<u64>(), unit.is_empty()) else {
+                return Err(format!(
+                    "invalid duration `{value}`, expected a format like `1h30m`"
+                ));
+            };
+            let Some((_, unit_millis)) = UNITS.iter().find(|(name, _)| *name == unit) else {
+                return Err(format!(
+                    "invalid unit `{unit}` in duration `{value}`, expected one of: w, d, h, m, s, ms"
+                ));
+            };
+
+            millis = number
+                .checked_mul(*unit_millis)
+                .and_then(|component| millis.checked_add(component))
+                .ok_or_else(|| format!("duration `{value}` is too long"))?;
+            rest = rest[number_len + unit_len..].trim_start();
+        }
+
+        Ok(Self::from_millis(millis))
+    }
+}
+
+impl From<Duration> for time::Duration {
+    fn from(duration: Duration) -> Self {
+        duration.0
+    }
+}
+
+impl From<time::Duration> for Duration {
+    fn from(duration: time::Duration) -> Self {
+        Self(duration)
+    }
+}
+
+impl<'de> Deserialize<'de> for Duration {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(DurationVisitor)
+    }
+}
+
+struct DurationVisitor;
+
+impl<'de> Visitor<'de> for DurationVisitor {
+    type Value = Duration;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .write_str("a duration such as \"30s\", \"5m\" or \"1h30m\", or a number of seconds")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        value.parse().map_err(E::custom)
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        Ok(Duration::from_secs(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E>
+    where
+        E: de::Error,
+    {
+        u64::try_from(value)
+            .map(Duration::from_secs)
+            .map_err(|_| E::custom("duration may not be negative"))
+    }
+}
+
+impl Serialize for Duration {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        let parse = |value: &str| value.parse::<Duration>();
+
+        assert_eq!(parse("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse("5m"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse("1d 12h"), Ok(Duration::from_secs(129_600)));
+        assert_eq!(parse("1s500ms"), Ok(Duration::from_millis(1500)));
+        assert_eq!(parse("60"), Ok(Duration::from_secs(60)));
+        assert!(parse("").is_err());
+        assert!(parse("5x").is_err());
+        assert!(parse("m").is_err());
+        assert!(parse("1.5h").is_err());
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(Duration::from_secs(0).to_string(), "0s");
+        assert_eq!(Duration::from_secs(300).to_string(), "5m");
+        assert_eq!(Duration::from_secs(5400).to_string(), "1h30m");
+        assert_eq!(Duration::from_millis(1500).to_string(), "1s500ms");
+    }
+
+    #[test]
+    fn deserializes_from_query_data() {
+        #[derive(Debug, Deserialize, PartialEq, Serialize)]
+        struct TargetType {
+            step: Duration,
+        }
+
+        let target: TargetType = serde_qs::from_str("step=1h30m").unwrap();
+        assert_eq!(
+            target,
+            TargetType {
+                step: Duration::from_secs(5400)
+            }
+        );
+        assert_eq!(serde_qs::to_string(&target).unwrap(), "step=1h30m");
+    }
+}
@@ -1,7 +1,9 @@
 mod date_time_range;
+mod duration;
 mod provider_data_types;
 mod result;
 
 pub use date_time_range::*;
+pub use duration::*;
 pub use provider_data_types::*;
 pub use result::*;
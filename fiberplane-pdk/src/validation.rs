@@ -0,0 +1,396 @@
+//! Validation of query data and config fields.
+//!
+//! The `QuerySchema` and `ConfigSchema` derive macros generate a `validate()`
+//! method based on the validation attributes of the struct's fields, which is
+//! called by the generated `parse()` method:
+//!
+//! * `#[pdk(non_empty)]` - The value may not be empty or only contain
+//!   whitespace.
+//! * `#[pdk(url)]` - The value must be a valid URL.
+//! * `#[pdk(regex = "...")]` - The value must match the given regular
+//!   expression in its entirety.
+//! * `#[pdk(one_of = ["...", "..."])]` - The value must be one of the given
+//!   options.
+//! * `#[pdk(min = ...)]` and `#[pdk(max = ...)]` - On integer, floating-point
+//!   and [`Duration`] fields, the value must lie within the given range.
+//!   Durations are compared in seconds.
+//!
+//! Optional fields are only validated if they have a value, while every item
+//! in a list is validated separately. Fields annotated with
+//! `#[serde(flatten)]` are validated by the `validate()` method of their type.
+//! If validation fails, an [`Error::ValidationError`] is returned with a single
+//! [`ValidationError`] for every offending field.
+
+use crate::bindings::{Error, ValidationError};
+use crate::secret::Secret;
+use crate::types::{Duration, Result};
+use regex::Regex;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use url::Url;
+
+thread_local! {
+    // Compiled patterns of `regex` validations, since the same patterns are
+    // used every time the same type is parsed.
+    static REGEXES: RefCell<HashMap<String, std::result::Result<Regex, String>>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Collects validation errors for the fields of a struct.
+///
+/// This is used by the code generated by the derive macros, but may also be
+/// used directly:
+///
+/// ```
+/// use fiberplane_pdk::validation::Validator;
+///
+/// let query = "";
+/// let step = 0;
+/// let result = Validator::new()
+///     .non_empty("query", &query.to_owned())
+///     .min("step", &step, 1.0)
+///     .finish();
+/// assert!(result.is_err());
+/// ```
+#[derive(Debug, Default)]
+#[must_use = "validation errors are only returned by `finish()`"]
+pub struct Validator {
+    errors: Vec<ValidationError>,
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Checks that the value is not empty and doesn't consist of whitespace
+    /// only.
+    pub fn non_empty(self, field_name: &str, value: &impl FieldValue) -> Self {
+        if value.is_blank() {
+            self.with_error(field_name, "Please enter a value".to_owned())
+        } else {
+            self
+        }
+    }
+
+    /// Checks that the value is a valid URL.
+    pub fn url(self, field_name: &str, value: &impl FieldValue) -> Self {
+        let error = value
+            .texts()
+            .into_iter()
+            .find_map(|text| Url::parse(text).err());
+        match error {
+            Some(err) => self.with_error(field_name, format!("Please enter a valid URL: {err}")),
+            None => self,
+        }
+    }
+
+    /// Checks that the value matches the given regular expression in its
+    /// entirety.
+    pub fn regex(self, field_name: &str, value: &impl FieldValue, pattern: &str) -> Self {
+        let matches = REGEXES.with(|regexes| {
+            let mut regexes = regexes.borrow_mut();
+            let regex = regexes.entry(pattern.to_owned()).or_insert_with(|| {
+                Regex::new(&format!("^(?:{pattern})$")).map_err(|err| err.to_string())
+            });
+            regex
+                .as_ref()
+                .map(|regex| value.texts().into_iter().all(|text| regex.is_match(text)))
+                .map_err(Clone::clone)
+        });
+
+        match matches {
+            Ok(true) => self,
+            Ok(false) => self.with_error(
+                field_name,
+                format!("Value does not match the pattern `{pattern}`"),
+            ),
+            Err(err) => self.with_error(field_name, format!("Invalid validation pattern: {err}")),
+        }
+    }
+
+    /// Checks that the value is one of the given options.
+    pub fn one_of(self, field_name: &str, value: &impl FieldValue, options: &[&str]) -> Self {
+        if value
+            .texts()
+            .into_iter()
+            .all(|text| options.contains(&text))
+        {
+            self
+        } else {
+            self.with_error(
+                field_name,
+                format!("Value must be one of: {}", options.join(", ")),
+            )
+        }
+    }
+
+    /// Checks that the value is at least the given minimum.
+    pub fn min(self, field_name: &str, value: &impl FieldValue, min: f64) -> Self {
+        if value.numbers().into_iter().all(|number| number >= min) {
+            self
+        } else {
+            self.with_error(field_name, format!("Value must be at least {min}"))
+        }
+    }
+
+    /// Checks that the value is at most the given maximum.
+    pub fn max(self, field_name: &str, value: &impl FieldValue, max: f64) -> Self {
+        if value.numbers().into_iter().all(|number| number <= max) {
+            self
+        } else {
+            self.with_error(field_name, format!("Value must be at most {max}"))
+        }
+    }
+
+    /// Adds the errors returned by the `validate()` method of a flattened
+    /// type.
+    ///
+    /// Only validation errors are collected, since those are the only errors
+    /// returned by `validate()`.
+    pub fn nested(mut self, result: Result<()>) -> Self {
+        if let Err(Error::ValidationError { errors }) = result {
+            for error in errors {
+                self = self.with_error(&error.field_name, error.message);
+            }
+        }
+        self
+    }
+
+    /// Returns an [`Error::ValidationError`] if any of the checks failed.
+    pub fn finish(self) -> Result<()> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::ValidationError {
+                errors: self.errors,
+            })
+        }
+    }
+
+    /// Adds an error, unless the field already has one.
+    fn with_error(mut self, field_name: &str, message: String) -> Self {
+        if !self
+            .errors
+            .iter()
+            .any(|error| error.field_name == field_name)
+        {
+            self.errors.push(
+                ValidationError::builder()
+                    .field_name(field_name.to_owned())
+                    .message(message)
+                    .build(),
+            );
+        }
+        self
+    }
+}
+
+/// Values that can be checked by a [`Validator`].
+pub trait FieldValue {
+    /// Returns whether the value is empty or consists of whitespace only.
+    fn is_blank(&self) -> bool {
+        false
+    }
+
+    /// Returns the textual values to validate.
+    fn texts(&self) -> Vec<&str> {
+        Vec::new()
+    }
+
+    /// Returns the numeric values to validate.
+    fn numbers(&self) -> Vec<f64> {
+        Vec::new()
+    }
+}
+
+impl FieldValue for String {
+    fn is_blank(&self) -> bool {
+        self.trim().is_empty()
+    }
+
+    fn texts(&self) -> Vec<&str> {
+        vec![self]
+    }
+}
+
+impl FieldValue for Secret {
+    fn is_blank(&self) -> bool {
+        self.expose_secret().trim().is_empty()
+    }
+
+    fn texts(&self) -> Vec<&str> {
+        vec![self.expose_secret()]
+    }
+}
+
+impl FieldValue for Url {
+    fn texts(&self) -> Vec<&str> {
+        vec![self.as_str()]
+    }
+}
+
+impl<T: FieldValue> FieldValue for Option<T> {
+    fn is_blank(&self) -> bool {
+        self.as_ref().map_or(false, FieldValue::is_blank)
+    }
+
+    fn texts(&self) -> Vec<&str> {
+        self.as_ref().map(FieldValue::texts).unwrap_or_default()
+    }
+
+    fn numbers(&self) -> Vec<f64> {
+        self.as_ref().map(FieldValue::numbers).unwrap_or_default()
+    }
+}
+
+impl<T: FieldValue> FieldValue for Vec<T> {
+    fn is_blank(&self) -> bool {
+        self.is_empty() || self.iter().any(FieldValue::is_blank)
+    }
+
+    fn texts(&self) -> Vec<&str> {
+        self.iter().flat_map(FieldValue::texts).collect()
+    }
+
+    fn numbers(&self) -> Vec<f64> {
+        self.iter().flat_map(FieldValue::numbers).collect()
+    }
+}
+
+impl FieldValue for Duration {
+    fn numbers(&self) -> Vec<f64> {
+        vec![self.as_millis() as f64 / 1000.0]
+    }
+}
+
+impl FieldValue for f64 {
+    fn numbers(&self) -> Vec<f64> {
+        vec![*self]
+    }
+}
+
+macro_rules! impl_field_value_for_numbers {
+    ($($ty:ty),*) => {
+        $(
+            impl FieldValue for $ty {
+                fn numbers(&self) -> Vec<f64> {
+                    vec![*self as f64]
+                }
+            }
+        )*
+    };
+}
+
+impl_field_value_for_numbers!(f32, i8, i16, i32, i64, u8, u16, u32, u64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field_errors(result: Result<()>) -> Vec<(String, String)> {
+        match result {
+            Ok(()) => Vec::new(),
+            Err(Error::ValidationError { errors }) => errors
+                .into_iter()
+                .map(|error| (error.field_name, error.message))
+                .collect(),
+            Err(other) => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn reports_one_error_per_field() {
+        let errors = field_errors(
+            Validator::new()
+                .non_empty("query", &" ".to_owned())
+                .regex("query", &" ".to_owned(), "[a-z]+")
+                .url("endpoint", &"localhost".to_owned())
+                .one_of("method", &"PUT".to_owned(), &["GET", "POST"])
+                .min("step", &0u32, 1.0)
+                .max("limit", &Some(1_000i64), 100.0)
+                .max("quantile", &1.5f64, 1.0)
+                .min("period", &Duration::from_millis(500), 1.0)
+                .finish(),
+        );
+
+        assert_eq!(
+            errors,
+            vec![
+                ("query".to_owned(), "Please enter a value".to_owned()),
+                (
+                    "endpoint".to_owned(),
+                    "Please enter a valid URL: relative URL without a base".to_owned()
+                ),
+                (
+                    "method".to_owned(),
+                    "Value must be one of: GET, POST".to_owned()
+                ),
+                ("step".to_owned(), "Value must be at least 1".to_owned()),
+                ("limit".to_owned(), "Value must be at most 100".to_owned()),
+                ("quantile".to_owned(), "Value must be at most 1".to_owned()),
+                ("period".to_owned(), "Value must be at least 1".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn accepts_valid_and_missing_values() {
+        let errors = field_errors(
+            Validator::new()
+                .non_empty("query", &"up".to_owned())
+                .non_empty("label", &None::<String>)
+                .regex(
+                    "services",
+                    &vec!["api".to_owned(), "web".to_owned()],
+                    "[a-z]+",
+                )
+                .url("endpoint", &"http://localhost:9090".to_owned())
+                .min("step", &None::<u32>, 1.0)
+                .max("quantile", &0.99f32, 1.0)
+                .finish(),
+        );
+
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn collects_errors_of_nested_types() {
+        let nested = Validator::new()
+            .non_empty("token", &"".to_owned())
+            .non_empty("query", &"".to_owned())
+            .finish();
+        let errors = field_errors(
+            Validator::new()
+                .non_empty("query", &"".to_owned())
+                .nested(nested)
+                .nested(Ok(()))
+                .finish(),
+        );
+
+        assert_eq!(
+            errors,
+            vec![
+                ("query".to_owned(), "Please enter a value".to_owned()),
+                ("token".to_owned(), "Please enter a value".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn matches_patterns_against_entire_value() {
+        let errors = field_errors(
+            Validator::new()
+                .regex("service", &"api-gateway".to_owned(), "api")
+                .finish(),
+        );
+
+        assert_eq!(
+            errors,
+            vec![(
+                "service".to_owned(),
+                "Value does not match the pattern `api`".to_owned()
+            )]
+        );
+    }
+}
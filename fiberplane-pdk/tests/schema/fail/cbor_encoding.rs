@@ -0,0 +1,15 @@
+//! This test makes sure that code does not compile when using the CBOR
+//! encoding without the `cbor` feature of the PDK.
+
+use fiberplane_pdk_macros::ProviderData;
+use serde::{Deserialize, Serialize};
+
+const RESULT_MIME_TYPE: &str = "application/vnd.fiberplane.test.result";
+
+#[derive(Deserialize, ProviderData, Serialize)]
+#[pdk(mime_type = RESULT_MIME_TYPE, encoding = cbor)]
+pub struct QueryResult {
+    values: Vec<f64>,
+}
+
+pub fn main() {}
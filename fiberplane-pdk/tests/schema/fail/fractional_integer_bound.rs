@@ -0,0 +1,13 @@
+//! This test makes sure that code does not compile when an integer field has
+//! a fractional `min` or `max` bound.
+
+use fiberplane_pdk_macros::QuerySchema;
+use serde::Deserialize;
+
+#[derive(Deserialize, QuerySchema)]
+pub struct Query {
+    #[pdk(max = 0.99)]
+    limit: u32,
+}
+
+pub fn main() {}
@@ -0,0 +1,18 @@
+//! This test makes sure that code does not compile when a handler returns
+//! data of which the MIME type is not one of the supported MIME types of its
+//! query type.
+
+use fiberplane_pdk::prelude::*;
+
+async fn query_events(_request: ProviderRequest) -> Result<Events> {
+    Ok(Events(Vec::new()))
+}
+
+pdk_query_types! {
+    TIMESERIES_QUERY_TYPE => {
+        handler: query_events(ProviderRequest).await -> Events,
+        supported_mime_types: [TIMESERIES_MIME_TYPE]
+    }
+}
+
+pub fn main() {}
@@ -0,0 +1,83 @@
+//! This test ensures that fields with a default value are not required, and
+//! that the default is used as their initial value.
+
+// Note: users of the macro should never have to pick imports like that.
+// Using simply `use fiberplane_pdk::prelude::*` should be enough.
+use fiberplane_models::{
+    blobs::Blob,
+    providers::{
+        CheckboxField, IntegerField, QueryField, SelectField, TextField, FORM_ENCODED_MIME_TYPE,
+    },
+};
+use fiberplane_pdk::prelude::Duration;
+use fiberplane_pdk_macros::QuerySchema;
+use serde::Deserialize;
+
+fn default_limit() -> u32 {
+    100
+}
+
+#[derive(Debug, Deserialize, PartialEq, QuerySchema)]
+pub struct Query {
+    #[serde(default = "default_limit")]
+    #[pdk(default = 100)]
+    limit: u32,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[pdk(default = "5m")]
+    step: Option<Duration>,
+    #[pdk(select, option = "asc", option = "desc", default = "desc")]
+    order: Option<String>,
+    #[serde(default)]
+    #[pdk(default = true)]
+    live: bool,
+}
+
+pub fn main() {
+    assert_eq!(
+        Query::schema(),
+        vec![
+            QueryField::from(
+                IntegerField::new()
+                    .with_name("limit")
+                    .with_min(0)
+                    .with_initial_value(100)
+            ),
+            QueryField::from(TextField::new().with_name("tags").multiple()),
+            QueryField::from(
+                TextField::new()
+                    .with_name("step")
+                    .with_placeholder("e.g. 30s, 5m or 1h30m")
+                    .with_initial_value("5m")
+            ),
+            QueryField::from(
+                SelectField::new()
+                    .with_name("order")
+                    .with_options(["asc".to_owned(), "desc".to_owned()])
+                    .with_initial_value("desc")
+            ),
+            QueryField::from(
+                CheckboxField::new()
+                    .with_name("live")
+                    .with_value("true")
+                    .checked_by_default()
+            ),
+        ]
+    );
+
+    let input = Blob::builder()
+        .mime_type(FORM_ENCODED_MIME_TYPE)
+        .data("step=1m")
+        .build();
+
+    assert_eq!(
+        Query::parse(input).unwrap(),
+        Query {
+            limit: 100,
+            tags: Vec::new(),
+            step: Some(Duration::from_secs(60)),
+            order: None,
+            live: false,
+        }
+    );
+}
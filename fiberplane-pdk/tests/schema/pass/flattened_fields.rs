@@ -0,0 +1,32 @@
+//! This test ensures that fields annotated with `#[serde(flatten)]` are
+//! expanded into the fields of the flattened type.
+
+// Note: users of the macro should never have to pick imports like that.
+// Using simply `use fiberplane_pdk::prelude::*` should be enough.
+use fiberplane_models::providers::{QueryField, TextField};
+use fiberplane_pdk_macros::QuerySchema;
+use serde::Deserialize;
+
+#[derive(Deserialize, QuerySchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Filter {
+    service_name: Option<String>,
+}
+
+#[derive(Deserialize, QuerySchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Query {
+    query_string: String,
+    #[serde(flatten)]
+    filter: Option<Filter>,
+}
+
+pub fn main() {
+    assert_eq!(
+        Query::schema(),
+        vec![
+            QueryField::from(TextField::new().with_name("queryString").required()),
+            QueryField::from(TextField::new().with_name("serviceName")),
+        ]
+    );
+}
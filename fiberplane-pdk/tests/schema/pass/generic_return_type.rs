@@ -0,0 +1,26 @@
+//! This test ensures that handlers in `pdk_query_types!` may return data of a
+//! type with several generic arguments.
+
+use fiberplane_pdk::prelude::*;
+use serde::{Deserialize, Serialize};
+
+const TABLE_MIME_TYPE: &str = "application/vnd.fiberplane.test.table";
+
+#[derive(Deserialize, ProviderData, Serialize)]
+#[pdk(mime_type = TABLE_MIME_TYPE)]
+pub struct Table<K, V> {
+    rows: Vec<(K, V)>,
+}
+
+async fn query_table(_request: ProviderRequest) -> Result<Table<String, f64>> {
+    Ok(Table { rows: Vec::new() })
+}
+
+pdk_query_types! {
+    "x-table" => {
+        handler: query_table(ProviderRequest).await -> Table<String, f64>,
+        label: "Table query"
+    }
+}
+
+pub fn main() {}
@@ -0,0 +1,101 @@
+//! This test ensures that flattened structs and enums in configs generate
+//! fields that match how the config is deserialized.
+
+// Note: users of the macro should never have to pick imports like that.
+// Using simply `use fiberplane_pdk::prelude::*` should be enough.
+use fiberplane_models::providers::{ConfigField, SelectField, TextField};
+use fiberplane_pdk::prelude::Error;
+use fiberplane_pdk_macros::ConfigSchema;
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(ConfigSchema, Debug, Deserialize, PartialEq)]
+#[pdk(nested)]
+#[serde(rename_all = "camelCase")]
+pub struct Api {
+    #[pdk(url)]
+    base_url: String,
+    #[pdk(secret)]
+    token: Option<String>,
+}
+
+#[derive(ConfigSchema, Debug, Deserialize, PartialEq)]
+#[pdk(nested, label = "Storage")]
+#[serde(tag = "storage", rename_all = "snake_case")]
+pub enum Storage {
+    Memory,
+    Disk {
+        #[pdk(label = "Directory", non_empty)]
+        path: String,
+    },
+}
+
+// Nested, so the test doesn't need to export `get_config_schema()`.
+#[derive(ConfigSchema, Debug, Deserialize, PartialEq)]
+#[pdk(nested)]
+pub struct Config {
+    #[serde(flatten)]
+    api: Option<Api>,
+    #[serde(flatten)]
+    storage: Storage,
+}
+
+// Config fields can't be compared directly, so they're compared as JSON.
+fn to_json(fields: Vec<ConfigField>) -> serde_json::Value {
+    serde_json::to_value(fields).unwrap()
+}
+
+pub fn main() {
+    assert_eq!(
+        to_json(Config::schema()),
+        to_json(vec![
+            ConfigField::from(TextField::new().with_name("baseUrl")),
+            ConfigField::from(TextField::new().with_name("token")),
+            ConfigField::from(
+                SelectField::new()
+                    .with_name("storage")
+                    .with_label("Storage")
+                    .with_options(["memory".to_owned(), "disk".to_owned()])
+                    .required()
+            ),
+            ConfigField::from(
+                TextField::new()
+                    .with_name("path")
+                    .with_label("Directory")
+                    .with_prerequisites(["storage".to_owned()])
+            ),
+        ])
+    );
+    assert_eq!(
+        to_json(Api::schema()),
+        to_json(vec![
+            ConfigField::from(TextField::new().with_name("baseUrl").required()),
+            ConfigField::from(TextField::new().with_name("token")),
+        ])
+    );
+    assert_eq!(Config::secret_fields(), vec!["token"]);
+
+    assert_eq!(
+        Config::parse(json!({ "storage": "disk", "path": "/tmp" })).unwrap(),
+        Config {
+            api: None,
+            storage: Storage::Disk {
+                path: "/tmp".to_owned()
+            },
+        }
+    );
+
+    let Err(Error::ValidationError { errors }) = Config::parse(json!({
+        "baseUrl": "localhost",
+        "storage": "disk",
+        "path": " ",
+    })) else {
+        panic!("expected validation errors");
+    };
+
+    let field_names: Vec<_> = errors
+        .iter()
+        .map(|error| error.field_name.as_str())
+        .collect();
+    assert_eq!(field_names, vec!["baseUrl", "path"]);
+}
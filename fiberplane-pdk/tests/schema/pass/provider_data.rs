@@ -0,0 +1,49 @@
+//! This test ensures that `ProviderData` can be derived for enums and generic
+//! types, and that their blobs can be parsed again.
+
+// Note: users of the macro should never have to pick imports like that.
+// Using simply `use fiberplane_pdk::prelude::*` should be enough.
+use fiberplane_pdk::provider_data::ProviderData;
+use fiberplane_pdk_macros::ProviderData;
+use serde::{Deserialize, Serialize};
+
+const RESULT_MIME_TYPE: &str = "application/vnd.fiberplane.test.result";
+const PAGED_MIME_TYPE: &str = "application/vnd.fiberplane.test.paged";
+
+#[derive(Debug, Deserialize, PartialEq, ProviderData, Serialize)]
+#[pdk(mime_type = RESULT_MIME_TYPE, encoding = json)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryResult {
+    Values(Vec<f64>),
+    Table { columns: Vec<String> },
+}
+
+#[derive(Debug, Deserialize, PartialEq, ProviderData, Serialize)]
+#[pdk(mime_type = PAGED_MIME_TYPE)]
+pub struct Paged<T> {
+    items: Vec<T>,
+    continuation_token: Option<String>,
+}
+
+pub fn main() {
+    let result = QueryResult::Table {
+        columns: vec!["name".to_owned()],
+    };
+    let blob = result.to_blob().unwrap();
+    assert_eq!(
+        blob.mime_type,
+        "application/vnd.fiberplane.test.result+json"
+    );
+    assert_eq!(QueryResult::parse_blob(blob).unwrap(), result);
+
+    let page = Paged {
+        items: vec![QueryResult::Values(vec![1.0, 2.0])],
+        continuation_token: Some("page-2".to_owned()),
+    };
+    let blob = page.to_blob().unwrap();
+    assert_eq!(
+        blob.mime_type,
+        "application/vnd.fiberplane.test.paged+msgpack"
+    );
+    assert_eq!(Paged::parse_blob(blob).unwrap(), page);
+}
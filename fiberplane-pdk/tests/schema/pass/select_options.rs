@@ -0,0 +1,69 @@
+//! This test ensures that fields of enums deriving `SelectOptions` generate
+//! select fields, with options that match how the enum is deserialized.
+
+// Note: users of the macro should never have to pick imports like that.
+// Using simply `use fiberplane_pdk::prelude::*` should be enough.
+use fiberplane_models::{
+    blobs::Blob,
+    providers::{QueryField, SelectField, FORM_ENCODED_MIME_TYPE},
+};
+use fiberplane_pdk::select_options::SelectOptions;
+use fiberplane_pdk_macros::{QuerySchema, SelectOptions};
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, SelectOptions)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    InProgress,
+    #[serde(rename = "done")]
+    #[pdk(label = "Finished")]
+    Complete,
+}
+
+#[derive(Debug, Deserialize, PartialEq, QuerySchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Query {
+    #[pdk(label = "Status of the item", select)]
+    status: Status,
+    #[pdk(select)]
+    other_statuses: Option<Vec<Status>>,
+}
+
+pub fn main() {
+    assert_eq!(Status::options(), vec!["in_progress", "done"]);
+    assert_eq!(Status::InProgress.label(), "In progress");
+    assert_eq!(Status::Complete.label(), "Finished");
+    assert_eq!(Status::from_value("done"), Some(&Status::Complete));
+
+    assert_eq!(
+        Query::schema(),
+        vec![
+            QueryField::from(
+                SelectField::new()
+                    .with_name("status")
+                    .with_label("Status of the item")
+                    .with_options(["in_progress".to_owned(), "done".to_owned()])
+                    .required()
+            ),
+            QueryField::from(
+                SelectField::new()
+                    .with_name("otherStatuses")
+                    .multiple()
+                    .with_options(["in_progress".to_owned(), "done".to_owned()])
+            ),
+        ]
+    );
+
+    let input = Blob::builder()
+        .mime_type(FORM_ENCODED_MIME_TYPE)
+        .data("status=done&otherStatuses[0]=in_progress")
+        .build();
+
+    assert_eq!(
+        Query::parse(input).unwrap(),
+        Query {
+            status: Status::Complete,
+            other_statuses: Some(vec![Status::InProgress]),
+        }
+    );
+}
@@ -0,0 +1,52 @@
+//! This test ensures that validation attributes are enforced when parsing
+//! query data, with a single error per offending field.
+
+// Note: users of the macro should never have to pick imports like that.
+// Using simply `use fiberplane_pdk::prelude::*` should be enough.
+use fiberplane_models::{blobs::Blob, providers::FORM_ENCODED_MIME_TYPE};
+use fiberplane_pdk::prelude::Error;
+use fiberplane_pdk_macros::QuerySchema;
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize, QuerySchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Query {
+    #[pdk(non_empty, regex = "[a-z_]+")]
+    metric_name: String,
+    #[pdk(one_of = ["avg", "max"])]
+    aggregation: Option<String>,
+    #[pdk(url)]
+    endpoint: Option<String>,
+    #[pdk(min = 1, max = 3600)]
+    period: u32,
+    #[pdk(min = 0, max = 0.99)]
+    ratio: Option<f64>,
+}
+
+fn parse(data: &'static str) -> Result<Query, Error> {
+    Query::parse(
+        Blob::builder()
+            .mime_type(FORM_ENCODED_MIME_TYPE)
+            .data(data)
+            .build(),
+    )
+}
+
+pub fn main() {
+    assert!(parse("metricName=cpu_usage&aggregation=avg&period=60&ratio=0.99").is_ok());
+
+    let Err(Error::ValidationError { errors }) =
+        parse("metricName=+&aggregation=sum&endpoint=localhost&period=0&ratio=1")
+    else {
+        panic!("expected validation errors");
+    };
+
+    let field_names: Vec<_> = errors
+        .iter()
+        .map(|error| error.field_name.as_str())
+        .collect();
+    assert_eq!(
+        field_names,
+        vec!["metricName", "aggregation", "endpoint", "period", "ratio"]
+    );
+}
@@ -0,0 +1,73 @@
+//! This test ensures that floating-point numbers, URLs and durations generate
+//! text fields with a suitable placeholder, and that they are validated when
+//! parsing.
+
+// Note: users of the macro should never have to pick imports like that.
+// Using simply `use fiberplane_pdk::prelude::*` should be enough.
+use fiberplane_models::{
+    blobs::Blob,
+    providers::{QueryField, TextField, FORM_ENCODED_MIME_TYPE},
+};
+use fiberplane_pdk::prelude::{Duration, Error};
+use fiberplane_pdk_macros::QuerySchema;
+use serde::Deserialize;
+use url::Url;
+
+#[derive(Debug, Deserialize, PartialEq, QuerySchema)]
+pub struct Query {
+    #[pdk(min = 0.5, max = 0.99)]
+    quantile: f64,
+    endpoint: Option<Url>,
+    #[pdk(min = 1, placeholder = "5m")]
+    step: Option<Duration>,
+}
+
+fn parse(data: &'static str) -> Result<Query, Error> {
+    Query::parse(
+        Blob::builder()
+            .mime_type(FORM_ENCODED_MIME_TYPE)
+            .data(data)
+            .build(),
+    )
+}
+
+pub fn main() {
+    assert_eq!(
+        Query::schema(),
+        vec![
+            QueryField::from(
+                TextField::new()
+                    .with_name("quantile")
+                    .with_placeholder("e.g. 0.5")
+                    .required()
+            ),
+            QueryField::from(
+                TextField::new()
+                    .with_name("endpoint")
+                    .with_placeholder("https://")
+            ),
+            QueryField::from(TextField::new().with_name("step").with_placeholder("5m")),
+        ]
+    );
+
+    assert_eq!(
+        parse("quantile=0.95&endpoint=http%3A%2F%2Flocalhost%3A9090&step=1m30s").unwrap(),
+        Query {
+            quantile: 0.95,
+            endpoint: Some(Url::parse("http://localhost:9090").unwrap()),
+            step: Some(Duration::from_secs(90)),
+        }
+    );
+
+    let Err(Error::ValidationError { errors }) = parse("quantile=0.995&step=500ms") else {
+        panic!("expected validation errors");
+    };
+
+    let field_names: Vec<_> = errors
+        .iter()
+        .map(|error| error.field_name.as_str())
+        .collect();
+    assert_eq!(field_names, vec!["quantile", "step"]);
+
+    assert!(parse("quantile=0.5&step=5x").is_err());
+}
@@ -7,18 +7,17 @@ mod sigv4;
 pub use self::canonical_request::request_state;
 pub use canonical_request::CanonicalRequest;
 use fiberplane_pdk::{
-    prelude::{log, make_http_request, now},
-    providers::{Error, HttpRequest, HttpRequestError, HttpRequestMethod},
+    http::{decode_json, send_with_retries},
+    prelude::{now, RetryPolicy, Secret},
+    providers::{Error, HttpRequest, HttpRequestMethod},
 };
 use http::Method;
-use secrecy::SecretString;
 use serde::de::DeserializeOwned;
 use std::collections::BTreeMap;
 
 #[derive(Debug)]
 pub enum ClientError {
     InvalidRequest(String),
-    Host(HttpRequestError),
     UnexpectedResponse { expected: String, actual: String },
 }
 
@@ -30,7 +29,6 @@ impl std::fmt::Display for ClientError {
             ClientError::InvalidRequest(e) => {
                 write!(f, "invalid request to send to runtime host: {e}")
             }
-            ClientError::Host(e) => write!(f, "runtime http client error: {e:?}"),
             ClientError::UnexpectedResponse { expected, actual } => {
                 write!(
                     f,
@@ -55,8 +53,8 @@ pub struct ClientCommon {
     host: String,
     endpoint: String,
     region: String,
-    access_key_id: SecretString,
-    secret_access_key: SecretString,
+    access_key_id: Secret,
+    secret_access_key: Secret,
 }
 
 impl ClientCommon {
@@ -64,7 +62,7 @@ impl ClientCommon {
         &self,
         request: R,
         extra_unsigned_headers: Option<&BTreeMap<String, String>>,
-    ) -> Result<D, ClientError>
+    ) -> Result<D, Error>
     where
         R: Into<CanonicalRequest<{ request_state::STEM }>>,
         D: DeserializeOwned,
@@ -93,42 +91,15 @@ impl ClientCommon {
         let request = {
             let mut request = HttpRequest::default();
             request.method = method;
-            request.url = url;
+            request.url = url.clone();
             request.headers = Some(headers.clone());
             request.body = canonical.body;
             request
         };
 
-        log(format!("CloudWatch: Sending request {request:?}"));
-
-        make_http_request(request)
-            .await
-            .map_err(|err| {
-                match &err {
-                    HttpRequestError::ServerError {
-                        status_code,
-                        response,
-                    } => {
-                        log(format!(
-                            "CloudWatch: HTTP error: {status_code}; {:?}",
-                            String::from_utf8_lossy(response)
-                        ));
-                    }
-                    other => log(format!("CloudWatch: HTTP error: {other:?}")),
-                };
-                ClientError::Host(err)
-            })
-            .and_then(|response| {
-                serde_json::from_slice(&response.body).map_err(|err| {
-                    log(format!(
-                        "CloudWatch response body:\n{}",
-                        String::from_utf8_lossy(&response.body)
-                    ));
-                    ClientError::InvalidRequest(format!(
-                        "could not deserialize the result of the call: {err}"
-                    ))
-                })
-            })
+        // All AWS APIs use `POST`, so only throttled requests are retried.
+        let response = send_with_retries(request, &RetryPolicy::default()).await?;
+        decode_json(&url, &response)
     }
 
     fn format_headers(
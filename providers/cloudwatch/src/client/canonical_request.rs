@@ -10,10 +10,9 @@ pub mod request_state {
 
 use super::sigv4;
 use bytes::Bytes;
-use fiberplane_pdk::prelude::Timestamp;
+use fiberplane_pdk::prelude::{Secret, Timestamp};
 use http::Method;
 use itertools::Itertools;
-use secrecy::SecretString;
 use std::collections::BTreeMap;
 
 /// A valid Canonical Request for AWS API.
@@ -107,8 +106,8 @@ impl CanonicalRequest<{ request_state::READY_TO_SIGN_V4 }> {
 
     pub fn to_sigv4_auth_header(
         &self,
-        access_key_id: &SecretString,
-        secret_access_key: &SecretString,
+        access_key_id: &Secret,
+        secret_access_key: &Secret,
         region: &str,
         service: &str,
     ) -> (String, String) {
@@ -1,4 +1,4 @@
-use super::{ClientCommon, ClientError};
+use super::ClientCommon;
 use crate::{api::paginate::paginate_vec, config::Config, types::api::cloudwatch_logs::*};
 use fiberplane_pdk::prelude::{Error, Timestamp};
 use std::collections::HashMap;
@@ -93,7 +93,7 @@ impl Client {
     pub async fn get_query_results(
         &self,
         query_id: String,
-    ) -> Result<GetQueryResultsResponse, ClientError> {
+    ) -> Result<GetQueryResultsResponse, Error> {
         self.common
             .send(GetQueryResultsRequest { query_id }, None)
             .await
@@ -111,7 +111,6 @@ impl Client {
         self.common
             .send(request, None)
             .await
-            .map_err(Into::into)
             .map(|r: GetLogRecordResponse| r.log_record)
     }
 }
@@ -4,9 +4,9 @@ use std::ops::Deref;
 
 use super::{canonical_request::request_state, CanonicalRequest};
 use bytes::Bytes;
-use fiberplane_pdk::prelude::Timestamp;
+use fiberplane_pdk::prelude::{Secret, Timestamp};
+use fiberplane_pdk::tracing::trace;
 use hmac::{Hmac, Mac};
-use secrecy::{ExposeSecret, SecretString};
 use sha2::{Digest, Sha256};
 use time::{
     format_description::well_known::Iso8601,
@@ -18,8 +18,8 @@ static AWS4_REQUEST: &str = "aws4_request";
 
 pub fn format_auth_header_value(
     request: &CanonicalRequest<{ request_state::READY_TO_SIGN_V4 }>,
-    access_key_id: &SecretString,
-    secret_access_key: &SecretString,
+    access_key_id: &Secret,
+    secret_access_key: &Secret,
     region: &str,
     service: &str,
 ) -> (String, String) {
@@ -68,7 +68,7 @@ fn sign(key: &[u8], message: String) -> Vec<u8> {
 }
 
 fn signature_key(
-    secret_access_key: &SecretString,
+    secret_access_key: &Secret,
     region: &str,
     date: &Timestamp,
     service: &str,
@@ -93,7 +93,7 @@ fn credential_scope(region: &str, date: &Timestamp, service: &str) -> String {
 }
 
 pub fn signature(
-    secret_access_key: &SecretString,
+    secret_access_key: &Secret,
     region: &str,
     service: &str,
     request: &CanonicalRequest<{ request_state::READY_TO_SIGN_V4 }>,
@@ -103,10 +103,7 @@ pub fn signature(
 
     let credential_scope = credential_scope(region, date, service);
     let canonical_payload = request.to_string();
-    // log(format!(
-    //     "CloudWatch: Canonical payload being hashed\n{}",
-    //     canonical_payload
-    // ));
+    trace!(%canonical_payload, "Hashing canonical payload");
     let request_digest = hash_to_hexstring(canonical_payload);
     let message = format!(
         "{}\n{}\n{}\n{}",
@@ -115,7 +112,7 @@ pub fn signature(
         credential_scope,
         request_digest
     );
-    // log(format!("CloudWatch: message being signed\n{}", message));
+    trace!(%message, "Signing message");
 
     hex::encode(sign(key.as_slice(), message))
 }
@@ -144,7 +141,7 @@ mod tests {
 20150830/us-east-1/iam/aws4_request
 f536975d06c0309214f805bb90ccff089219ecd68b2577efef23edd43b7e1a59"#;
 
-        let secret_access_key = "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".parse().unwrap();
+        let secret_access_key = Secret::new("wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY");
         let sign_time = datetime!(2015-08-30 12:36:00 UTC).into();
 
         let key = signature_key(&secret_access_key, "us-east-1", &sign_time, "iam");
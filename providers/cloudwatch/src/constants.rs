@@ -1,4 +1,3 @@
-use const_format::formatcp;
 pub use fiberplane_pdk::providers::{
     CELLS_MIME_TYPE, EVENTS_MIME_TYPE, FORM_ENCODED_MIME_TYPE, SUGGESTIONS_MIME_TYPE,
     TIMESERIES_MIME_TYPE,
@@ -14,11 +13,6 @@ pub const START_LOG_QUERY_QUERY_TYPE: &str = "x-start-query";
 pub const GET_QUERY_RESULTS_QUERY_TYPE: &str = "x-get-query-results";
 pub const GET_LOG_RECORD_QUERY_TYPE: &str = "x-get-log-record";
 
-pub const SUGGESTIONS_MSGPACK_MIME_TYPE: &str = formatcp!("{SUGGESTIONS_MIME_TYPE}+msgpack");
-pub const TIMESERIES_MSGPACK_MIME_TYPE: &str = formatcp!("{TIMESERIES_MIME_TYPE}+msgpack");
-pub const CELLS_MSGPACK_MIME_TYPE: &str = formatcp!("{CELLS_MIME_TYPE}+msgpack");
-pub const EVENTS_MSGPACK_MIME_TYPE: &str = formatcp!("{EVENTS_MIME_TYPE}+msgpack");
-pub const EVENTS_JSON_MIME_TYPE: &str = formatcp!("{EVENTS_MIME_TYPE}+json");
 pub const LIST_METRICS_MIME_TYPE: &str = "application/json";
 pub const QUERY_DATA_MIME_TYPE: &str = FORM_ENCODED_MIME_TYPE;
 pub const QUERY_RESULTS_MIME_TYPE: &str =
@@ -26,7 +20,6 @@ pub const QUERY_RESULTS_MIME_TYPE: &str =
 
 pub const TIME_RANGE_PARAM_NAME: &str = "time_range";
 pub const EXPRESSION_PARAM_NAME: &str = "expression";
-pub const LABEL_PARAM_NAME: &str = "label";
 pub const PERIOD_PARAM_NAME: &str = "timeperiod";
 pub const TAG_KEY_PARAM_NAME: &str = "tag_key";
 pub const TAG_VALUE_PARAM_NAME: &str = "tag_value";
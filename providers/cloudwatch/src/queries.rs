@@ -8,18 +8,10 @@ pub mod list_metrics;
 pub mod start_query;
 pub mod status;
 
-use crate::constants::CELLS_MSGPACK_MIME_TYPE;
-use fiberplane_pdk::prelude::{Blob, Cell, Error, Timestamp};
+use crate::constants::CELLS_MIME_TYPE;
+use fiberplane_pdk::prelude::{Blob, Cell, Encoding, Error};
+use fiberplane_pdk::provider_data::to_blob_with_encoding;
 
 pub fn serialize_cells(cells: Vec<Cell>) -> Result<Blob, Error> {
-    Ok(Blob::builder()
-        .data(rmp_serde::to_vec_named(&cells)?)
-        .mime_type(CELLS_MSGPACK_MIME_TYPE.to_owned())
-        .build())
-}
-
-pub fn try_from_iso_date(timestamp: &str) -> Result<Timestamp, Error> {
-    Timestamp::parse(timestamp).map_err(|err| Error::Deserialization {
-        message: format!("could not deserialize timestamp '{timestamp}': {err}"),
-    })
+    to_blob_with_encoding(CELLS_MIME_TYPE, &cells, Encoding::MessagePack)
 }
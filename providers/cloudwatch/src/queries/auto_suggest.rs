@@ -8,11 +8,13 @@ use crate::{
     constants::{
         DISCOVERABLE_FIELDS, EXPRESSION_PARAM_NAME, GRAPH_METRIC_QUERY_TYPE,
         LIST_METRICS_QUERY_TYPE, LOG_GROUP_PARAM_NAME, PERIOD_PARAM_NAME, QUERY_PARAM_NAME,
-        START_LOG_QUERY_QUERY_TYPE, SUGGESTIONS_MSGPACK_MIME_TYPE, TAG_KEY_PARAM_NAME,
+        START_LOG_QUERY_QUERY_TYPE, SUGGESTIONS_MIME_TYPE, TAG_KEY_PARAM_NAME,
         TAG_VALUE_PARAM_NAME,
     },
 };
 use fiberplane_pdk::prelude::*;
+use fiberplane_pdk::provider_data::to_blob_with_encoding;
+use fiberplane_pdk::tracing::warn;
 use itertools::Itertools;
 
 pub async fn invoke2_handler(query_data: Blob, config: Config) -> Result<Blob> {
@@ -23,18 +25,16 @@ pub async fn invoke2_handler(query_data: Blob, config: Config) -> Result<Blob> {
         START_LOG_QUERY_QUERY_TYPE => list_log_query_suggestions(query, config).await?,
         GRAPH_METRIC_QUERY_TYPE => list_graph_metric_suggestions(query, config).await?,
         unknown => {
-            log(format!(
-                "Received a suggestion query for unsupported query type {unknown}"
-            ));
+            warn!(
+                query_type = unknown,
+                "Received a suggestion query for an unsupported query type"
+            );
 
             return Err(Error::UnsupportedRequest);
         }
     };
 
-    Ok(Blob::builder()
-        .data(rmp_serde::to_vec_named(&suggestions)?)
-        .mime_type(SUGGESTIONS_MSGPACK_MIME_TYPE.to_owned())
-        .build())
+    to_blob_with_encoding(SUGGESTIONS_MIME_TYPE, &suggestions, Encoding::MessagePack)
 }
 
 async fn list_metrics_suggestions(
@@ -1,18 +1,20 @@
 //! Describe Queries query handling
 use super::serialize_cells;
 use crate::{
-    api::cloudwatch_logs::QueryInfo, client::cloudwatch_logs::Client, config::Config,
-    constants::LOG_GROUP_PARAM_NAME,
+    api::cloudwatch_logs::{QueryInfo, QueryStatus},
+    client::cloudwatch_logs::Client,
+    config::Config,
 };
-use fiberplane_pdk::prelude::{Blob, Cell, Error, ProviderRequest, TextCell};
+use fiberplane_pdk::prelude::{Blob, Cell, Error, ProviderRequest, QuerySchema, TextCell};
 use fiberplane_pdk::providers::FORM_ENCODED_MIME_TYPE;
+use serde::Deserialize;
 
 pub async fn invoke2_handler(config: Config, request: ProviderRequest) -> Result<Blob, Error> {
     let request: DescribeQueriesInput = request.query_data.try_into()?;
     let client = Client::from(&config);
 
     client
-        .describe_queries(request.log_group_name, None, None)
+        .describe_queries(request.log_group_name, request.status, None)
         .await
         .and_then(try_into_blob)
 }
@@ -36,8 +38,23 @@ fn try_into_blob(groups: Vec<QueryInfo>) -> Result<Blob, Error> {
     )
 }
 
+/// Query data for the describe queries query type.
+#[derive(Deserialize, QuerySchema)]
+pub(crate) struct DescribeQueriesQuery {
+    #[pdk(
+        label = "Group name to list the queries for",
+        placeholder = "RDSOSMetrics",
+        supports_suggestions
+    )]
+    log_group: Option<String>,
+
+    #[pdk(label = "Only list queries with this status", select)]
+    status: Option<QueryStatus>,
+}
+
 struct DescribeQueriesInput {
     log_group_name: Option<String>,
+    status: Option<QueryStatus>,
 }
 
 impl TryFrom<Blob> for DescribeQueriesInput {
@@ -48,15 +65,11 @@ impl TryFrom<Blob> for DescribeQueriesInput {
             return Err(Error::UnsupportedRequest);
         }
 
-        let mut log_group_name = None;
-        for (key, value) in form_urlencoded::parse(&blob.data) {
-            if let LOG_GROUP_PARAM_NAME = key.as_ref() {
-                if !value.is_empty() {
-                    log_group_name = Some(value.to_string());
-                }
-            }
-        }
+        let DescribeQueriesQuery { log_group, status } = DescribeQueriesQuery::parse(blob)?;
 
-        Ok(Self { log_group_name })
+        Ok(Self {
+            log_group_name: log_group.filter(|name| !name.is_empty()),
+            status,
+        })
     }
 }
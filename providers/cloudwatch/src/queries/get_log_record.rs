@@ -3,11 +3,12 @@ use crate::{
     client::cloudwatch_logs::Client,
     config::Config,
     constants::{
-        BODY_KEY, EVENTS_MIME_TYPE, EVENTS_MSGPACK_MIME_TYPE, INGESTION_TS_KEY, LOG_KEY,
-        LOG_RECORD_POINTER_PARAM_NAME, SPAN_KEY, TRACE_KEY, TS_KEY,
+        BODY_KEY, EVENTS_MIME_TYPE, INGESTION_TS_KEY, LOG_KEY, LOG_RECORD_POINTER_PARAM_NAME,
+        SPAN_KEY, TRACE_KEY, TS_KEY,
     },
 };
-use fiberplane_pdk::prelude::{Blob, Cell, Error, LogCell, ProviderRequest};
+use fiberplane_pdk::prelude::{Blob, Cell, Encoding, Error, LogCell, ProviderRequest};
+use fiberplane_pdk::provider_data::to_blob_with_encoding;
 use fiberplane_pdk::providers::{
     OtelMetadata, OtelSpanId, OtelTraceId, ProviderEvent, FORM_ENCODED_MIME_TYPE,
 };
@@ -116,10 +117,7 @@ pub fn convert_log_entry_to_event(res: HashMap<String, String>) -> Result<Provid
 
 fn try_into_blob(res: HashMap<String, String>) -> Result<Blob, Error> {
     let event = vec![convert_log_entry_to_event(res)?];
-    Ok(Blob::builder()
-        .data(rmp_serde::to_vec_named(&event)?)
-        .mime_type(EVENTS_MSGPACK_MIME_TYPE.to_owned())
-        .build())
+    to_blob_with_encoding(EVENTS_MIME_TYPE, &event, Encoding::MessagePack)
 }
 
 pub fn create_cells_handler(_response: Blob) -> Result<Vec<Cell>, Error> {
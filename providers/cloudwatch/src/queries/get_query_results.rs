@@ -4,11 +4,12 @@ use crate::{
     client::cloudwatch_logs::Client,
     config::Config,
     constants::{
-        BODY_KEY, EVENTS_JSON_MIME_TYPE, EVENTS_MIME_TYPE, EVENTS_MSGPACK_MIME_TYPE, LOG_KEY,
-        PTR_KEY, QUERY_ID_PARAM_NAME, QUERY_RESULTS_MIME_TYPE, SPAN_KEY, TRACE_KEY, TS_KEY,
+        BODY_KEY, EVENTS_MIME_TYPE, LOG_KEY, PTR_KEY, QUERY_ID_PARAM_NAME, QUERY_RESULTS_MIME_TYPE,
+        SPAN_KEY, TRACE_KEY, TS_KEY,
     },
 };
-use fiberplane_pdk::prelude::{Blob, Cell, Error, LogCell, ProviderRequest, TextCell};
+use fiberplane_pdk::prelude::{Blob, Cell, Encoding, Error, LogCell, ProviderRequest, TextCell};
+use fiberplane_pdk::provider_data::to_blob_with_encoding;
 use fiberplane_pdk::providers::{
     OtelMetadata, OtelSpanId, OtelTraceId, ProviderEvent, FORM_ENCODED_MIME_TYPE,
 };
@@ -21,12 +22,7 @@ pub async fn invoke2_handler(config: Config, request: ProviderRequest) -> Result
     let client = Client::from(&config);
 
     // Short version that doesn't fetch everything
-    let res = client
-        .get_query_results(request.query_id)
-        .await
-        .map_err(|e| Error::Invocation {
-            message: format!("failed to list metrics: {e}"),
-        })?;
+    let res = client.get_query_results(request.query_id).await?;
 
     let events = LogLines(
         res.results
@@ -143,10 +139,7 @@ impl TryFrom<QueryResults> for Blob {
     type Error = Error;
 
     fn try_from(value: QueryResults) -> Result<Self, Self::Error> {
-        Ok(Self::builder()
-            .data(rmp_serde::to_vec_named(&value)?)
-            .mime_type(format!("{QUERY_RESULTS_MIME_TYPE}+msgpack"))
-            .build())
+        to_blob_with_encoding(QUERY_RESULTS_MIME_TYPE, &value, Encoding::MessagePack)
     }
 }
 
@@ -154,21 +147,9 @@ impl TryFrom<Blob> for QueryResults {
     type Error = Error;
 
     fn try_from(blob: Blob) -> Result<Self, Self::Error> {
-        if blob.mime_type == format!("{QUERY_RESULTS_MIME_TYPE}+msgpack") {
-            Ok(
-                rmp_serde::from_slice(&blob.data).map_err(|e| Error::Deserialization {
-                    message: format!("could not deserialize query results: {e}"),
-                })?,
-            )
-        } else if blob.mime_type == format!("{QUERY_RESULTS_MIME_TYPE}+json") {
-            Ok(
-                serde_json::from_slice(&blob.data).map_err(|e| Error::Deserialization {
-                    message: format!("could not deserialize query results: {e}"),
-                })?,
-            )
-        } else {
-            Err(Error::UnsupportedRequest)
-        }
+        Encoding::from_mime_type(&blob.mime_type, QUERY_RESULTS_MIME_TYPE)
+            .ok_or(Error::UnsupportedRequest)?
+            .decode(&blob.data)
     }
 }
 
@@ -298,25 +279,10 @@ impl TryFrom<Blob> for LogLines {
     type Error = Error;
 
     fn try_from(blob: Blob) -> Result<Self, Self::Error> {
-        if blob.mime_type == EVENTS_MSGPACK_MIME_TYPE {
-            Ok(Self(
-                rmp_serde::from_slice::<Vec<ProviderEvent>>(&blob.data).map_err(|e| {
-                    Error::Deserialization {
-                        message: format!("could not deserialize log events: {e}"),
-                    }
-                })?,
-            ))
-        } else if blob.mime_type == EVENTS_JSON_MIME_TYPE {
-            Ok(Self(
-                serde_json::from_slice::<Vec<ProviderEvent>>(&blob.data).map_err(|e| {
-                    Error::Deserialization {
-                        message: format!("could not deserialize log events: {e}"),
-                    }
-                })?,
-            ))
-        } else {
-            Err(Error::UnsupportedRequest)
-        }
+        let events = Encoding::from_mime_type(&blob.mime_type, EVENTS_MIME_TYPE)
+            .ok_or(Error::UnsupportedRequest)?
+            .decode(&blob.data)?;
+        Ok(Self(events))
     }
 }
 
@@ -324,9 +290,6 @@ impl TryFrom<LogLines> for Blob {
     type Error = Error;
 
     fn try_from(value: LogLines) -> Result<Self, Self::Error> {
-        Ok(Self::builder()
-            .data(rmp_serde::to_vec_named(&value.0)?)
-            .mime_type(EVENTS_MSGPACK_MIME_TYPE.to_owned())
-            .build())
+        to_blob_with_encoding(EVENTS_MIME_TYPE, &value.0, Encoding::MessagePack)
     }
 }
@@ -17,7 +17,6 @@
 //! - `select sum(Invocations) from schema("AWS/Lambda", FunctionName) group by FunctionName`
 //! - `select avg(NumberOfObjects) from schema("AWS/S3", BucketName) group by BucketName`
 //!   + BucketSizeBytes
-use super::try_from_iso_date;
 use crate::{
     api::{
         cloudwatch::{MetricDataResult, ScanOrder},
@@ -25,43 +24,50 @@ use crate::{
     },
     client::{cloudwatch::Client, resource_groups_tagging::Client as TagsClient},
     config::Config,
-    constants::{
-        EXPRESSION_PARAM_NAME, LABEL_PARAM_NAME, PERIOD_PARAM_NAME, TIMESERIES_MIME_TYPE,
-        TIMESERIES_MSGPACK_MIME_TYPE, TIME_RANGE_PARAM_NAME,
-    },
+    constants::TIMESERIES_MIME_TYPE,
 };
+use fiberplane_pdk::cache::Cache;
+use fiberplane_pdk::concurrency::join;
 use fiberplane_pdk::prelude::{
-    now, Blob, Cell, Error, GraphCell, GraphType, ProviderRequest, StackingType, Timestamp,
+    Blob, Cell, DateTimeRange, Duration, Encoding, Error, GraphCell, GraphType, ProviderRequest,
+    QuerySchema, StackingType, Timestamp,
 };
+use fiberplane_pdk::provider_data::to_blob_with_encoding;
 use fiberplane_pdk::providers::{Timeseries, FORM_ENCODED_MIME_TYPE};
+use serde::Deserialize;
 use std::collections::BTreeMap;
 
+/// Resources are listed to attach their tags to the series of every chart, so
+/// we cache them for a while.
+static RESOURCES: Cache = Cache::new("cloudwatch_resources", Duration::from_secs(5 * 60));
+
 pub async fn invoke2_handler(config: Config, request: ProviderRequest) -> Result<Blob, Error> {
     let request: GraphMetricRequest = request.query_data.try_into()?;
-    let all_resources = TagsClient::from(&config)
-        .list_all_resources(None)
-        .await
-        .unwrap_or_default();
+    let tags_client = TagsClient::from(&config);
     let client = Client::from(&config);
-    client
-        .get_metric_data(
+
+    // The resources are only used to label the series, so they can be listed
+    // while the metric data is being fetched.
+    let (all_resources, data) = join(
+        RESOURCES.get_or_fetch(&config, &"all", || tags_client.list_all_resources(None)),
+        client.get_metric_data(
             request.expressions,
             request.start_time,
             request.end_time,
             request.max_datapoints,
             request.order_points_by,
-        )
-        .await
-        .and_then(|data| {
-            let series: Vec<Timeseries> = data
-                .into_iter()
-                .map(|mdr| mdr_to_ts(mdr, &all_resources))
-                .collect();
-            Ok(Blob::builder()
-                .mime_type(TIMESERIES_MSGPACK_MIME_TYPE.to_string())
-                .data(rmp_serde::to_vec_named(&series)?)
-                .build())
-        })
+        ),
+    )
+    .await;
+    let all_resources: Vec<ResourceTagMapping> = all_resources.unwrap_or_default();
+
+    data.and_then(|data| {
+        let series: Vec<Timeseries> = data
+            .into_iter()
+            .map(|mdr| mdr_to_ts(mdr, &all_resources))
+            .collect();
+        to_blob_with_encoding(TIMESERIES_MIME_TYPE, &series, Encoding::MessagePack)
+    })
 }
 
 pub fn create_cells_handler(_response: Blob) -> Result<Vec<Cell>, Error> {
@@ -77,6 +83,32 @@ pub fn create_cells_handler(_response: Blob) -> Result<Vec<Cell>, Error> {
     Ok(vec![graph_cell])
 }
 
+/// Query data for the graph metric query type.
+#[derive(Deserialize, QuerySchema)]
+pub(crate) struct GraphMetricQuery {
+    #[pdk(
+        label = "Query of the metric to graph",
+        placeholder = "select avg(CPUUtilization) from schema(\"AWS/EC2\", InstanceId) group by InstanceId",
+        multiline,
+        non_empty
+    )]
+    expression: String,
+
+    #[pdk(
+        label = "Title of the timeseries",
+        placeholder = "CPU usage",
+        multiline
+    )]
+    label: String,
+
+    #[pdk(label = "Period of time between points", placeholder = "5m", min = 1)]
+    #[serde(rename = "timeperiod")]
+    period: Duration,
+
+    #[pdk(label = "Specify a time range")]
+    time_range: DateTimeRange,
+}
+
 struct GraphMetricRequest {
     expressions: Vec<(String, Option<String>, usize)>,
     start_time: Timestamp,
@@ -93,36 +125,17 @@ impl TryFrom<Blob> for GraphMetricRequest {
             return Err(Error::UnsupportedRequest);
         }
 
-        let mut expression = String::new();
-        let mut label = None;
-        let mut from = now();
-        let mut to = now();
-        let mut period = 0;
-        for (key, value) in form_urlencoded::parse(&blob.data) {
-            match key.as_ref() {
-                EXPRESSION_PARAM_NAME => expression = value.to_string(),
-                LABEL_PARAM_NAME => label = Some(value.to_string()),
-                TIME_RANGE_PARAM_NAME => {
-                    // TODO: Add validation error for non-compliant value
-                    if let Some((ts_from, ts_to)) = value.split_once(' ') {
-                        from = try_from_iso_date(ts_from)?;
-                        to = try_from_iso_date(ts_to)?;
-                    }
-                }
-                PERIOD_PARAM_NAME => {
-                    // TODO: Add validation error for non-compliant value
-                    period = value.parse().map_err(|err| Error::Deserialization {
-                        message: format!("Invalid period: {err}"),
-                    })?;
-                }
-                _ => {}
-            }
-        }
+        let GraphMetricQuery {
+            expression,
+            label,
+            period,
+            time_range,
+        } = GraphMetricQuery::parse(blob)?;
 
         Ok(Self {
-            expressions: vec![(expression, label, period)],
-            start_time: from,
-            end_time: to,
+            expressions: vec![(expression, Some(label), period.as_secs() as usize)],
+            start_time: time_range.from,
+            end_time: time_range.to,
             max_datapoints: None,
             order_points_by: None,
         })
@@ -1,5 +1,5 @@
 //! "Start Log query" query handling
-use super::{serialize_cells, try_from_iso_date};
+use super::serialize_cells;
 use crate::{
     client::cloudwatch_logs::Client,
     config::Config,
@@ -9,9 +9,11 @@ use crate::{
     },
 };
 use fiberplane_pdk::prelude::{
-    now, Annotation, AnnotationWithOffset, Blob, Cell, Error, ProviderRequest, TextCell, Timestamp,
+    now, Annotation, AnnotationWithOffset, Blob, Cell, DateTimeRange, Error, ProviderRequest,
+    TextCell, Timestamp, ValidationError,
 };
 use fiberplane_pdk::providers::FORM_ENCODED_MIME_TYPE;
+use fiberplane_pdk::templating::TemplateVariables;
 
 pub async fn invoke2_handler(config: Config, request: ProviderRequest) -> Result<Blob, Error> {
     let request: StartQueryInput = request.query_data.try_into()?;
@@ -78,8 +80,7 @@ impl TryFrom<Blob> for StartQueryInput {
 
         let mut query = String::new();
         let mut log_group_names = Vec::new();
-        let mut from = now();
-        let mut to = now();
+        let mut time_range = DateTimeRange::new(now(), now());
         for (key, value) in form_urlencoded::parse(&blob.data) {
             match key.as_ref() {
                 QUERY_PARAM_NAME => query = value.to_string(),
@@ -87,21 +88,27 @@ impl TryFrom<Blob> for StartQueryInput {
                     log_group_names.extend(value.lines().map(ToString::to_string))
                 }
                 TIME_RANGE_PARAM_NAME => {
-                    // TODO: Add validation error for non-compliant value
-                    if let Some((ts_from, ts_to)) = value.split_once(' ') {
-                        from = try_from_iso_date(ts_from)?;
-                        to = try_from_iso_date(ts_to)?;
-                    }
+                    time_range = value.parse().map_err(|message| Error::ValidationError {
+                        errors: vec![ValidationError::builder()
+                            .field_name(TIME_RANGE_PARAM_NAME.to_owned())
+                            .message(message)
+                            .build()],
+                    })?;
                 }
                 _ => {}
             }
         }
 
+        // Variables are substituted once all parameters are known, since the
+        // query may precede the time range.
+        let query = TemplateVariables::new(&time_range, time_range.step_for(120))
+            .interpolate(QUERY_PARAM_NAME, &query)?;
+
         Ok(Self {
             query,
             log_group_names,
-            start_time: from,
-            end_time: to,
+            start_time: time_range.from,
+            end_time: time_range.to,
             limit: None,
         })
     }
@@ -5,13 +5,14 @@ use crate::{
     TIME_RANGE_PARAM_NAME,
 };
 use api::cloudwatch::{ListMetricsResponse, ListMetricsResult, Metric as SdkMetric};
-use fiberplane_pdk::prelude::{log, Blob, Error, ProviderRequest, ValidationError};
+use fiberplane_pdk::prelude::{
+    Blob, DateTimeRange, Error, ProviderRequest, Timestamp, TimestampFormat, ValidationError,
+};
+use fiberplane_pdk::tracing::warn;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, time::SystemTime};
-use time::{
-    ext::NumericalDuration, format_description::well_known::Rfc3339, Duration, OffsetDateTime,
-};
+use std::collections::HashMap;
+use time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MetricList {
@@ -117,19 +118,8 @@ pub struct GraphMetricQuery {
 }
 
 impl GraphMetricQuery {
-    fn parse_iso_date(timestamp: &str) -> Result<f64, Error> {
-        OffsetDateTime::parse(timestamp, &Rfc3339)
-            .map(|timestamp| timestamp.unix_timestamp_nanos() as f64 / 1_000_000_000.0)
-            .map_err(|e| Error::Deserialization {
-                message: format!("error reading timestamp from RFC3339 format: {e}"),
-            })
-    }
-
-    fn to_iso_date(timestamp: f64) -> String {
-        let time = SystemTime::UNIX_EPOCH + timestamp.seconds();
-        OffsetDateTime::from(time)
-            .format(&Rfc3339)
-            .expect("Error formatting timestamp as RFC3339 timestamp")
+    fn to_float(timestamp: &Timestamp) -> f64 {
+        timestamp.unix_timestamp_nanos() as f64 / 1_000_000_000.0
     }
 
     // This is what could be automatically generated from SupportedQueryType
@@ -158,44 +148,31 @@ impl GraphMetricQuery {
                                     .build(),
                             ),
                         },
-                        TIME_RANGE_PARAM_NAME => {
-                            if let Some(split) = value.split_once(' ') {
-                                match Self::parse_iso_date(split.0) {
-                                    Ok(ts) => acc.from = ts,
-                                    Err(e) => errors.push(
-                                        ValidationError::builder()
-                                            .field_name(TIME_RANGE_PARAM_NAME.to_string())
-                                            .message(format!("Invalid start of time range: {e}"))
-                                            .build(),
-                                    ),
-                                };
-                                match Self::parse_iso_date(split.1) {
-                                    Ok(ts) => acc.to = ts,
-                                    Err(e) => errors.push(
-                                        ValidationError::builder()
-                                            .field_name(TIME_RANGE_PARAM_NAME.to_string())
-                                            .message(format!("Invalid end of time range: {e}"))
-                                            .build(),
-                                    ),
-                                };
-                            }
-                            if acc.from >= acc.to {
+                        TIME_RANGE_PARAM_NAME => match value.parse::<DateTimeRange>() {
+                            Ok(range) if range.from >= range.to => {
+                                let (from, to) = range.format(TimestampFormat::Rfc3339);
                                 errors.push(
                                     ValidationError::builder()
                                         .field_name(TIME_RANGE_PARAM_NAME.to_string())
                                         .message(format!(
-                                            "Time range is invalid: {} is not strictly before {}",
-                                            Self::to_iso_date(acc.from),
-                                            Self::to_iso_date(acc.to)
+                                            "Time range is invalid: {from} is not strictly before {to}"
                                         ))
                                         .build(),
                                 )
                             }
-                        }
+                            Ok(range) => {
+                                acc.from = Self::to_float(&range.from);
+                                acc.to = Self::to_float(&range.to);
+                            }
+                            Err(e) => errors.push(
+                                ValidationError::builder()
+                                    .field_name(TIME_RANGE_PARAM_NAME.to_string())
+                                    .message(format!("Invalid time range: {e}"))
+                                    .build(),
+                            ),
+                        },
                         unknown => {
-                            log(format!(
-                                "https provider received an unknown query parameter: {unknown}",
-                            ));
+                            warn!(parameter = unknown, "Received an unknown query parameter");
                         }
                     }
                     (acc, errors)
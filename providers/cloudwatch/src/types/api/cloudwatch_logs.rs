@@ -13,6 +13,7 @@ use super::IntTimestamp as Timestamp;
 pub use describe_log_groups::*;
 pub use describe_log_streams::*;
 pub use describe_queries::*;
+use fiberplane_pdk::prelude::SelectOptions;
 pub use get_log_record::*;
 pub use get_query_results::*;
 use serde::{Deserialize, Serialize};
@@ -114,7 +115,7 @@ pub struct LogStream {
     pub upload_sequence_token: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Copy, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize, Copy, Default, SelectOptions)]
 pub enum QueryStatus {
     Scheduled,
     Running,
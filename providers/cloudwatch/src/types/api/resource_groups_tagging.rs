@@ -201,7 +201,7 @@ pub struct GetResourcesResponse {
 }
 
 /// https://docs.aws.amazon.com/resourcegroupstagging/latest/APIReference/API_ResourceTagMapping.html
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct ResourceTagMapping {
     /// Information that shows whether a resource is compliant with the
@@ -213,7 +213,7 @@ pub struct ResourceTagMapping {
 }
 
 /// https://docs.aws.amazon.com/resourcegroupstagging/latest/APIReference/API_ComplianceDetails.html
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct ComplianceDetails {
     #[serde(rename = "ComplianceStatus")]
@@ -223,7 +223,7 @@ pub struct ComplianceDetails {
 }
 
 /// https://docs.aws.amazon.com/resourcegroupstagging/latest/APIReference/API_Tag.html
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct Tag {
     pub key: String,
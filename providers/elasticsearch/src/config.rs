@@ -1,3 +1,4 @@
+use fiberplane_pdk::http::HttpClient;
 use fiberplane_pdk::prelude::*;
 use serde::Deserialize;
 use std::str::FromStr;
@@ -20,15 +21,32 @@ pub(crate) struct ElasticConfig {
     #[pdk(label = "Optional field names to treat as the message body")]
     pub body_field_names: Vec<String>,
 
-    #[serde(default, skip_serializing_if = "Option::is_none")]
-    #[pdk(label = "Your API key")]
-    pub api_key: Option<String>,
+    /// Elasticsearch API keys are sent as `Authorization: ApiKey <key>`, which
+    /// is the default for `Auth::ApiKey`.
+    #[serde(flatten)]
+    pub auth: Option<Auth>,
+
+    #[pdk(label = "Log level", select)]
+    #[serde(default)]
+    pub log_level: LogLevel,
 }
 
 impl ElasticConfig {
-    pub fn parse_url(&self) -> Result<Url> {
-        Url::from_str(&self.url).map_err(|err| Error::Config {
+    pub fn client(&self) -> Result<HttpClient> {
+        let mut url = Url::from_str(&self.url).map_err(|err| Error::Config {
             message: format!("Invalid Elasticsearch URL: {err}"),
-        })
+        })?;
+
+        // Requests are sent below the path of the configured URL, if any.
+        if !url.path().ends_with('/') {
+            let path = format!("{}/", url.path());
+            url.set_path(&path);
+        }
+
+        let mut builder = HttpClient::builder(url);
+        if let Some(auth) = &self.auth {
+            builder = builder.auth(auth);
+        }
+        Ok(builder.build())
     }
 }
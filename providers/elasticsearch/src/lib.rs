@@ -4,14 +4,21 @@ mod tests;
 
 use config::ElasticConfig;
 use elasticsearch_dsl::{Hit, SearchResponse};
+use fiberplane_pdk::http::HttpClient;
+use fiberplane_pdk::otel::{Flattener, ResourceClassifier};
+use fiberplane_pdk::pagination::{Page, Paginator};
 use fiberplane_pdk::prelude::*;
-use fiberplane_pdk::serde_json::{self, Map, Value};
+use fiberplane_pdk::serde_json::{Map, Value};
+use fiberplane_pdk::tracing::{debug, warn};
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
-use std::collections::BTreeMap;
 use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 
-const PAGE_SIZE: u32 = 30;
+/// Number of log lines to fetch if the query doesn't specify a limit.
+const DEFAULT_LIMIT: usize = 30;
+
+/// Maximum number of hits to request at once.
+const MAX_PAGE_SIZE: usize = 1000;
 
 pub(crate) static TIMESTAMP_FIELDS: &[&str] = &["@timestamp", "timestamp", "fields.timestamp"];
 pub(crate) static BODY_FIELDS: &[&str] =
@@ -21,11 +28,10 @@ pub(crate) static BODY_FIELDS: &[&str] =
 // Schema to the OpenTelemetry Log specification.
 //
 // See: https://github.com/open-telemetry/opentelemetry-specification/blob/main/specification/logs/data-model.md#elastic-common-schema
-static RESOURCE_FIELD_PREFIXES: &[&str] = &["agent.", "cloud.", "container.", "host.", "service."];
-static RESOURCE_FIELD_EXCEPTIONS: &[&str] = &["container.labels", "host.uptime", "service.state"];
+static ECS_RESOURCE_NAMESPACES: &[&str] = &["agent", "cloud", "container", "host", "service"];
+static ECS_RESOURCE_EXCEPTIONS: &[&str] = &["container.labels", "host.uptime", "service.state"];
 
-static COMMIT_HASH: &str = env!("VERGEN_GIT_SHA");
-static BUILD_TIMESTAMP: &str = env!("VERGEN_BUILD_TIMESTAMP");
+const BUILD_INFO: BuildInfo = pdk_build_info!();
 
 #[derive(Deserialize, QuerySchema)]
 pub struct ElasticQuery {
@@ -34,38 +40,39 @@ pub struct ElasticQuery {
 
     #[pdk(label = "Specify a time range")]
     pub time_range: DateTimeRange,
+
+    /// Elasticsearch doesn't return hits beyond its result window, which is
+    /// 10,000 hits by default.
+    #[pdk(
+        label = "Maximum number of log lines (defaults to 30)",
+        min = 1,
+        max = 10000
+    )]
+    pub limit: Option<u32>,
 }
 
 #[derive(Serialize)]
 struct SearchRequestBody {
+    #[serde(default, skip_serializing_if = "is_zero")]
+    from: usize,
+
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    size: Option<u32>,
+    size: Option<usize>,
 }
 
 pdk_query_types! {
     EVENTS_QUERY_TYPE => {
         label: "Elasticsearch query",
-        handler: fetch_logs(ElasticQuery, ElasticConfig).await,
-        supported_mime_types: [EVENTS_MIME_TYPE]
+        handler: fetch_logs(ElasticQuery, ElasticConfig).await -> Events,
+        cells: create_log_cell()
     },
     STATUS_QUERY_TYPE => {
-        supported_mime_types: [STATUS_MIME_TYPE],
-        handler: check_status(ProviderRequest).await
-    }
-}
-
-#[pdk_export]
-fn create_cells(query_type: String, _response: Blob) -> Result<Vec<Cell>> {
-    log(format!("Creating cells for query type: {query_type}"));
-
-    match query_type.as_str() {
-        EVENTS_QUERY_TYPE => create_log_cell(),
-        _ => Err(Error::UnsupportedRequest),
+        handler: check_status(ElasticConfig).await -> StatusReport
     }
 }
 
-async fn fetch_logs(query: ElasticQuery, config: ElasticConfig) -> Result<Blob> {
-    let mut url = config.parse_url()?;
+async fn fetch_logs(query: ElasticQuery, config: ElasticConfig) -> Result<Events> {
+    let client = config.client()?;
 
     // Look for the timestamp and body first in the configured fields and then
     // in the default fields:
@@ -82,19 +89,6 @@ async fn fetch_logs(query: ElasticQuery, config: ElasticConfig) -> Result<Blob>
         .chain(BODY_FIELDS.iter().copied())
         .collect();
 
-    // Add "_search" to the path
-    {
-        let mut path_segments = url.path_segments_mut().map_err(|_| Error::Config {
-            message: "Invalid Elasticsearch URL: Not a base URL".to_owned(),
-        })?;
-        path_segments.push("_search");
-    }
-
-    let mut headers = BTreeMap::from([("Content-Type".to_owned(), "application/json".to_owned())]);
-    if let Some(api_key) = config.api_key {
-        headers.insert("Authorization".to_owned(), format!("ApiKey {api_key}"));
-    }
-
     // Lucene Query Syntax: https://www.elastic.co/guide/en/kibana/current/lucene-query.html
     // TODO should we determine timestamp field name from API?
     let query_string = format!(
@@ -102,22 +96,45 @@ async fn fetch_logs(query: ElasticQuery, config: ElasticConfig) -> Result<Blob>
         query.query, timestamp_field_names[0], query.time_range.from, query.time_range.to
     );
 
+    let limit = query.limit.map_or(DEFAULT_LIMIT, |limit| limit as usize);
+    let mut log_lines = Paginator::new(MAX_PAGE_SIZE)
+        .with_limit(limit)
+        .fetch(0, |offset, page_size| {
+            fetch_page(
+                &client,
+                &query_string,
+                offset,
+                page_size,
+                &timestamp_field_names,
+                &body_field_names,
+            )
+        })
+        .await?
+        .items;
+
+    sort_newest_first(&mut log_lines);
+    Ok(Events(log_lines))
+}
+
+async fn fetch_page(
+    client: &HttpClient,
+    query_string: &str,
+    offset: usize,
+    page_size: usize,
+    timestamp_field_names: &[&str],
+    body_field_names: &[&str],
+) -> Result<Page<ProviderEvent, usize>> {
     let body = SearchRequestBody {
-        size: Some(PAGE_SIZE),
+        from: offset,
+        size: Some(page_size),
     };
-    url.set_query(Some(&format!("q={}", &query_string)));
-
-    let body = serde_json::to_vec(&body).map_err(|err| Error::Data {
-        message: format!("Error serializing query: {err:?}"),
-    })?;
-    let request = HttpRequest::post(url, body).with_headers(headers);
 
-    // Parse response
-    let response = make_http_request(request).await?;
-    let response: SearchResponse =
-        serde_json::from_slice(&response.body).map_err(|err| Error::Data {
-            message: format!("Error parsing Elasticsearch response: {err:?}"),
-        })?;
+    let response: SearchResponse = client
+        .post("_search")
+        .query("q", query_string)
+        .json(&body)?
+        .send_json()
+        .await?;
 
     if response.timed_out {
         return Err(Error::Other {
@@ -126,14 +143,15 @@ async fn fetch_logs(query: ElasticQuery, config: ElasticConfig) -> Result<Blob>
     }
 
     let num_hits = response.hits.hits.len();
-    log(format!("Got {num_hits} query results from Elasticsearch"));
-
-    Events(parse_response(
-        response,
-        &timestamp_field_names,
-        &body_field_names,
+    debug!(num_hits, offset, "Got query results from Elasticsearch");
+
+    // Hits that cannot be parsed are skipped, so the offset of the next page
+    // is based on the number of hits rather than the number of log lines.
+    let next = (num_hits >= page_size).then_some(offset + num_hits);
+    Ok(Page::new(
+        parse_response(response, timestamp_field_names, body_field_names),
+        next,
     ))
-    .to_blob()
 }
 
 fn parse_response(
@@ -145,11 +163,26 @@ fn parse_response(
     let mut log_lines: Vec<ProviderEvent> = hits
         .filter_map(|hit| parse_hit(hit, timestamp_field_names, body_field_names))
         .collect();
-    // Sort logs so the newest ones are first
-    log_lines.sort_by(|a, b| b.time.partial_cmp(&a.time).unwrap_or(Ordering::Equal));
+    sort_newest_first(&mut log_lines);
     log_lines
 }
 
+fn sort_newest_first(log_lines: &mut [ProviderEvent]) {
+    log_lines.sort_by(|a, b| b.time.partial_cmp(&a.time).unwrap_or(Ordering::Equal));
+}
+
+/// Returns the hex digits of a trace or span ID, which is usually a string.
+fn id_to_hex(id: &Value) -> String {
+    match id {
+        Value::String(id) => id.replace('-', ""),
+        other => other.to_string(),
+    }
+}
+
+fn is_zero(value: &usize) -> bool {
+    *value == 0
+}
+
 fn parse_hit(
     hit: Hit,
     timestamp_field_names: &[&str],
@@ -157,33 +190,26 @@ fn parse_hit(
 ) -> Option<ProviderEvent> {
     let source: Map<String, Value> = hit
         .source()
-        .map_err(|err| {
-            log(format!(
-                "Error parsing ElasticSearch hit as JSON object: {err:?}"
-            ));
-        })
+        .map_err(|err| warn!(error = ?err, "Error parsing Elasticsearch hit as JSON object"))
         .ok()?;
-    let mut flattened_fields = BTreeMap::new();
-    for (key, val) in source.into_iter() {
-        flatten_nested_value(&mut flattened_fields, key, val);
-    }
+    let mut flattened_fields = Flattener::new().flatten(Value::Object(source));
 
     // Parse the trace ID and span ID from hex if they exist
     let trace_id = flattened_fields.remove("trace.id").and_then(|trace_id| {
-        if let Ok(bytes) = hex::decode(trace_id.to_string().replace('-', "")) {
+        if let Ok(bytes) = hex::decode(id_to_hex(&trace_id)) {
             bytes.try_into().ok().map(OtelTraceId::new)
         } else {
-            log(format!("unable to decode ID as hex in log: {trace_id}"));
+            warn!(%trace_id, "Unable to decode trace ID as hex");
             // Put the value back if we were unable to parse it
             flattened_fields.insert("trace.id".to_owned(), trace_id);
             None
         }
     });
     let span_id = flattened_fields.remove("span.id").and_then(|span_id| {
-        if let Ok(bytes) = hex::decode(span_id.to_string().replace('-', "")) {
+        if let Ok(bytes) = hex::decode(id_to_hex(&span_id)) {
             bytes.try_into().ok().map(OtelSpanId::new)
         } else {
-            log(format!("unable to decode ID as hex in log: {span_id}"));
+            warn!(%span_id, "Unable to decode span ID as hex");
             // Put the value back if we were unable to parse it
             flattened_fields.insert("span.id".to_owned(), span_id);
             None
@@ -225,19 +251,10 @@ fn parse_hit(
 
     // All fields that are not mapped to the resource field
     // become part of the attributes field
-    // TODO refactor this so we only make one pass over the fields
-    let (resource, attributes): (BTreeMap<String, Value>, BTreeMap<String, Value>) =
-        flattened_fields.into_iter().partition(|(key, _)| {
-            RESOURCE_FIELD_PREFIXES
-                .iter()
-                .any(|prefix| key.starts_with(prefix))
-                && !RESOURCE_FIELD_EXCEPTIONS.contains(&key.as_str())
-        });
-
-    let mut otel = OtelMetadata::builder()
-        .attributes(attributes)
-        .resource(resource)
-        .build();
+    let mut otel = ResourceClassifier::new()
+        .with_namespaces(ECS_RESOURCE_NAMESPACES.iter().copied())
+        .with_exceptions(ECS_RESOURCE_EXCEPTIONS.iter().copied())
+        .to_metadata(flattened_fields);
     otel.trace_id = trace_id;
     otel.span_id = span_id;
 
@@ -250,50 +267,15 @@ fn parse_hit(
     Some(event)
 }
 
-fn flatten_nested_value(output: &mut BTreeMap<String, Value>, key: String, value: Value) {
-    match value {
-        Value::Object(v) => {
-            for (sub_key, val) in v.into_iter() {
-                flatten_nested_value(output, format!("{key}.{sub_key}"), val);
-            }
-        }
-        Value::Array(v) => {
-            for (index, val) in v.into_iter().enumerate() {
-                // TODO should the separator be dots instead?
-                flatten_nested_value(output, format!("{key}[{index}]"), val);
-            }
-        }
-        primitive => {
-            output.insert(key, primitive);
-        }
-    };
-}
-
-async fn check_status(request: ProviderRequest) -> Result<Blob> {
-    let config = ElasticConfig::parse(request.config)?;
-    let mut url = config.parse_url()?;
-
-    // Add "_xpack" to the path
-    {
-        let mut path_segments = url.path_segments_mut().map_err(|_| Error::Config {
-            message: "Invalid ElasticSearch URL: cannot-be-a-base".to_string(),
-        })?;
-        path_segments.push("_xpack");
-    }
-
-    let request = HttpRequest::get(url)
-        .with_headers([("Content-Type".to_owned(), "application/json".to_owned())]);
-
-    // At this point we don't care to validate the info Loki sends back.
-    // We just care it responded with 200 OK.
-    let _ = make_http_request(request).await?;
-
-    ProviderStatus::builder()
-        .status(Ok(()))
-        .version(COMMIT_HASH.to_owned())
-        .built_at(BUILD_TIMESTAMP.to_owned())
-        .build()
-        .to_blob()
+async fn check_status(config: ElasticConfig) -> Result<StatusReport> {
+    BUILD_INFO
+        .check(async {
+            // The root endpoint responds with information about the cluster,
+            // including its version.
+            let info: Value = config.client()?.get_json("").await?;
+            Ok(info["version"]["number"].as_str().map(str::to_owned))
+        })
+        .await
 }
 
 pub fn create_log_cell() -> Result<Vec<Cell>> {
@@ -1,37 +1,9 @@
 use super::*;
 use elasticsearch_dsl::{Hit, HitsMetadata, SearchResponse, TotalHits, TotalHitsRelation};
-use fiberplane_pdk::bindings::common::mem::FatPtr;
 use fiberplane_pdk::serde_json::{self, json};
+use fiberplane_pdk::testing::{block_on, Cassette, MockHost};
 use time::OffsetDateTime;
 
-#[no_mangle]
-unsafe fn __fp_gen_log(_: FatPtr) {}
-
-#[no_mangle]
-unsafe fn __fp_host_resolve_async_value(_: FatPtr, _: FatPtr) {}
-
-#[no_mangle]
-unsafe fn __fp_gen_make_http_request(_: FatPtr) -> FatPtr {
-    todo!()
-}
-
-#[test]
-fn flatten_nested_values() {
-    let mut fields = BTreeMap::new();
-    flatten_nested_value(&mut fields, "a".to_owned(), json!(1));
-    assert_eq!(fields.get("a").unwrap(), &json!(1));
-
-    flatten_nested_value(&mut fields, "b".to_owned(), json!({ "c": true }));
-    assert_eq!(fields.get("b.c").unwrap(), &json!(true));
-
-    flatten_nested_value(&mut fields, "e.f".to_owned(), json!({ "g": { "h": null } }));
-    assert_eq!(fields.get("e.f.g.h").unwrap(), &json!(null));
-
-    flatten_nested_value(&mut fields, "j.arr".to_owned(), json!(["apple", "banana"]));
-    assert_eq!(fields.get("j.arr[0]").unwrap(), "apple");
-    assert_eq!(fields.get("j.arr[1]").unwrap(), "banana");
-}
-
 #[test]
 fn extracts_timestamp_and_body_from_fields() {
     let hit = serde_json::from_value(json!({
@@ -91,6 +63,46 @@ fn uses_default_values_if_timestamp_or_body_extraction_fails() {
     assert_eq!(record.title, "");
 }
 
+#[test]
+fn maps_ecs_resource_fields() {
+    let hit = serde_json::from_value(json!({
+        "_index": "index",
+        "_type": "type",
+        "_id": "id",
+        "_score": 1.0,
+        "_source": {
+            "agent": { "name": "filebeat" },
+            "cloud": { "region": "eu-west-1" },
+            "container": { "labels": { "team": "payments" } },
+            "host": { "name": "web-1", "uptime": 86400 },
+            "k8s": { "pod": { "name": "api-7d9f" } },
+            "process": { "pid": 42 },
+            "service": { "name": "api", "state": "running" },
+        }
+    }))
+    .unwrap();
+    let otel = parse_hit(hit, TIMESTAMP_FIELDS, BODY_FIELDS).unwrap().otel;
+    assert_eq!(
+        otel.resource.keys().collect::<Vec<_>>(),
+        [
+            "agent.name",
+            "cloud.region",
+            "container.labels.team",
+            "host.name",
+            "service.name"
+        ]
+    );
+    assert_eq!(
+        otel.attributes.keys().collect::<Vec<_>>(),
+        [
+            "host.uptime",
+            "k8s.pod.name",
+            "process.pid",
+            "service.state"
+        ]
+    );
+}
+
 #[test]
 fn timestamp_deserializes_from_unix_epoch() {
     let js = json!({
@@ -162,3 +174,73 @@ fn sorts_logs_by_timestamp_newest_first() {
     assert_eq!(logs[1].title, "2");
     assert_eq!(logs[2].title, "3");
 }
+
+#[test]
+fn rejects_limits_beyond_the_result_window() {
+    let parse = |limit: &str| {
+        let query_data = url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("query", "service.name:checkout")
+            .append_pair("time_range", "2021-12-20T15:00:00Z 2021-12-20T16:00:00Z")
+            .append_pair("limit", limit)
+            .finish();
+        ElasticQuery::parse(
+            Blob::builder()
+                .data(query_data.into_bytes())
+                .mime_type(FORM_ENCODED_MIME_TYPE.to_owned())
+                .build(),
+        )
+    };
+
+    assert!(matches!(parse("10000"), Ok(query) if query.limit == Some(10_000)));
+    assert!(matches!(
+        parse("10001"),
+        Err(Error::ValidationError { errors }) if errors[0].field_name == "limit"
+    ));
+}
+
+#[test]
+fn fetches_logs() {
+    let host = MockHost::new();
+    host.replay(Cassette::load(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/cassettes/fetch_logs.json"
+    )));
+    let _guard = host.install();
+
+    let query_data = url::form_urlencoded::Serializer::new(String::new())
+        .append_pair("query", "service.name:checkout")
+        .append_pair("time_range", "2021-12-20T15:00:00Z 2021-12-20T16:00:00Z")
+        .finish();
+    let request = ProviderRequest::builder()
+        .query_type(EVENTS_QUERY_TYPE.to_owned())
+        .query_data(
+            Blob::builder()
+                .data(query_data.into_bytes())
+                .mime_type(FORM_ENCODED_MIME_TYPE.to_owned())
+                .build(),
+        )
+        .config(json!({ "url": "http://localhost:9200", "apiKey": "secret" }))
+        .build();
+
+    let blob = block_on(invoke2(request)).unwrap();
+    let Events(events) = Events::parse_blob(blob).unwrap();
+    host.assert_all_mocks_called();
+
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].title, "Payment processed");
+    assert_eq!(events[1].title, "Payment declined");
+
+    let otel = &events[1].otel;
+    assert_eq!(
+        otel.resource.keys().collect::<Vec<_>>(),
+        ["host.name", "service.name", "service.version"]
+    );
+    assert_eq!(
+        otel.attributes.get("http.response.status_code"),
+        Some(&json!(402))
+    );
+    assert_eq!(otel.attributes.get("host.uptime"), Some(&json!(86400)));
+    assert_eq!(otel.attributes.get("tags[1]"), Some(&json!("declined")));
+    assert!(otel.trace_id.is_some());
+    assert!(otel.span_id.is_some());
+}
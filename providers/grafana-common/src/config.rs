@@ -1,9 +1,11 @@
-use fiberplane_provider_bindings::Error;
+use fiberplane_pdk::prelude::{
+    Auth, Error, FromConfig, FromRequest, ProviderConfig, ProviderRequest,
+};
 use serde::Deserialize;
 use std::collections::BTreeMap;
 use url::Url;
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Hash)]
 #[serde(rename_all = "camelCase")]
 pub struct Config {
     pub url: Url,
@@ -18,26 +20,21 @@ impl Config {
         })
     }
 
-    pub fn to_headers(&self) -> Option<BTreeMap<String, String>> {
-        match &self.auth {
-            Some(Auth::Basic { username, password }) => Some(BTreeMap::from([(
-                "Authorization".to_string(),
-                format!("Basic {}", base64::encode(format!("{username}:{password}"))),
-            )])),
-            Some(Auth::Bearer { token }) => Some(BTreeMap::from([(
-                "Authorization".to_string(),
-                format!("Bearer {token}"),
-            )])),
-            None => None,
-        }
+    pub fn to_headers(&self) -> BTreeMap<String, String> {
+        self.auth.as_ref().map(Auth::to_headers).unwrap_or_default()
     }
 }
 
-#[derive(Deserialize)]
-#[serde(untagged, rename_all = "camelCase")]
-pub enum Auth {
-    Basic { username: String, password: String },
-    Bearer { token: String },
+impl FromConfig for Config {
+    fn from_config(config: &ProviderConfig) -> Result<Self, Error> {
+        Self::parse(config.clone())
+    }
+}
+
+impl FromRequest for Config {
+    fn from_request(request: &ProviderRequest) -> Result<Self, Error> {
+        Self::parse(request.config.clone())
+    }
 }
 
 #[cfg(test)]
@@ -51,7 +48,9 @@ mod tests {
         "token": "mytoken"
       }"#;
         let config: Config = serde_json::from_str(with_token).unwrap();
-        assert!(matches!(config.auth, Some(Auth::Bearer { token }) if token == "mytoken"));
+        assert!(
+            matches!(config.auth, Some(Auth::Bearer { token }) if token.expose_secret() == "mytoken")
+        );
 
         let with_username_password = r#"{
         "url": "http://localhost:3100",
@@ -60,7 +59,7 @@ mod tests {
       }"#;
         let config: Config = serde_json::from_str(with_username_password).unwrap();
         assert!(
-            matches!(config.auth, Some(Auth::Basic { username, password }) if username == "myusername" && password == "mypassword")
+            matches!(config.auth, Some(Auth::Basic { username, password }) if username == "myusername" && password.expose_secret() == "mypassword")
         );
 
         let without_auth = r#"{
@@ -1,13 +1,17 @@
 mod config;
 
 pub use config::Config;
-use fiberplane_provider_bindings::{
-    log, make_http_request, Blob, Error, HttpRequest, HttpRequestError,
-};
+use fiberplane_pdk::cache::Cache;
+use fiberplane_pdk::http::HttpClient;
+use fiberplane_pdk::prelude::{Blob, Duration, Error, HttpRequestError, RetryPolicy};
+use fiberplane_pdk::tracing::info;
 use serde::{de::DeserializeOwned, Deserialize};
-use std::collections::BTreeMap;
 use url::Url;
 
+/// Data sources rarely change, so we remember their IDs instead of listing
+/// them every time a direct query fails.
+static DATA_SOURCE_IDS: Cache = Cache::new("grafana_data_source_ids", Duration::from_secs(5 * 60));
+
 /// Response to the /api/datasources endpoint
 #[derive(Deserialize, Debug)]
 struct Datasource {
@@ -28,22 +32,16 @@ pub async fn query_direct_and_proxied<T>(
 where
     T: DeserializeOwned,
 {
-    // Send it first as a direct query
-    match send_query(
-        &config.url,
-        path_and_query,
-        config.to_headers(),
-        body.clone(),
-    )
-    .await
-    {
+    // Send it first as a direct query. The proxy is only tried if that fails,
+    // rather than concurrently, because looking up the data source would cost
+    // an extra request to every backend that isn't Grafana. Once the data
+    // source ID is cached, falling back costs a single request.
+    match send_query(&config.url, path_and_query, config, body.clone()).await {
         Ok(response) => Ok(response),
         Err(direct_err) => {
             // If the direct query fails, see if the URL is a Grafana URL and try to get the proxy URL from it
             if let Ok(url) = get_grafana_datasource_proxy_url(config, data_source_type).await {
-                if let Ok(response) =
-                    send_query(&url, path_and_query, config.to_headers(), body).await
-                {
+                if let Ok(response) = send_query(&url, path_and_query, config, body).await {
                     Ok(response)
                 } else {
                     Err(direct_err)
@@ -59,47 +57,29 @@ where
 async fn send_query<T>(
     url: &Url,
     path_and_query: &str,
-    headers: Option<BTreeMap<String, String>>,
+    config: &Config,
     body: Option<Blob>,
 ) -> Result<T, Error>
 where
     T: DeserializeOwned,
 {
-    let url = url
-        .join(path_and_query)
-        .map_err(|e| Error::Config {
-            message: format!("Invalid URL: {e:?}"),
-        })?
-        .to_string();
-
-    let request = if let Some(blob) = body {
-        HttpRequest::post(url, blob.data)
-            .with_headers([("Content-Type".to_owned(), blob.mime_type)])
-    } else if let Some(headers) = headers {
-        HttpRequest::get(url).with_headers(headers)
-    } else {
-        HttpRequest::get(url)
+    let client = client(url, config);
+    let request = match body {
+        Some(blob) => client.post(path_and_query).blob(blob),
+        None => client.get(path_and_query),
     };
 
-    log(format!(
-        "Sending {:?} request to {}",
-        request.method, request.url
-    ));
-
-    let response = make_http_request(request)
-        .await
-        .map_err(|error| match &error {
-            HttpRequestError::ServerError {
-                status_code,
-                response,
-            } if *status_code == 400 => Error::Other {
-                message: String::from_utf8_lossy(response).to_string(),
-            },
-            _ => Error::Http { error },
-        })?;
-
-    serde_json::from_slice(&response.body).map_err(|e| Error::Data {
-        message: format!("Error parsing response: {e:?}"),
+    request.send_json().await.map_err(|error| match error {
+        Error::Http {
+            error:
+                HttpRequestError::ServerError {
+                    status_code: 400,
+                    response,
+                },
+        } => Error::Other {
+            message: String::from_utf8_lossy(&response).to_string(),
+        },
+        error => error,
     })
 }
 
@@ -110,24 +90,25 @@ async fn get_grafana_datasource_proxy_url(
     config: &Config,
     data_source_type: &'static str,
 ) -> Result<Url, Error> {
+    let client = client(&config.url, config);
+    let data_source_id = DATA_SOURCE_IDS
+        .get_or_fetch(config, &data_source_type, || {
+            find_data_source_id(&client, data_source_type)
+        })
+        .await?;
+
+    // Construct the proxy URL
+    client.url(&format!("api/datasources/proxy/{data_source_id}/"))
+}
+
+/// Load the Grafana datasources and return the ID of the first one of the given
+/// type.
+async fn find_data_source_id(
+    client: &HttpClient,
+    data_source_type: &'static str,
+) -> Result<u32, Error> {
     // Query for the available datasources
-    let url = config
-        .url
-        .join("api/datasources")
-        .map_err(|e| Error::Config {
-            message: format!("Invalid URL: {e:?}"),
-        })?;
-    let request = HttpRequest::get(url);
-    let response = make_http_request(if let Some(headers) = config.to_headers() {
-        request.with_headers(headers)
-    } else {
-        request
-    })
-    .await?;
-    let data_sources: Vec<Datasource> =
-        serde_json::from_slice(&response.body).map_err(|e| Error::Deserialization {
-            message: format!("Could not parse Grafana datasources response: {e:?}"),
-        })?;
+    let data_sources: Vec<Datasource> = client.get_json("api/datasources").await?;
 
     // Find one of type "loki"
     let loki_data_source = data_sources
@@ -137,13 +118,16 @@ async fn get_grafana_datasource_proxy_url(
             message: format!("No {data_source_type} data source found in grafana"),
         })?;
 
-    log(format!("Found loki data source: {loki_data_source:?}"));
+    info!(data_source = ?loki_data_source, "Found loki data source");
 
-    // Construct the proxy URL
-    config
-        .url
-        .join(&format!("api/datasources/proxy/{}/", loki_data_source.id))
-        .map_err(|e| Error::Config {
-            message: format!("Invalid URL: {e:?}"),
-        })
+    Ok(loki_data_source.id)
+}
+
+fn client(url: &Url, config: &Config) -> HttpClient {
+    // Queries with a body are sent as `POST` requests, but they only read
+    // data, so they can be retried safely.
+    HttpClient::builder(url.clone())
+        .default_headers(config.to_headers())
+        .retry_policy(RetryPolicy::default().with_non_idempotent_requests(true))
+        .build()
 }
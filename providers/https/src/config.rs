@@ -1,8 +1,9 @@
+use fiberplane_pdk::prelude::{pdk_export, Auth, ConfigSchema, LogLevel};
 use serde::Deserialize;
 use std::collections::BTreeMap;
 use url::Url;
 
-#[derive(Deserialize)]
+#[derive(ConfigSchema, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Config {
     /// If it is absent, allow the provider to hit any address
@@ -12,16 +13,27 @@ pub struct Config {
     /// Show response headers in the query results
     /// Defaults to false
     #[serde(default)]
+    #[pdk(label = "Show response headers in the query results")]
     pub show_headers: bool,
+
+    #[pdk(label = "Log level", select)]
+    #[serde(default)]
+    pub log_level: LogLevel,
 }
 
-#[derive(Deserialize)]
+#[derive(ConfigSchema, Deserialize)]
+#[pdk(nested)]
 #[serde(rename_all = "camelCase")]
 /// The description of an API to hit.
 pub struct Api {
     /// The base url of the API we are interested in
+    #[pdk(
+        label = "Base URL of the API we are interested in",
+        placeholder = "Leave empty to allow querying any URL"
+    )]
     pub base_url: Url,
     /// The path to the healthcheck/status endpoint
+    #[pdk(label = "Path to the healthcheck or status endpoint, relative to the base URL")]
     pub health_check_path: Option<String>,
     /// Authentication credentials
     #[serde(flatten)]
@@ -31,30 +43,14 @@ pub struct Api {
 impl Api {
     /// Convert the configuration to associated headers
     pub fn to_headers(&self) -> Option<BTreeMap<String, String>> {
-        match &self.auth {
-            Some(Auth::Basic { username, password }) => Some(BTreeMap::from([(
-                "Authorization".to_string(),
-                format!("Basic {}", base64::encode(format!("{username}:{password}"))),
-            )])),
-            Some(Auth::Bearer { token }) => Some(BTreeMap::from([(
-                "Authorization".to_string(),
-                format!("Bearer {token}"),
-            )])),
-            None => None,
-        }
+        self.auth.as_ref().map(Auth::to_headers)
     }
 }
 
-#[derive(Deserialize)]
-#[serde(untagged, rename_all = "camelCase")]
-pub enum Auth {
-    Basic { username: String, password: String },
-    Bearer { token: String },
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use fiberplane_pdk::providers::ConfigField;
 
     #[test]
     fn empty_config_is_allowed() {
@@ -81,7 +77,7 @@ mod tests {
       }"#;
         let config: Config = serde_json::from_str(with_token).unwrap();
         assert!(
-            matches!(config.api.and_then(|api| api.auth), Some(Auth::Bearer { token }) if token == "mytoken")
+            matches!(config.api.and_then(|api| api.auth), Some(Auth::Bearer { token }) if token.expose_secret() == "mytoken")
         );
     }
 
@@ -95,7 +91,7 @@ mod tests {
       }"#;
         let config: Config = serde_json::from_str(with_username_password).unwrap();
         assert!(
-            matches!(config.api.and_then(|api| api.auth), Some(Auth::Basic { username, password }) if username == "myusername" && password == "mypassword")
+            matches!(config.api.and_then(|api| api.auth), Some(Auth::Basic { username, password }) if username == "myusername" && password.expose_secret() == "mypassword")
         );
     }
 
@@ -108,4 +104,35 @@ mod tests {
         let config: Config = serde_json::from_str(without_auth).unwrap();
         assert!(config.api.unwrap().auth.is_none());
     }
+
+    #[test]
+    fn schema_matches_deserialization() {
+        let names: Vec<_> = Config::schema()
+            .into_iter()
+            .map(|field| match field {
+                ConfigField::Checkbox(field) => (field.name, field.required),
+                ConfigField::Select(field) => (field.name, field.required),
+                ConfigField::Text(field) => (field.name, field.required),
+                other => panic!("unexpected field: {other:?}"),
+            })
+            .collect();
+
+        assert_eq!(
+            names,
+            [
+                "baseUrl",
+                "healthCheckPath",
+                "username",
+                "password",
+                "token",
+                "apiKey",
+                "apiKeyHeader",
+                "headerName",
+                "headerValue",
+                "showHeaders",
+                "logLevel"
+            ]
+            .map(|name| (name.to_owned(), false))
+        );
+    }
 }
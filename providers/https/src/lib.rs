@@ -1,4 +1,6 @@
+use fiberplane_pdk::http::HttpClient;
 use fiberplane_pdk::prelude::*;
+use fiberplane_pdk::tracing::{debug, warn};
 use std::collections::BTreeMap;
 use std::convert::TryInto;
 use std::env;
@@ -6,46 +8,17 @@ use url::Url;
 
 mod config;
 mod constants;
+mod method;
 mod provider_response;
 
 use config::*;
 use constants::*;
+use method::Method;
 use provider_response::HttpsProviderResponse;
 
 static COMMIT_HASH: &str = env!("VERGEN_GIT_SHA");
 static BUILD_TIMESTAMP: &str = env!("VERGEN_BUILD_TIMESTAMP");
 
-#[pdk_export]
-fn get_config_schema() -> ConfigSchema {
-    vec![
-        TextField::new()
-            .with_name("baseUrl")
-            .with_label("Base URL of the API we are interested in")
-            .with_placeholder("Leave empty to allow querying any URL")
-            .into(),
-        TextField::new()
-            .with_name("healthCheckPath")
-            .with_label("Path to the healthcheck or status endpoint, relative to the base URL")
-            .into(),
-        TextField::new()
-            .with_name("username")
-            .with_label("Username for authentication (if the API uses Basic auth)")
-            .into(),
-        TextField::new()
-            .with_name("password")
-            .with_label("Password for authentication (if the API uses Basic auth)")
-            .into(),
-        TextField::new()
-            .with_name("token")
-            .with_label("Token for authentication (if the API uses Bearer auth)")
-            .into(),
-        CheckboxField::new()
-            .with_name("showHeaders")
-            .with_label("Show response headers in the query results")
-            .into(),
-    ]
-}
-
 #[pdk_export]
 async fn get_supported_query_types(config: ProviderConfig) -> Vec<SupportedQueryType> {
     let config = serde_json::from_value::<Config>(config);
@@ -57,11 +30,10 @@ async fn get_supported_query_types(config: ProviderConfig) -> Vec<SupportedQuery
     vec![
         SupportedQueryType::new(PERFORM_QUERY_TYPE)
             .with_schema(vec![
-                // TODO: Wait for Studio to implement the select field (FP-2590),
-                // then use a QueryField::Select to implement the type of query
-                TextField::new()
+                SelectField::new()
                     .with_name(HTTP_METHOD_PARAM_NAME)
                     .with_label("Type of query")
+                    .with_options(Method::options())
                     .required()
                     .into(),
                 TextField::new()
@@ -91,15 +63,16 @@ async fn get_supported_query_types(config: ProviderConfig) -> Vec<SupportedQuery
 #[pdk_export]
 async fn invoke2(request: ProviderRequest) -> Result<Blob> {
     init_panic_hook();
-    log(format!(
-        "https provider (commit: {}, built at: {}) invoked for query type \"{}\" and query data \"{:?}\" with config \"{:?}\"",
-        COMMIT_HASH, BUILD_TIMESTAMP, request.query_type, request.query_data, request.config
-    ));
+    init_logging();
 
-    let config: Config =
-        serde_json::from_value(request.config.clone()).map_err(|err| Error::Config {
-            message: format!("Error parsing config: {err:?}"),
-        })?;
+    let config = Config::parse(request.config.clone())?;
+    debug!(
+        version = COMMIT_HASH,
+        built_at = BUILD_TIMESTAMP,
+        query_type = %request.query_type,
+        query_data = ?request.query_data,
+        "https provider invoked"
+    );
 
     match request.query_type.as_str() {
         PERFORM_QUERY_TYPE => handle_query(config, request).await,
@@ -111,39 +84,37 @@ async fn invoke2(request: ProviderRequest) -> Result<Blob> {
 #[pdk_export]
 fn create_cells(query_type: String, response: Blob) -> Result<Vec<Cell>> {
     Err(Error::Invocation {
-        message: format!("create_cells is not implemented for this provider, it only returns {} blobs that must be handled by the runtime natively (received a {} blob for {}).", CELLS_MSGPACK_MIME_TYPE, response.mime_type, query_type)
+        message: format!("create_cells is not implemented for this provider, it only returns {} blobs that must be handled by the runtime natively (received a {} blob for {}).", Encoding::MessagePack.mime_type(CELLS_MIME_TYPE), response.mime_type, query_type)
     })
 }
 
 /// Send a query to the given URL
+///
+/// An empty path means the URL itself is queried.
 async fn send_query(
     url: &Url,
-    path_and_query: &str,
+    path: &str,
+    query: Vec<(String, String)>,
     method: HttpRequestMethod,
     headers: Option<BTreeMap<String, String>>,
     body: Option<Blob>,
 ) -> Result<HttpsProviderResponse> {
-    let url = url
-        .join(path_and_query)
-        .map_err(|e| Error::Config {
-            message: format!("Invalid URL: {e:?}"),
-        })?
-        .to_string();
-
-    let mut headers = headers.unwrap_or_default();
-    if let Some(ref blob) = body {
-        headers.insert("Content-Type".to_owned(), blob.mime_type.clone());
-    };
-
-    log(format!("Sending {method:?} request to {url}"));
-
-    let mut request = HttpRequest::default();
-    request.url.clone_from(&url);
-    request.method = method;
-    request.headers = Some(headers);
-    request.body = body.map(|blob| blob.data);
+    let client = HttpClient::builder(url.clone())
+        .default_headers(headers.unwrap_or_default())
+        .build();
+
+    let target = if path.is_empty() { url.as_str() } else { path };
+    let mut request = client.request(method, target).query_pairs(query);
+    if let Some(blob) = body {
+        request = request.blob(blob);
+    }
 
-    make_http_request(request).await.try_into()
+    // Failed requests are part of the query results, rather than errors.
+    match request.send().await {
+        Ok(response) => response.try_into(),
+        Err(Error::Http { error }) => error.try_into(),
+        Err(error) => Err(error),
+    }
 }
 
 async fn check_status(config: Config) -> Result<Blob> {
@@ -152,6 +123,7 @@ async fn check_status(config: Config) -> Result<Blob> {
             let info = send_query(
                 &api.base_url,
                 api.health_check_path.as_deref().unwrap_or_default(),
+                Vec::new(),
                 HttpRequestMethod::Get,
                 api.to_headers(),
                 None,
@@ -174,22 +146,22 @@ async fn handle_query(config: Config, request: ProviderRequest) -> Result<Blob>
         return Err(Error::UnsupportedRequest);
     }
     let mut path = String::new();
-    let mut query = String::new();
+    let mut query = Vec::new();
     let mut url = Err(Error::Invocation {
         message: "no URL given".to_string(),
     });
     let mut headers: Option<BTreeMap<String, String>> = None;
-    let mut method = HttpRequestMethod::Get;
+    let mut method = Method::default();
     for (key, value) in form_urlencoded::parse(&request.query_data.data) {
         match key.as_ref() {
-            HTTP_METHOD_PARAM_NAME => match value.as_ref().to_uppercase().as_str() {
-                "GET" => method = HttpRequestMethod::Get,
-                unsupported => {
+            HTTP_METHOD_PARAM_NAME => match Method::from_value(&value.to_uppercase()) {
+                Some(selected_method) => method = *selected_method,
+                None => {
                     return Err(Error::ValidationError {
                         errors: vec![ValidationError::builder()
                             .field_name(HTTP_METHOD_PARAM_NAME.to_string())
                             .message(format!(
-                                "{unsupported} is not a supported HTTPS method with this provider."
+                                "{value} is not a supported HTTPS method with this provider."
                             ))
                             .build()],
                     })
@@ -243,31 +215,22 @@ async fn handle_query(config: Config, request: ProviderRequest) -> Result<Blob>
                 }
             }
             QUERY_PARAM_NAME => {
-                let mut serializer = form_urlencoded::Serializer::new(String::new());
-                serializer.extend_pairs(
-                    value
-                        .as_ref()
-                        .lines()
-                        .filter_map(|line| line.split_once('=')),
-                );
-                query = serializer.finish()
+                query = value
+                    .as_ref()
+                    .lines()
+                    .filter_map(|line| line.split_once('='))
+                    .map(|(key, value)| (key.to_owned(), value.to_owned()))
+                    .collect();
             }
             _ => {
-                log(format!(
-                    "https provider received an unknown query parameter: {}",
-                    key.as_ref()
-                ));
+                warn!(parameter = %key, "Received an unknown query parameter");
             }
         }
     }
 
     let url = url?;
 
-    if !query.is_empty() {
-        path = format!("{path}?{query}");
-    }
-
-    send_query(&url, &path, method, headers, None)
+    send_query(&url, &path, query, method.into(), headers, None)
         .await
         .and_then(|resp| resp.try_into_blob(config.show_headers))
 }
@@ -0,0 +1,18 @@
+use fiberplane_pdk::prelude::{HttpRequestMethod, SelectOptions};
+use serde::Deserialize;
+
+/// HTTP methods that may be used for queries.
+#[derive(Clone, Copy, Debug, Default, Deserialize, SelectOptions)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Method {
+    #[default]
+    Get,
+}
+
+impl From<Method> for HttpRequestMethod {
+    fn from(method: Method) -> Self {
+        match method {
+            Method::Get => Self::Get,
+        }
+    }
+}
@@ -1,9 +1,10 @@
 use fiberplane_pdk::bindings::*;
+use fiberplane_pdk::provider_data::{to_blob_with_encoding, Encoding};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::BTreeMap;
 
-use crate::constants::CELLS_MSGPACK_MIME_TYPE;
+use crate::constants::CELLS_MIME_TYPE;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub(crate) struct HttpsProviderResponse {
@@ -138,8 +139,5 @@ impl HttpsProviderResponse {
 }
 
 fn serialize_cells(cells: Vec<Cell>) -> Result<Blob, Error> {
-    Ok(Blob::builder()
-        .data(rmp_serde::to_vec_named(&cells)?)
-        .mime_type(CELLS_MSGPACK_MIME_TYPE.to_owned())
-        .build())
+    to_blob_with_encoding(CELLS_MIME_TYPE, &cells, Encoding::MessagePack)
 }
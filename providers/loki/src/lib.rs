@@ -1,18 +1,25 @@
 #[cfg(test)]
 mod tests;
 
+use fiberplane_pdk::otel::ResourceClassifier;
+use fiberplane_pdk::pagination::{Page, Paginator};
 use fiberplane_pdk::prelude::*;
-use fiberplane_pdk::serde_json::{self, Value};
+use fiberplane_pdk::serde_json::Value;
+use fiberplane_pdk::templating::TemplateVariables;
 use grafana_common::{query_direct_and_proxied, Config};
 use serde::Deserialize;
 use std::collections::BTreeMap;
 use std::str::FromStr;
 use time::OffsetDateTime;
 
-const PAGE_SIZE: &str = "30";
+/// Number of log lines to fetch if the query doesn't specify a limit.
+const DEFAULT_LIMIT: usize = 30;
 
-static COMMIT_HASH: &str = env!("VERGEN_GIT_SHA");
-static BUILD_TIMESTAMP: &str = env!("VERGEN_BUILD_TIMESTAMP");
+/// Maximum number of log lines Loki returns for a single request, unless
+/// configured otherwise.
+const MAX_PAGE_SIZE: usize = 1000;
+
+const BUILD_INFO: BuildInfo = pdk_build_info!();
 
 #[derive(Deserialize, QuerySchema)]
 pub struct LokiQuery {
@@ -21,17 +28,19 @@ pub struct LokiQuery {
 
     #[pdk(label = "Specify a time range")]
     pub time_range: DateTimeRange,
+
+    #[pdk(label = "Maximum number of log lines (defaults to 30)", min = 1)]
+    pub limit: Option<u32>,
 }
 
 pdk_query_types! {
     EVENTS_QUERY_TYPE => {
         label: "Loki query",
-        handler: fetch_logs(LokiQuery, Config).await,
-        supported_mime_types: [EVENTS_MIME_TYPE]
+        handler: fetch_logs(LokiQuery, Config).await -> Events,
+        cells: create_log_cell()
     },
     STATUS_QUERY_TYPE => {
-        supported_mime_types: [STATUS_MIME_TYPE],
-        handler: check_status(ProviderRequest).await
+        handler: check_status(Config).await -> StatusReport
     }
 }
 
@@ -59,31 +68,70 @@ struct Data {
     values: Vec<(String, String)>,
 }
 
-#[pdk_export]
-fn create_cells(query_type: String, _response: Blob) -> Result<Vec<Cell>> {
-    log(format!("Creating cells for query type: {query_type}"));
-
-    match query_type.as_str() {
-        EVENTS_QUERY_TYPE => create_log_cell(),
-        _ => Err(Error::UnsupportedRequest),
-    }
+async fn fetch_logs(query: LokiQuery, config: Config) -> Result<Events> {
+    let interval = query.time_range.step_for(120);
+    let logql = TemplateVariables::new(&query.time_range, interval)
+        .with_timestamp_format(TimestampFormat::UnixSeconds)
+        .interpolate("query", &query.query)?;
+
+    // Loki expects the start and end as Unix epochs in nanoseconds
+    let (from, _) = query.time_range.format(TimestampFormat::UnixNanos);
+
+    // Loki returns the most recent lines first, so every next page ends right
+    // after the oldest line of the previous one.
+    let limit = query.limit.map_or(DEFAULT_LIMIT, |limit| limit as usize);
+    let cursor = Cursor {
+        end: query.time_range.to.unix_timestamp_nanos(),
+        seen: Vec::new(),
+    };
+    let log_lines = Paginator::new(MAX_PAGE_SIZE)
+        .with_limit(limit)
+        .fetch(cursor, |cursor, page_size| {
+            fetch_page(&config, &logql, &from, cursor, page_size)
+        })
+        .await?
+        .items;
+
+    Ok(Events(log_lines))
 }
 
-async fn fetch_logs(query: LokiQuery, config: Config) -> Result<Blob> {
-    // Convert unix epoch in seconds to epoch in nanoseconds
-    let from = (query.time_range.from.unix_timestamp_nanos()).to_string();
-    let to = (query.time_range.to.unix_timestamp_nanos()).to_string();
+/// Position of the next page of log lines.
+#[derive(Debug, PartialEq)]
+struct Cursor {
+    /// End of the page, exclusive, as a Unix epoch in nanoseconds.
+    end: i128,
+
+    /// Lines that were already fetched at the last nanosecond before the end.
+    ///
+    /// Other lines may share the timestamp of the oldest line of a page, so
+    /// the next page still includes that timestamp, and these lines are
+    /// skipped instead.
+    seen: Vec<ProviderEvent>,
+}
 
+/// Fetches the log lines before the end of the given cursor, returning a
+/// cursor that ends right after the oldest line for the next page.
+///
+/// There is no next page if the page isn't full, or if all of its lines share
+/// the timestamp the cursor was already at, since the next page would then
+/// return the same lines.
+async fn fetch_page(
+    config: &Config,
+    logql: &str,
+    start: &str,
+    cursor: Cursor,
+    limit: usize,
+) -> Result<Page<ProviderEvent, Cursor>> {
     let query_string: String =
-        url::form_urlencoded::Serializer::new(String::with_capacity(query.query.capacity()))
-            .append_pair("query", &query.query)
-            .append_pair("limit", PAGE_SIZE)
-            .append_pair("start", &from)
-            .append_pair("end", &to)
+        url::form_urlencoded::Serializer::new(String::with_capacity(logql.len()))
+            .append_pair("query", logql)
+            .append_pair("limit", &limit.to_string())
+            .append_pair("start", start)
+            .append_pair("end", &cursor.end.to_string())
             .finish();
 
     let response: QueryResponse = query_direct_and_proxied(
-        &config,
+        config,
         "loki",
         &format!("loki/api/v1/query_range?{query_string}"),
         None,
@@ -112,11 +160,40 @@ async fn fetch_logs(query: LokiQuery, config: Config) -> Result<Blob> {
             message: format!("Failed to parse data, got error: {e:?}"),
         })?;
 
-    Events(log_lines).to_blob()
+    // Lines from different streams are not ordered, so we look for the oldest
+    // one across all of them.
+    let oldest = log_lines
+        .iter()
+        .map(|line| line.time.unix_timestamp_nanos())
+        .min();
+    let next = match oldest {
+        Some(oldest) if log_lines.len() >= limit && oldest + 1 < cursor.end => Some(Cursor {
+            end: oldest + 1,
+            seen: log_lines
+                .iter()
+                .filter(|line| line.time.unix_timestamp_nanos() == oldest)
+                .cloned()
+                .collect(),
+        }),
+        _ => None,
+    };
+
+    // The end is exclusive, but lines at the end are skipped as well in case
+    // Loki includes them.
+    let log_lines = log_lines
+        .into_iter()
+        .filter(|line| line.time.unix_timestamp_nanos() < cursor.end)
+        .filter(|line| !cursor.seen.contains(line))
+        .collect();
+    Ok(Page::new(log_lines, next))
 }
 
 fn data_mapper(data: &Data) -> impl Iterator<Item = Result<ProviderEvent>> + '_ {
-    let attributes = &data.labels;
+    // Loki doesn't allow dots in label names, so they are usually replaced
+    // with underscores, as in `service_name`.
+    let (resource, attributes) = ResourceClassifier::new()
+        .with_separators([".", "_"])
+        .partition(data.labels.clone());
     data.values.iter().map(move |(timestamp, value)| {
         let timestamp = i128::from_str(timestamp)
             .ok()
@@ -125,7 +202,7 @@ fn data_mapper(data: &Data) -> impl Iterator<Item = Result<ProviderEvent>> + '_
 
         let metadata = OtelMetadata::builder()
             .attributes(attributes.clone())
-            .resource(BTreeMap::new())
+            .resource(resource.clone())
             .build();
         let event = ProviderEvent::builder()
             .title(value)
@@ -137,28 +214,29 @@ fn data_mapper(data: &Data) -> impl Iterator<Item = Result<ProviderEvent>> + '_
     })
 }
 
-async fn check_status(request: ProviderRequest) -> Result<Blob> {
-    let config = serde_json::from_value(request.config)?;
-
+async fn check_status(config: Config) -> Result<StatusReport> {
     // Send a fake query to check the status
     let query_string = url::form_urlencoded::Serializer::new(String::new())
         .append_pair("query", r#"{job="fiberplane_check_status"} != ``"#)
         .finish();
+    let path = format!("loki/api/v1/query_range?{query_string}");
+
+    BUILD_INFO
+        .check_with_version(
+            query_direct_and_proxied::<Value>(&config, "loki", &path, None),
+            query_version(&config),
+        )
+        .await
+}
 
-    query_direct_and_proxied::<Value>(
-        &config,
-        "loki",
-        &format!("loki/api/v1/query_range?{query_string}"),
-        None,
-    )
-    .await?;
-
-    ProviderStatus::builder()
-        .status(Ok(()))
-        .version(COMMIT_HASH.to_owned())
-        .built_at(BUILD_TIMESTAMP.to_owned())
-        .build()
-        .to_blob()
+/// Returns the version of the Loki instance, if it exposes its build
+/// information.
+async fn query_version(config: &Config) -> Option<String> {
+    let build_info: Value =
+        query_direct_and_proxied(config, "loki", "loki/api/v1/status/buildinfo", None)
+            .await
+            .ok()?;
+    build_info["version"].as_str().map(str::to_owned)
 }
 
 pub fn create_log_cell() -> Result<Vec<Cell>> {
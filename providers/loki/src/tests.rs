@@ -1,6 +1,8 @@
-use crate::{data_mapper, Data, QueryData, QueryResponse};
+use crate::{data_mapper, fetch_page, Cursor, Data, QueryData, QueryResponse};
 use fiberplane_pdk::prelude::*;
-use fiberplane_pdk::serde_json::{json, Deserializer};
+use fiberplane_pdk::serde_json::{json, Deserializer, Value};
+use fiberplane_pdk::testing::{block_on, Cassette, HttpMock, MockHost};
+use grafana_common::Config;
 use serde::Deserialize;
 use std::collections::BTreeMap;
 use time::OffsetDateTime;
@@ -33,6 +35,30 @@ const DATA: &str = r#"{
         }
       }"#;
 
+const TIME_RANGE: &str = "2019-09-23T19:20:00Z 2019-09-23T20:20:00Z";
+
+fn load_cassette(name: &str) -> Cassette {
+    Cassette::load(format!("{}/cassettes/{name}", env!("CARGO_MANIFEST_DIR")))
+}
+
+fn events_request(config: Value, query: &str) -> ProviderRequest {
+    let query_data = url::form_urlencoded::Serializer::new(String::new())
+        .append_pair("query", query)
+        .append_pair("time_range", TIME_RANGE)
+        .finish();
+
+    ProviderRequest::builder()
+        .query_type(EVENTS_QUERY_TYPE.to_owned())
+        .query_data(
+            Blob::builder()
+                .data(query_data.into_bytes())
+                .mime_type(FORM_ENCODED_MIME_TYPE.to_owned())
+                .build(),
+        )
+        .config(config)
+        .build()
+}
+
 #[test]
 fn test_deserialization() {
     let value = QueryResponse::deserialize(&mut Deserializer::from_str(DATA)).unwrap();
@@ -86,3 +112,199 @@ fn test_data_mapper() {
             .build()
     );
 }
+
+#[test]
+fn test_data_mapper_resource_labels() {
+    let data = Data {
+        labels: BTreeMap::from([
+            ("job".to_owned(), json!("varlogs")),
+            ("k8s_pod_name".to_owned(), json!("api-7d9f")),
+            ("service_name".to_owned(), json!("api")),
+        ]),
+        values: vec![("1569266497240578000".to_owned(), "foo".to_owned())],
+    };
+
+    let mapped = data_mapper(&data).collect::<Result<Vec<_>>>().unwrap();
+    let otel = &mapped[0].otel;
+    assert_eq!(
+        otel.resource.keys().collect::<Vec<_>>(),
+        ["k8s_pod_name", "service_name"]
+    );
+    assert_eq!(otel.attributes.keys().collect::<Vec<_>>(), ["job"]);
+}
+
+#[test]
+fn test_supported_query_types() {
+    let query_types = block_on(crate::get_supported_query_types(json!({})));
+
+    assert_eq!(query_types.len(), 2);
+    assert_eq!(query_types[0].query_type, EVENTS_QUERY_TYPE);
+    assert_eq!(query_types[0].mime_types, vec![EVENTS_MIME_TYPE]);
+    assert_eq!(query_types[1].query_type, STATUS_QUERY_TYPE);
+    assert_eq!(query_types[1].mime_types, vec![STATUS_MIME_TYPE]);
+}
+
+#[test]
+fn test_fetch_logs() {
+    let host = MockHost::new();
+    host.replay(load_cassette("fetch_logs.json"));
+    let _guard = host.install();
+
+    let request = events_request(
+        json!({ "url": "http://localhost:3100" }),
+        r#"{job="varlogs"}"#,
+    );
+    let blob = block_on(crate::invoke2(request)).unwrap();
+    let Events(events) = Events::parse_blob(blob).unwrap();
+
+    host.assert_all_mocks_called();
+
+    let titles: Vec<_> = events.iter().map(|event| event.title.as_str()).collect();
+    assert_eq!(titles, ["foo", "bar"]);
+    assert_eq!(
+        events[0].time,
+        Timestamp::from(
+            OffsetDateTime::from_unix_timestamp_nanos(1_569_266_497_240_578_000).unwrap()
+        )
+    );
+    assert_eq!(
+        events[0].otel.attributes,
+        BTreeMap::from([
+            ("filename".to_owned(), json!("/var/log/myproject.log")),
+            ("job".to_owned(), json!("varlogs")),
+            ("level".to_owned(), json!("info")),
+        ])
+    );
+    assert!(events[0].otel.resource.is_empty());
+}
+
+#[test]
+fn test_fetch_logs_through_grafana_proxy() {
+    let host = MockHost::new();
+    host.replay(load_cassette("fetch_logs_grafana_proxy.json"));
+    let _guard = host.install();
+
+    let config = json!({ "url": "http://localhost:3000", "token": "secret" });
+    let request = events_request(config, r#"{job="varlogs"}"#);
+    let blob = block_on(crate::invoke2(request)).unwrap();
+    let Events(events) = Events::parse_blob(blob).unwrap();
+
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].title, "foo");
+    assert_eq!(events[1].title, "bar");
+    assert!(host
+        .logs()
+        .iter()
+        .any(|line| line.starts_with("INFO grafana_common: Found loki data source")));
+    host.assert_all_mocks_called();
+}
+
+#[test]
+fn test_fetch_logs_server_error() {
+    let host = MockHost::new();
+    host.mock(
+        HttpMock::get("http://localhost:3100/loki/api/v1/query_range")
+            .respond_with_body(400, "parse error at line 1, col 1"),
+    )
+    .mock(HttpMock::get("http://localhost:3100/api/datasources").respond_with_body(401, ""));
+    let _guard = host.install();
+
+    let request = events_request(json!({ "url": "http://localhost:3100" }), "{");
+    let result = block_on(crate::invoke2(request));
+
+    assert!(matches!(
+        result,
+        Err(Error::Other { message }) if message == "parse error at line 1, col 1"
+    ));
+}
+
+/// Returns a mock for a page of log lines ending at the given timestamp, with
+/// a stream for every list of `(timestamp, line)` pairs.
+fn page_mock(end: &str, streams: &[&[(&str, &str)]]) -> HttpMock {
+    let result: Vec<_> = streams
+        .iter()
+        .enumerate()
+        .map(|(i, values)| json!({ "stream": { "job": format!("job{i}") }, "values": values }))
+        .collect();
+    HttpMock::get(&format!(
+        "http://localhost:3100/loki/api/v1/query_range?limit=3&end={end}"
+    ))
+    .respond_with_json(&json!({
+        "status": "success",
+        "data": { "resultType": "streams", "result": result }
+    }))
+}
+
+#[test]
+fn test_fetch_page_skips_lines_on_the_boundary() {
+    let host = MockHost::new();
+    host.mock(page_mock(
+        "100",
+        &[&[("90", "c"), ("80", "b")], &[("80", "a")]],
+    ))
+    .mock(page_mock(
+        "81",
+        &[&[("80", "b"), ("70", "z")], &[("80", "a")]],
+    ));
+    let _guard = host.install();
+
+    let config = Config::parse(json!({ "url": "http://localhost:3100" })).unwrap();
+    let fetch = |cursor| block_on(fetch_page(&config, "{job=~\".+\"}", "0", cursor, 3)).unwrap();
+    let titles = |events: &[ProviderEvent]| {
+        events
+            .iter()
+            .map(|event| event.title.clone())
+            .collect::<Vec<_>>()
+    };
+
+    let first = fetch(Cursor {
+        end: 100,
+        seen: Vec::new(),
+    });
+    assert_eq!(titles(&first.items), ["c", "b", "a"]);
+    let next = first.next.unwrap();
+    assert_eq!(next.end, 81);
+    assert_eq!(titles(&next.seen), ["b", "a"]);
+
+    let second = fetch(next);
+    assert_eq!(titles(&second.items), ["z"]);
+    assert_eq!(second.next.unwrap().end, 71);
+}
+
+#[test]
+fn test_fetch_page_stops_when_the_cursor_does_not_move() {
+    let host = MockHost::new();
+    host.mock(page_mock(
+        "81",
+        &[&[("80", "b"), ("80", "c")], &[("80", "a")]],
+    ));
+    let _guard = host.install();
+
+    let config = Config::parse(json!({ "url": "http://localhost:3100" })).unwrap();
+    let page = block_on(fetch_page(
+        &config,
+        "{job=~\".+\"}",
+        "0",
+        Cursor {
+            end: 81,
+            seen: Vec::new(),
+        },
+        3,
+    ))
+    .unwrap();
+
+    assert_eq!(page.items.len(), 3);
+    assert_eq!(page.next, None);
+}
+
+#[test]
+fn test_create_cells() {
+    let cells = crate::create_cells(
+        EVENTS_QUERY_TYPE.to_owned(),
+        Events(Vec::new()).to_blob().unwrap(),
+    )
+    .unwrap();
+
+    assert_eq!(cells.len(), 1);
+    assert!(matches!(&cells[0], Cell::Log(_)));
+}
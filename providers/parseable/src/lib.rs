@@ -1,31 +1,30 @@
-use std::collections::BTreeMap;
-
-use base64::{prelude::BASE64_STANDARD, Engine};
+use fiberplane_pdk::http::{parse_url, HttpClient};
+use fiberplane_pdk::otel::{Flattener, ResourceClassifier};
 use fiberplane_pdk::{prelude::*, provider_data::ProviderData};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use time::{
     format_description::FormatItem, macros::format_description, parsing::Parsed, OffsetDateTime,
 };
-use url::Url;
 
 pub const QUERY_API: &str = "/api/v1/query";
 pub const LIVENESS_API: &str = "/api/v1/liveness";
+pub const ABOUT_API: &str = "/api/v1/about";
 
-static COMMIT_HASH: &str = env!("VERGEN_GIT_SHA");
-static BUILD_TIMESTAMP: &str = env!("VERGEN_BUILD_TIMESTAMP");
+const BUILD_INFO: BuildInfo = pdk_build_info!();
 
-#[derive(ConfigSchema, Deserialize, Serialize)]
+#[derive(ConfigSchema, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct Config {
     #[pdk(label = "Parseable endpoint", placeholder = "Please specify a URL")]
     pub endpoint: String,
 
-    #[pdk(label = "Username", placeholder = "admin")]
-    pub username: String,
+    #[serde(flatten)]
+    pub auth: Auth,
 
-    #[pdk(label = "Password", placeholder = "admin")]
-    pub password: String,
+    #[pdk(label = "Log level", select)]
+    #[serde(default)]
+    pub log_level: LogLevel,
 }
 
 #[derive(QuerySchema, Deserialize, Serialize, Debug, Clone)]
@@ -40,20 +39,11 @@ struct Query {
 pdk_query_types! {
     EVENTS_QUERY_TYPE => {
         label: "Parseable: Run a query",
-        handler: query_handler(Query, Config).await,
-        supported_mime_types: [EVENTS_MIME_TYPE],
+        handler: query_handler(Query, Config).await -> Events,
+        cells: create_log_cell(),
     },
     STATUS_QUERY_TYPE => {
-        handler: check_status(ProviderRequest).await,
-        supported_mime_types: [STATUS_MIME_TYPE]
-    }
-}
-
-#[pdk_export]
-fn create_cells(query_type: String, _response: Blob) -> Result<Vec<Cell>> {
-    match query_type.as_str() {
-        EVENTS_QUERY_TYPE => create_log_cell(),
-        _ => Err(Error::UnsupportedRequest),
+        handler: check_status(Config).await -> StatusReport
     }
 }
 
@@ -68,27 +58,26 @@ pub fn create_log_cell() -> Result<Vec<Cell>> {
     Ok(vec![logs_cell])
 }
 
-async fn query_handler(query: Query, config: Config) -> Result<Blob> {
+async fn query_handler(query: Query, config: Config) -> Result<Events> {
     let events = run_query(&query, &config).await?;
-    Events(events).to_blob()
+    Ok(Events(events))
 }
 
-async fn check_status(request: ProviderRequest) -> Result<Blob> {
-    let config = Config::parse(request.config)?;
-    let url = get_url(LIVENESS_API, &config)?;
-    let response = make_http_request(HttpRequest::get(url)).await?;
-    if response.status_code == 200 {
-        ProviderStatus::builder()
-            .status(Ok(()))
-            .version(COMMIT_HASH.to_owned())
-            .built_at(BUILD_TIMESTAMP.to_owned())
-            .build()
-            .to_blob()
-    } else {
-        Err(Error::Http {
-            error: HttpRequestError::Offline,
+async fn check_status(config: Config) -> Result<StatusReport> {
+    BUILD_INFO
+        .check(async {
+            let client = client(&config)?;
+            let response = client.get(LIVENESS_API).send().await?;
+            if response.status_code != 200 {
+                return Err(Error::Http {
+                    error: HttpRequestError::Offline,
+                });
+            }
+
+            let about: Option<Value> = client.get_json(ABOUT_API).await.ok();
+            Ok(about.and_then(|about| about["version"].as_str().map(str::to_owned)))
         })
-    }
+        .await
 }
 
 async fn run_query(query: &Query, config: &Config) -> Result<Vec<ProviderEvent>> {
@@ -98,35 +87,29 @@ async fn run_query(query: &Query, config: &Config) -> Result<Vec<ProviderEvent>>
         "endTime": query.time_range.to.to_string()
     });
 
-    let url = get_url(QUERY_API, config)?;
-    let mut headers = BTreeMap::new();
-    headers.insert(
-        "Authorization".to_string(),
-        basic_auth_header(&config.username, &config.password),
-    );
-    headers.insert("Content-Type".to_string(), "application/json".to_string());
-
-    let request = HttpRequest::post(url, query.to_string()).with_headers(headers);
-    let response = make_http_request(request).await?;
-    let body: Value = serde_json::from_slice(&response.body)?;
-
-    if response.status_code == 200 {
-        let Value::Array(arr) = body else {
-            return Err(Error::Other {
-                message: format!("Expected an array, received: {}", body),
-            });
-        };
-        let mut rows = Vec::with_capacity(arr.len());
-        for value in arr {
-            rows.push(parse_row(value)?)
-        }
-        Ok(rows)
-    } else {
-        // Return error message returned by Parseable API.
-        Err(Error::Other {
-            message: String::from_utf8_lossy(&response.body).to_string(),
-        })
+    let body: Value = client(config)?
+        .post_json(QUERY_API, &query)
+        .await
+        .map_err(|error| match error {
+            // Return error message returned by Parseable API.
+            Error::Http {
+                error: HttpRequestError::ServerError { response, .. },
+            } => Error::Other {
+                message: String::from_utf8_lossy(&response).to_string(),
+            },
+            error => error,
+        })?;
+
+    let Value::Array(arr) = body else {
+        return Err(Error::Other {
+            message: format!("Expected an array, received: {}", body),
+        });
+    };
+    let mut rows = Vec::with_capacity(arr.len());
+    for value in arr {
+        rows.push(parse_row(value)?)
     }
+    Ok(rows)
 }
 
 fn parse_row(value: Value) -> Result<ProviderEvent> {
@@ -145,10 +128,12 @@ fn parse_row(value: Value) -> Result<ProviderEvent> {
         None => Timestamp::from(OffsetDateTime::UNIX_EPOCH),
     };
 
-    let otel = OtelMetadata::builder()
-        .attributes(BTreeMap::from_iter(object))
-        .resource(BTreeMap::default())
-        .build();
+    // Columns of nested JSON events are flattened with underscores when they
+    // are ingested, as in `service_name`.
+    let fields = Flattener::new().flatten(Value::Object(object));
+    let otel = ResourceClassifier::new()
+        .with_separators([".", "_"])
+        .to_metadata(fields);
 
     let event = ProviderEvent::builder()
         .otel(otel)
@@ -159,23 +144,10 @@ fn parse_row(value: Value) -> Result<ProviderEvent> {
     Ok(event)
 }
 
-fn get_url(api: &str, config: &Config) -> Result<Url> {
-    let base_url: Url = config
-        .endpoint
-        .parse()
-        .map_err(|e: url::ParseError| Error::Config {
-            message: format!("Invalid URL in configuration: {e:?}"),
-        })?;
-    base_url.join(api).map_err(|e| Error::Config {
-        message: format!("Invalid URL in configuration: {e:?}"),
-    })
-}
-
-fn basic_auth_header(username: &str, password: &str) -> String {
-    format!(
-        "Basic {}",
-        BASE64_STANDARD.encode(format!("{}:{}", username, password))
-    )
+fn client(config: &Config) -> Result<HttpClient> {
+    Ok(HttpClient::builder(parse_url(&config.endpoint)?)
+        .auth(&config.auth)
+        .build())
 }
 
 // Parseable's p_timestamp value is not sufficient for time parser.
@@ -1,7 +1,12 @@
 use super::prometheus::*;
+use fiberplane_pdk::cache::Cache;
 use fiberplane_pdk::prelude::*;
 use grafana_common::{query_direct_and_proxied, Config};
 
+/// The metadata of all metrics is fetched for every suggestion, so we cache it
+/// while the user is typing.
+static METADATA: Cache = Cache::new("prometheus_metadata", Duration::from_secs(60));
+
 /// See: https://prometheus.io/docs/prometheus/latest/querying/functions/
 const PROM_QL_FUNCTIONS: &[&str] = &[
     "abs",
@@ -52,11 +57,17 @@ const PROM_QL_FUNCTIONS: &[&str] = &[
     "stdvar_over_time",
 ];
 
-pub async fn query_suggestions(query: AutoSuggestRequest, config: Config) -> Result<Blob> {
+pub async fn query_suggestions(
+    query: AutoSuggestRequest,
+    config: Config,
+) -> Result<Vec<Suggestion>> {
     let (identifier, from) = extract_identifier(&query.query);
 
-    let response: PrometheusMetadataResponse =
-        query_direct_and_proxied(&config, "prometheus", "api/v1/metadata", None).await?;
+    let response: PrometheusMetadataResponse = METADATA
+        .get_or_fetch(&config, &"api/v1/metadata", || {
+            query_direct_and_proxied(&config, "prometheus", "api/v1/metadata", None)
+        })
+        .await?;
 
     let mut suggestions: Vec<Suggestion> = response
         .data
@@ -94,7 +105,7 @@ pub async fn query_suggestions(query: AutoSuggestRequest, config: Config) -> Res
         }
     }
 
-    Suggestions(suggestions).to_blob()
+    Ok(suggestions)
 }
 
 /// Extracts the identifier and starting offset that is currently being typed from the query. This
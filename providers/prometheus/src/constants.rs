@@ -2,6 +2,3 @@ pub const INSTANTS_MIME_TYPE: &str = "application/vnd.fiberplane.instants";
 pub const INSTANTS_QUERY_TYPE: &str = "x-instants";
 
 pub const QUERY_PARAM_NAME: &str = "query";
-
-pub const ONE_MINUTE: u32 = 60; // seconds
-pub const ONE_HOUR: u32 = 60 * ONE_MINUTE; // seconds
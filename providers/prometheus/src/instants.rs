@@ -1,5 +1,4 @@
 use super::{constants::*, prometheus::*};
-use fiberplane_models::blobs::Blob;
 use fiberplane_models::providers::Metric;
 use fiberplane_pdk::prelude::*;
 use grafana_common::{query_direct_and_proxied, Config};
@@ -19,9 +18,9 @@ pub struct Instant {
     pub metric: Metric,
 }
 
-pub async fn query_instants(request: ProviderRequest) -> Result<Blob> {
+pub async fn query_instants(config: Config, request: ProviderRequest) -> Result<Instants> {
     let response: PrometheusResponse = query_direct_and_proxied(
-        &Config::parse(request.config)?,
+        &config,
         "prometheus",
         "api/v1/query",
         Some(request.query_data),
@@ -38,5 +37,5 @@ pub async fn query_instants(request: ProviderRequest) -> Result<Blob> {
         .into_iter()
         .map(InstantVector::into_instant)
         .collect::<Result<Vec<_>>>()
-        .and_then(|instants| Instants(instants).to_blob())
+        .map(Instants)
 }
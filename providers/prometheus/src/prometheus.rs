@@ -1,7 +1,7 @@
 use super::instants::Instant;
 use fiberplane_pdk::prelude::Timestamp;
 use fiberplane_pdk::providers::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::BTreeMap,
     num::ParseFloatError,
@@ -40,12 +40,12 @@ impl InstantVector {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 pub struct Metadata {
     pub help: Option<String>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PrometheusMetadataResponse {
     pub data: BTreeMap<String, Vec<Metadata>>,
@@ -1,55 +1,30 @@
 use super::{constants::*, prometheus::*};
 use fiberplane_pdk::prelude::*;
+use fiberplane_pdk::templating::TemplateVariables;
 use grafana_common::{query_direct_and_proxied, Config};
 use serde::Deserialize;
 use serde_json::Result as SerdeResult;
-use std::fmt::Display;
-use std::time::SystemTime;
-use time::ext::NumericalDuration;
 
 #[derive(Deserialize, QuerySchema)]
 pub(crate) struct TimeseriesQuery {
-    #[pdk(label = "Enter your Prometheus query", supports_suggestions)]
+    #[pdk(label = "Enter your Prometheus query", non_empty)]
     query: String,
 
     #[pdk(label = "Specify a time range")]
     time_range: DateTimeRange,
 
+    #[pdk(
+        label = "Step between data points (derived from the time range if empty)",
+        min = 1
+    )]
+    step: Option<Duration>,
+
     #[allow(dead_code)]
     #[pdk(label = "Enable live mode", value = "true")]
     #[serde(default)]
     live: bool,
 }
 
-#[derive(Clone, Copy)]
-struct StepSize {
-    amount: u32,
-    unit: StepUnit,
-}
-
-impl Display for StepSize {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}{}", self.amount, self.unit.to_str())
-    }
-}
-
-#[derive(Clone, Copy)]
-enum StepUnit {
-    Hours,
-    Minutes,
-    Seconds,
-}
-
-impl StepUnit {
-    fn to_str(self) -> &'static str {
-        match self {
-            Self::Hours => "h",
-            Self::Minutes => "m",
-            Self::Seconds => "s",
-        }
-    }
-}
-
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct ApiResponse {
@@ -96,20 +71,24 @@ fn validate_or_parse_message(query: &str, message: &str) -> Error {
     }
 }
 
-pub(crate) async fn query_series(query: TimeseriesQuery, config: Config) -> Result<Blob> {
-    validate_query(&query)?;
-
-    let from = to_float(query.time_range.from);
-    let to = to_float(query.time_range.to);
-    let step = step_for_range(from, to);
-    let start = to_iso_date(round_to_grid(from, step, RoundToGridEdge::Start));
-    let end = to_iso_date(round_to_grid(to, step, RoundToGridEdge::End));
+pub(crate) async fn query_series(
+    query: TimeseriesQuery,
+    config: Config,
+) -> Result<TimeseriesVector> {
+    // We attempt to maintain roughly 120 steps for whatever the duration is,
+    // so that for a duration of one hour, we fetch per 30 seconds.
+    let step = query.step.unwrap_or_else(|| query.time_range.step_for(120));
+    let time_range = query.time_range.align_to(step);
+    let promql = TemplateVariables::new(&time_range, step)
+        .with_timestamp_format(TimestampFormat::UnixSeconds)
+        .interpolate(QUERY_PARAM_NAME, &query.query)?;
+    let (start, end) = time_range.format(TimestampFormat::Rfc3339);
 
     let mut form_data = form_urlencoded::Serializer::new(String::new());
-    form_data.append_pair("query", &query.query);
+    form_data.append_pair("query", &promql);
     form_data.append_pair("start", &start);
     form_data.append_pair("end", &end);
-    form_data.append_pair("step", &step.to_string());
+    form_data.append_pair("step", &format!("{}s", step.as_secs()));
     let query_string = form_data.finish();
 
     let body = Blob::builder()
@@ -121,7 +100,7 @@ pub(crate) async fn query_series(query: TimeseriesQuery, config: Config) -> Resu
         query_direct_and_proxied(&config, "prometheus", "api/v1/query_range", Some(body))
             .await
             .map_err(|err| match err {
-                Error::Other { message } => validate_or_parse_message(&query.query, &message),
+                Error::Other { message } => validate_or_parse_message(&promql, &message),
                 err => err,
             })?;
 
@@ -135,7 +114,7 @@ pub(crate) async fn query_series(query: TimeseriesQuery, config: Config) -> Resu
         .into_iter()
         .map(RangeVector::into_series)
         .collect::<Result<Vec<_>>>()
-        .and_then(|series_vector| TimeseriesVector(series_vector).to_blob())
+        .map(TimeseriesVector)
 }
 
 pub fn create_graph_cell() -> Result<Vec<Cell>> {
@@ -149,78 +128,3 @@ pub fn create_graph_cell() -> Result<Vec<Cell>> {
     );
     Ok(vec![graph_cell])
 }
-
-enum RoundToGridEdge {
-    Start,
-    End,
-}
-
-/// Rounds the timestamp to a "grid" with intervals defined by the step size.
-/// This assures that when we scroll a chart forward or backward in time, we
-/// "snap" to the same grid, to avoid the issue of bucket realignment, giving
-/// unexpected jumps in the graph.
-fn round_to_grid(timestamp: f64, step: StepSize, edge: RoundToGridEdge) -> f64 {
-    let step_seconds = step_to_seconds(step);
-    let round = match edge {
-        RoundToGridEdge::Start => f64::floor,
-        RoundToGridEdge::End => f64::ceil,
-    };
-    round(timestamp / step_seconds as f64) * step_seconds as f64
-}
-
-fn step_to_seconds(step: StepSize) -> u32 {
-    match step.unit {
-        StepUnit::Hours => ONE_HOUR * step.amount,
-        StepUnit::Minutes => ONE_MINUTE * step.amount,
-        StepUnit::Seconds => step.amount,
-    }
-}
-
-/// Returns the step to fetch from the given duration in seconds. We attempt
-/// to maintain roughly 120 steps for whatever the duration is, so that for a
-/// duration of one hour, we fetch per 30 seconds, however for a duration of one
-/// minute, we fetch per 1 seconds (as the step value is rounded up to a
-/// full unit).
-fn step_for_range(from: f64, to: f64) -> StepSize {
-    let mut step = (to - from) / 120.0;
-    let mut unit = StepUnit::Seconds;
-    if step >= 60.0 {
-        step /= 60.0;
-        unit = StepUnit::Minutes;
-        if step >= 60.0 {
-            step /= 60.0;
-            unit = StepUnit::Hours;
-        }
-    }
-
-    StepSize {
-        amount: f64::ceil(step) as u32,
-        unit,
-    }
-}
-
-fn to_float(timestamp: Timestamp) -> f64 {
-    timestamp.unix_timestamp_nanos() as f64 / 1_000_000_000.0
-}
-
-fn to_iso_date(timestamp: f64) -> String {
-    let time = SystemTime::UNIX_EPOCH + timestamp.seconds();
-    Timestamp::from(time).to_string()
-}
-
-fn validate_query(query: &TimeseriesQuery) -> Result<()> {
-    let mut errors = Vec::new();
-    if query.query.is_empty() {
-        errors.push(
-            ValidationError::builder()
-                .field_name(QUERY_PARAM_NAME.to_owned())
-                .message("Please enter a query".to_owned())
-                .build(),
-        );
-    }
-
-    match errors.is_empty() {
-        true => Ok(()),
-        false => Err(Error::ValidationError { errors }),
-    }
-}
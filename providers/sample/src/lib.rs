@@ -10,8 +10,7 @@ pub const CUSTOM_DATA_SHOWCASE_QUERY_TYPE: &str = "x-showcase-custom";
 
 pub const SHOWCASE_MIME_TYPE: &str = "application/vnd.fiberplane.providers.sample.showcase";
 
-static COMMIT_HASH: &str = env!("VERGEN_GIT_SHA");
-static BUILD_TIMESTAMP: &str = env!("VERGEN_BUILD_TIMESTAMP");
+const BUILD_INFO: BuildInfo = pdk_build_info!();
 
 /// This example shows how to define a struct and let the PDK generate a config
 /// schema for it. This schema is used by Fiberplane Studio to render the
@@ -25,21 +24,61 @@ struct SampleConfig {
     #[pdk(label = "Your API endpoint", placeholder = "Please specify a URL")]
     pub endpoint: String,
 
-    #[pdk(label = "Number of retries if a request fails", max = 10)]
+    #[pdk(label = "Number of retries if a request fails", max = 10, default = 3)]
+    #[serde(default = "default_num_retries")]
     pub num_retries: u8,
 
     #[pdk(
         select,
-        option = "eu-central-1",
-        option = "us-east-1",
-        option = "us-east-2",
         placeholder = "Select an environment",
         label = "Environment to query"
     )]
-    pub environment: String,
+    pub environment: Environment,
 
     #[pdk(label = "I accept the Terms of Use", checked_by_default)]
     pub accept: bool,
+
+    /// The PDK applies fields of the `LogLevel` type when the config is
+    /// parsed.
+    #[pdk(label = "Log level", select)]
+    #[serde(default)]
+    pub log_level: LogLevel,
+}
+
+impl SampleConfig {
+    /// Returns the policy for retrying failed requests to the API endpoint.
+    ///
+    /// The showcase doesn't send any requests, but this is how the configured
+    /// number of retries would be honored, for instance by passing the policy
+    /// to `HttpClient::builder()`.
+    #[allow(dead_code)]
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::default().with_max_attempts(u32::from(self.num_retries) + 1)
+    }
+}
+
+/// Fields with a default value are not required. Note that Serde needs to know
+/// about the default too, so the value is specified in both places.
+fn default_num_retries() -> u8 {
+    3
+}
+
+/// This example shows how to define an enum of which the variants are presented
+/// as the options of a select field. The option values follow the enum's Serde
+/// representation, so the generated `parse()` methods directly yield a variant.
+#[derive(Clone, Copy, Deserialize, SelectOptions, Serialize)]
+enum Environment {
+    #[pdk(label = "EU (Frankfurt)")]
+    #[serde(rename = "eu-central-1")]
+    EuCentral1,
+
+    #[pdk(label = "US East (N. Virginia)")]
+    #[serde(rename = "us-east-1")]
+    UsEast1,
+
+    #[pdk(label = "US East (Ohio)")]
+    #[serde(rename = "us-east-2")]
+    UsEast2,
 }
 
 /// This example shows how to define a struct and let the PDK generate a query
@@ -65,19 +104,17 @@ struct ShowcaseQueryData {
 
     #[pdk(
         select,
-        option = "eu-central-1",
-        option = "us-east-1",
-        option = "us-east-2",
         placeholder = "Select an environment",
         label = "Environment to query"
     )]
-    pub environment: String,
+    pub environment: Environment,
 }
 
 /// This type shows how we can conveniently generate custom data using the
-/// `ProviderData` derive macro.
+/// `ProviderData` derive macro. Blobs are encoded using MessagePack by
+/// default, but we opt for JSON here so the data is easy to inspect.
 #[derive(Deserialize, ProviderData, Serialize)]
-#[pdk(mime_type = SHOWCASE_MIME_TYPE)]
+#[pdk(mime_type = SHOWCASE_MIME_TYPE, encoding = json)]
 struct ShowcaseCustomData {
     config: SampleConfig,
     query_data: ShowcaseQueryData,
@@ -86,25 +123,26 @@ struct ShowcaseCustomData {
 pdk_query_types! {
     CELLS_SHOWCASE_QUERY_TYPE => {
         label: "Showcase query (cells)",
-        handler: query_cells_showcase(ShowcaseQueryData, SampleConfig),
-        supported_mime_types: [CELLS_MIME_TYPE]
+        handler: query_cells_showcase(ShowcaseQueryData, SampleConfig) -> Cells
     },
     CUSTOM_DATA_SHOWCASE_QUERY_TYPE => {
         label: "Showcase query (custom data)",
-        handler: query_custom_data_showcase(ShowcaseQueryData, SampleConfig),
-        supported_mime_types: [SHOWCASE_MIME_TYPE]
+        handler: query_custom_data_showcase(ShowcaseQueryData, SampleConfig) -> ShowcaseCustomData,
+        cells: create_showcase_cells(ShowcaseCustomData)
     },
     STATUS_QUERY_TYPE => {
-        handler: check_status(),
-        supported_mime_types: [STATUS_MIME_TYPE]
+        handler: check_status() -> StatusReport
     }
 }
 
 /// Creates cells reflecting the data we entered in the original form.
 ///
 /// In this example, `invoke2()` returned a Blob with data encoded in a custom
-/// format (indicated using the `SHOWCASE_JSON_MIME_TYPE`), and then
-/// `create_cells()` is invoked with that Blob to create the cells.
+/// format (indicated using the `SHOWCASE_MIME_TYPE`), and then
+/// `create_cells()` is invoked with that Blob to create the cells. Because the
+/// handler is registered using `cells:` in `pdk_query_types!`, the
+/// `create_cells()` function is generated for us, and the Blob is parsed into
+/// the `ShowcaseCustomData` type the handler expects.
 ///
 /// Note that if you only intend to create cells and no longer have any use for
 /// the data from which the cells are created, this process can be simplified:
@@ -113,10 +151,7 @@ pdk_query_types! {
 /// and specify the `CELLS_MIME_TYPE` with a `+json` or `+msgpack` suffix,
 /// respectively. In this scenario, `create_cells()` doesn't need to be
 /// implemented at all.
-#[pdk_export]
-fn create_cells(query_type: String, response: Blob) -> Result<Vec<Cell>> {
-    log(format!("Creating cells for query type: {query_type}"));
-
+fn create_showcase_cells(data: ShowcaseCustomData) -> Result<Vec<Cell>> {
     let ShowcaseCustomData {
         config: _,
         query_data:
@@ -127,7 +162,7 @@ fn create_cells(query_type: String, response: Blob) -> Result<Vec<Cell>> {
                 tags,
                 environment,
             },
-    } = ShowcaseCustomData::parse_blob(response)?;
+    } = data;
 
     Ok(vec![Cell::Text(
         TextCell::builder()
@@ -137,8 +172,9 @@ fn create_cells(query_type: String, response: Blob) -> Result<Vec<Cell>> {
             Your time range: {from} - {to}\n\
             Live mode was {live}\n\
             Provided tags: {:?}\n\
-            Environment {environment}",
-                tags.split('\n').collect::<Vec<_>>()
+            Environment: {}",
+                tags.split('\n').collect::<Vec<_>>(),
+                environment.label()
             ))
             .formatting(Formatting::default())
             .build(),
@@ -150,8 +186,11 @@ fn create_cells(query_type: String, response: Blob) -> Result<Vec<Cell>> {
 /// The Fiberplane Proxy server regularly submits status checks on its providers
 /// to determine whether they can still reach whatever service they are
 /// connected to. This is done by submitting a request with a query type of
-/// `STATUS_QUERY_TYPE`. A response of "ok" with the `STATUS_MIME_TYPE`
-/// indicates the provider is still available.
+/// `STATUS_QUERY_TYPE`. A response with the `STATUS_MIME_TYPE` indicates the
+/// provider is still available. The `StatusReport` includes the build
+/// information of the provider, as well as the version of the service it is
+/// connected to, if known. Providers that connect to a service can use
+/// `BuildInfo::check()` to determine both.
 ///
 /// If the provider is not available, an `Error` should be returned.
 ///
@@ -159,34 +198,36 @@ fn create_cells(query_type: String, response: Blob) -> Result<Vec<Cell>> {
 /// `get_supported_query_types()`. If the query type is omitted there, it means
 /// the provider doesn't support health checks, and the provider is assumed to
 /// be always available.
-fn check_status() -> Result<Blob> {
-    ProviderStatus::builder()
-        .status(Ok(()))
-        .version(COMMIT_HASH.to_owned())
-        .built_at(BUILD_TIMESTAMP.to_owned())
-        .build()
-        .to_blob()
+fn check_status() -> Result<StatusReport> {
+    Ok(BUILD_INFO.status())
 }
 
 /// This showcase shows how to return cells directly, without the need for
-/// returning custom data first. In this case, we directly encode the
-/// `Vec<Cell>` data using a JSON encoding.
+/// returning custom data first. In this case, we directly return the
+/// `Vec<Cell>` data wrapped in `Cells`.
 ///
-/// In many cases, this also allows you to omit implementing `create_cells()`
-/// entirely. But for this provider, we still need to implement it to support
-/// the custom data showcase.
-fn query_cells_showcase(query_data: ShowcaseQueryData, config: SampleConfig) -> Result<Blob> {
-    let response = query_custom_data_showcase(query_data, config)?;
-    let cells = create_cells(CELLS_SHOWCASE_QUERY_TYPE.to_owned(), response)?;
-
-    Cells(cells).to_blob()
+/// In many cases, this also allows you to omit the `cells` handler entirely.
+/// But for this provider, we still need one to support the custom data
+/// showcase.
+fn query_cells_showcase(query_data: ShowcaseQueryData, config: SampleConfig) -> Result<Cells> {
+    let data = query_custom_data_showcase(query_data, config)?;
+    let cells = create_showcase_cells(data)?;
+
+    Ok(Cells(cells))
 }
 
-/// For this showcase, we simply re-encode the query data, so that we can
+/// For this showcase, we simply return the query data, so that we can
 /// conveniently use it for other purposes again. In a real-world scenario,
 /// this is where we could perform some HTTP request and use the response to
 /// either generate a custom response, or to directly generate notebook cells
 /// using the `CELLS_MIME_TYPE` format (see the cells showcase).
-fn query_custom_data_showcase(query_data: ShowcaseQueryData, config: SampleConfig) -> Result<Blob> {
-    ShowcaseCustomData { config, query_data }.to_blob()
+///
+/// Because the return type of the handler is declared in `pdk_query_types!`,
+/// the macro encodes the data for us, and the supported MIME type of the query
+/// type is taken from the `ProviderData` implementation.
+fn query_custom_data_showcase(
+    query_data: ShowcaseQueryData,
+    config: SampleConfig,
+) -> Result<ShowcaseCustomData> {
+    Ok(ShowcaseCustomData { config, query_data })
 }
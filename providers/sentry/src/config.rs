@@ -1,9 +1,11 @@
+use fiberplane_pdk::prelude::Auth;
 use serde::Deserialize;
 
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SentryConfig {
-    pub token: String,
+    #[serde(flatten)]
+    pub auth: Auth,
 
     pub organization_slug: String,
 
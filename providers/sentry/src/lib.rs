@@ -3,27 +3,31 @@ mod percent_encode;
 mod sentry;
 
 use config::SentryConfig;
-use const_format::formatcp;
 use fiberplane_models::{
     notebooks::{TableColumnId, TableRow, TableRowId, TableRowValue},
     providers::{STATUS_MIME_TYPE, STATUS_QUERY_TYPE},
     utils::content_writer::ContentWriter,
 };
+use fiberplane_pdk::http::send;
+use fiberplane_pdk::pagination::{next_link, Page, Paginator};
 use fiberplane_pdk::prelude::*;
+use fiberplane_pdk::provider_data::to_blob_with_encoding;
+use fiberplane_pdk::tracing::debug;
 use percent_encode::encode_uri_component;
 pub use sentry::*;
 use std::{fmt::Write, str::FromStr};
 
 const OVERVIEW_QUERY_TYPE: &str = "x-issues-overview";
 
-const CELLS_MSGPACK_MIME_TYPE: &str = formatcp!("{CELLS_MIME_TYPE}+msgpack");
-
 const QUERY_PARAM_NAME: &str = "q";
 const TIME_RANGE_PARAM_NAME: &str = "time_range";
 const LIVE_PARAM_NAME: &str = "live";
 
-static COMMIT_HASH: &str = env!("VERGEN_GIT_SHA");
-static BUILD_TIMESTAMP: &str = env!("VERGEN_BUILD_TIMESTAMP");
+/// Sentry returns up to 100 issues per page.
+const PAGE_SIZE: usize = 100;
+const MAX_ISSUES: usize = 250;
+
+const BUILD_INFO: BuildInfo = pdk_build_info!();
 
 #[pdk_export]
 async fn get_supported_query_types(_config: ProviderConfig) -> Vec<SupportedQueryType> {
@@ -53,10 +57,14 @@ async fn get_supported_query_types(_config: ProviderConfig) -> Vec<SupportedQuer
 #[pdk_export]
 async fn invoke2(request: ProviderRequest) -> Result<Blob> {
     init_panic_hook();
-    log(format!(
-        "Sentry provider (commit: {}, built at: {}) invoked for query type \"{}\" and query data \"{:?}\"",
-        COMMIT_HASH, BUILD_TIMESTAMP, request.query_type, request.query_data
-    ));
+    init_logging();
+    debug!(
+        version = BUILD_INFO.version,
+        built_at = BUILD_INFO.built_at,
+        query_type = %request.query_type,
+        query_data = ?request.query_data,
+        "Sentry provider invoked"
+    );
 
     let config: SentryConfig =
         serde_json::from_value(request.config).map_err(|err| Error::Config {
@@ -65,14 +73,27 @@ async fn invoke2(request: ProviderRequest) -> Result<Blob> {
 
     match request.query_type.as_str() {
         OVERVIEW_QUERY_TYPE => query_issues_overview(request.query_data, config).await,
-        STATUS_QUERY_TYPE => Ok(Blob::builder()
-            .mime_type(STATUS_MIME_TYPE.to_owned())
-            .data("ok")
-            .build()),
+        STATUS_QUERY_TYPE => check_status(&config).await?.to_blob(),
         _ => Err(Error::UnsupportedRequest),
     }
 }
 
+/// Checks that the configured project can be accessed with the configured
+/// credentials. Sentry doesn't report its version, so none is included.
+async fn check_status(config: &SentryConfig) -> Result<StatusReport> {
+    BUILD_INFO
+        .check(async {
+            let url = format!(
+                "https://sentry.io/api/0/projects/{}/{}/",
+                encode_uri_component(&config.organization_slug),
+                encode_uri_component(&config.project_slug)
+            );
+            send(HttpRequest::get(url).with_headers(config.auth.to_headers())).await?;
+            Ok(None)
+        })
+        .await
+}
+
 async fn query_issues_overview(query_data: Blob, config: SentryConfig) -> Result<Blob> {
     let query = get_overview_query(&query_data)?;
     let url = format!(
@@ -82,18 +103,35 @@ async fn query_issues_overview(query_data: Blob, config: SentryConfig) -> Result
         encode_uri_component(&query)
     );
 
-    let response = make_http_request(HttpRequest::get(url).with_headers([(
-        "Authorization".to_owned(),
-        format!("Bearer {}", config.token),
-    )]))
-    .await?;
+    let issues = Paginator::new(PAGE_SIZE)
+        .with_limit(MAX_ISSUES)
+        .fetch(url, |url, _| fetch_issues(&config, url))
+        .await?
+        .items;
+
+    serialize_cells(create_overview_cells(issues)?)
+}
+
+/// Fetches a page of issues, using the cursor in the `Link` header to find the
+/// next page.
+async fn fetch_issues(config: &SentryConfig, url: String) -> Result<Page<SentryIssue, String>> {
+    // Sentry rate limits its API, so we retry requests that were rejected.
+    let response = RetryPolicy::default()
+        .send(HttpRequest::get(url).with_headers(config.auth.to_headers()))
+        .await?;
 
     let issues =
         serde_json::from_slice(response.body.as_ref()).map_err(|err| Error::Deserialization {
             message: format!("Cannot parse Sentry response: {err}"),
         })?;
 
-    serialize_cells(create_overview_cells(issues)?)
+    let next = response
+        .headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("link"))
+        .and_then(|(_, value)| next_link(value))
+        .map(|link| link.url);
+    Ok(Page::new(issues, next))
 }
 
 fn get_overview_query(query_data: &Blob) -> Result<String> {
@@ -116,13 +154,15 @@ fn get_overview_query(query_data: &Blob) -> Result<String> {
                     query.push(' ');
                 }
 
-                if let Some((from, to)) = value.split_once(' ') {
-                    write!(&mut query, "timestamp:>={from} timestamp:<{to}").map_err(|error| {
-                        Error::Data {
-                            message: format!("Could not write query string: {error}"),
-                        }
-                    })?;
-                }
+                let time_range: DateTimeRange = value.parse().map_err(|message| Error::Data {
+                    message: format!("Invalid time range: {message}"),
+                })?;
+                let (from, to) = time_range.format(TimestampFormat::Rfc3339);
+                write!(&mut query, "timestamp:>={from} timestamp:<{to}").map_err(|error| {
+                    Error::Data {
+                        message: format!("Could not write query string: {error}"),
+                    }
+                })?;
             }
             _ => {}
         }
@@ -204,10 +244,7 @@ fn create_overview_cells(issues: Vec<SentryIssue>) -> Result<Vec<Cell>> {
 }
 
 fn serialize_cells(cells: Vec<Cell>) -> Result<Blob> {
-    Ok(Blob::builder()
-        .data(rmp_serde::to_vec_named(&cells)?)
-        .mime_type(CELLS_MSGPACK_MIME_TYPE.to_owned())
-        .build())
+    to_blob_with_encoding(CELLS_MIME_TYPE, &cells, Encoding::MessagePack)
 }
 
 fn table_row_id_from_sentry_id(sentry_id: &str) -> Result<TableRowId> {